@@ -32,9 +32,7 @@ use odra::casper_types::{U256, U512};
 
 use magni_casper::magni::{Magni, MagniHostRef, MagniInitArgs};
 use magni_casper::tokens::{MCSPRToken, MCSPRTokenHostRef, MCSPRTokenInitArgs};
-
-const MOTES_PER_CSPR: u64 = 1_000_000_000;
-const MOTES_TO_WAD_FACTOR: u128 = 1_000_000_000; // 1e9
+use magni_casper::units::{self, MOTES_PER_CSPR};
 
 const DEFAULT_VALIDATOR_PUBLIC_KEY: &str =
     "012b365e09c5d75187b4abc25c4aa28109133bab6a256ef4abe24348073e590d80";
@@ -43,12 +41,6 @@ const DEFAULT_DEPLOY_GAS_TOKEN_MOTES: u64 = 450_000_000_000; // 450 CSPR
 const DEFAULT_DEPLOY_GAS_MAGNI_MOTES: u64 = 600_000_000_000; // 600 CSPR
 const DEFAULT_CALL_GAS_MOTES: u64 = 50_000_000_000; // 50 CSPR
 
-/// Convert motes (U512, 9 decimals) to wad (U256, 18 decimals)
-fn motes_to_wad(motes: U512) -> U256 {
-    let motes_u128 = motes.as_u128();
-    U256::from(motes_u128) * U256::from(MOTES_TO_WAD_FACTOR)
-}
-
 fn main() {
     println!("============================================");
     println!("  Magni V2 CSPR Vault — Livenet");
@@ -76,8 +68,9 @@ fn main() {
         .map(|v| v.trim() != "0" && !v.trim().is_empty())
         .unwrap_or(true);
 
-    let deposit_motes = U512::from(deposit_cspr) * U512::from(MOTES_PER_CSPR);
-    let borrow_wad = motes_to_wad(U512::from(borrow_cspr) * U512::from(MOTES_PER_CSPR));
+    let deposit_motes = units::cspr_to_motes(deposit_cspr);
+    let borrow_wad = units::motes_to_wad(units::cspr_to_motes(borrow_cspr))
+        .expect("MAGNI_DEMO_BORROW_CSPR out of range");
 
     println!("[INFO] Mode: {}", mode);
     println!("[INFO] Caller: {:?}", env.caller());
@@ -235,7 +228,7 @@ fn main() {
                 };
 
                 // Convert to motes (divide by 1e9)
-                let max_withdraw_motes = U512::from((max_withdraw_wad / U256::from(MOTES_TO_WAD_FACTOR)).as_u128());
+                let max_withdraw_motes = units::wad_to_motes(max_withdraw_wad);
 
                 // Withdraw half of max safe amount for demo
                 let withdraw_motes = max_withdraw_motes / 2;
@@ -21,10 +21,10 @@ use odra::host::{Deployer, HostRef, NoArgs};
 use odra::casper_types::U512;
 
 use magni_casper::staking_poc::{StakingPoC, StakingPoCHostRef};
+use magni_casper::units::MOTES_PER_CSPR;
 
 const DEFAULT_DEPLOY_GAS_MOTES: u64 = 300_000_000_000; // 300 CSPR
 const DEFAULT_CALL_GAS_MOTES: u64 = 100_000_000_000; // 100 CSPR
-const MOTES_PER_CSPR: u64 = 1_000_000_000; // 1 CSPR = 1e9 motes
 
 // Default testnet validator (from state_get_auction_info top validators)
 const DEFAULT_VALIDATOR: &str = "012b365e09c5d75187b4abc25c4aa28109133bab6a256ef4abe24348073e590d80";
@@ -4,12 +4,22 @@
 //! - tCSPR: Test token with faucet mint
 //! - mCSPR: Synthetic token mintable only by Magni
 //! - Magni: Core leverage staking contract with Styks oracle integration
+//! - swap_external: mCSPR/CSPR swap pool interface used by leverage helpers
+//! - units: motes <-> wad conversion shared by contracts, tests and binaries
+//! - mocks: misbehaving dependencies for tests (`test-support` feature only)
 
 #![cfg_attr(target_arch = "wasm32", no_std)]
 
 extern crate alloc;
 
+/// Crate version reported by every contract's `version()`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod units;
 pub mod tokens;
 pub mod styks_external;
+pub mod swap_external;
 pub mod magni;
 pub mod staking_poc;
+#[cfg(any(test, feature = "test-support"))]
+pub mod mocks;
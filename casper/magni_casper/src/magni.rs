@@ -2,7 +2,7 @@
 //!
 //! A collateral-debt vault for CSPR on Casper Network.
 //! - Users deposit CSPR as collateral (delegated to validators for staking)
-//! - Users can borrow mCSPR (debt token) against collateral up to 80% LTV
+//! - Users can borrow mCSPR (debt token) against collateral up to the LTV max (80% default)
 //! - Debt accrues interest at 2% APR (simple interest)
 //! - Withdrawals use 2-step process due to staking unbonding delay
 //!
@@ -14,35 +14,101 @@
 //! The leverage loop (mCSPR -> SwapPool -> CSPR -> re-deposit) is external.
 
 use odra::prelude::*;
+use odra::casper_types::account::AccountHash;
+use odra::casper_event_standard::EventInstance;
+use odra::casper_types::bytesrepr::{Bytes, ToBytes};
 use odra::casper_types::{AsymmetricType, PublicKey, U256, U512};
 use odra::ContractRef;
-use crate::tokens::MCSPRTokenContractRef;
+use crate::tokens::{Cep18TokenContractRef, MCSPRTokenContractRef};
+use crate::styks_external::{mock, StyksOracleContractRef};
+use crate::swap_external::SwapPoolContractRef;
+use crate::units;
 use alloc::vec::Vec;
 
 // ==========================================
 // Constants
 // ==========================================
 
-/// 1 CSPR = 1e9 motes
-const MOTES_PER_CSPR: u64 = 1_000_000_000;
-/// Conversion factor from motes (9 dec) to wad (18 dec) = 1e9
-const MOTES_TO_WAD_FACTOR: u128 = 1_000_000_000;
-/// 1 wad = 1e18
-const WAD: u128 = 1_000_000_000_000_000_000;
-
-/// LTV maximum = 80% = 8000 bps
-const LTV_MAX_BPS: u64 = 8000;
+/// Default LTV maximum = 80% = 8000 bps
+const DEFAULT_LTV_MAX_BPS: u64 = 8000;
 /// Basis points divisor
 const BPS_DIVISOR: u64 = 10_000;
 
-/// Interest rate = 2% APR = 200 bps
+/// Default base interest rate = 2% APR = 200 bps
 const INTEREST_RATE_BPS: u64 = 200;
+/// Default utilization kink of the two-slope rate model = 80%
+const DEFAULT_OPTIMAL_UTILIZATION_BPS: u64 = 8000;
 /// Seconds per year (365 days)
 const SECONDS_PER_YEAR: u64 = 31_536_000;
 
 /// Minimum delegation = 500 CSPR
 const MIN_DELEGATION_MOTES: u64 = 500_000_000_000;
 
+/// Default unbonding period = 14 hours (7 eras)
+const DEFAULT_UNBONDING_PERIOD_SECS: u64 = 50_400;
+/// Odra block time is reported in milliseconds
+const MILLIS_PER_SECOND: u64 = 1000;
+/// Block-time units per second, used to turn elapsed block time into years for accrual
+const TIME_UNIT_DIVISOR: u64 = 1000;
+// Accrual must read block time in the same unit as every other ms conversion here
+const _: () = assert!(TIME_UNIT_DIVISOR == MILLIS_PER_SECOND);
+
+/// Maximum share of accrued interest routed to the reserve = 50%
+const MAX_RESERVE_FACTOR_BPS: u64 = 5000;
+/// Maximum share of harvested staking rewards kept as a performance fee = 20%
+const MAX_PERFORMANCE_FEE_BPS: u64 = 2000;
+/// Maximum share of a liquidation bonus kept by the protocol = 50%
+const MAX_LIQUIDATION_RESERVE_SHARE_BPS: u64 = 5000;
+/// Maximum APR at zero utilization = 100%
+const MAX_BASE_RATE_BPS: u64 = 10_000;
+/// Maximum APR added up to optimal utilization = 100%
+const MAX_SLOPE1_BPS: u64 = 10_000;
+/// Maximum APR added from optimal to full utilization = 500%
+const MAX_SLOPE2_BPS: u64 = 50_000;
+
+/// Storage layout written by this build; on_upgrade migrates older layouts up to it
+pub const STORAGE_VERSION: u32 = 2;
+
+/// Default minimum deposit opening a vault: 1 CSPR in motes
+const DEFAULT_MIN_DEPOSIT_MOTES: u64 = 1_000_000_000;
+
+/// Operation bits used by operation_status / set_operations_paused
+pub const OP_DEPOSIT: u8 = 1 << 0;
+pub const OP_BORROW: u8 = 1 << 1;
+pub const OP_REPAY: u8 = 1 << 2;
+pub const OP_WITHDRAW: u8 = 1 << 3;
+const OP_ALL: u8 = OP_DEPOSIT | OP_BORROW | OP_REPAY | OP_WITHDRAW;
+
+/// Maximum number of registered validators
+const MAX_VALIDATORS: u32 = 10;
+/// Wait before an allowlisted validator may receive stake = 48 hours, long enough
+/// for depositors to see the change and exit through a full unbonding period
+pub const VALIDATOR_ALLOWLIST_DELAY_SECS: u64 = 172_800;
+/// Maximum number of users accrue_many and unhealthy_among process in one call
+const MAX_USER_BATCH: usize = 50;
+
+/// Shortfall below which apply_slashing ignores on-chain drift = 1 CSPR
+const SLASHING_DUST_MOTES: u64 = 1_000_000_000;
+/// Collateral below which finalize_withdraw treats a vault as empty = 1000 motes
+const VAULT_DUST_MOTES: u64 = 1_000;
+/// Debt below which finalize_withdraw treats a vault as empty (1000 motes' worth, in wad)
+const VAULT_DUST_WAD: u128 = 1_000_000_000_000;
+
+/// LTV at which a position becomes liquidatable (85%)
+const LIQUIDATION_THRESHOLD_BPS: u64 = 8500;
+/// Highest LTV max the owner may set; a fresh borrow must never be liquidatable
+const LTV_MAX_CEILING_BPS: u64 = LIQUIDATION_THRESHOLD_BPS;
+/// Extra collateral a liquidator receives on top of the debt repaid (5%)
+const LIQUIDATION_BONUS_BPS: u64 = 500;
+/// Default wait between a vault becoming liquidatable and liquidation = 1 hour
+const DEFAULT_LIQUIDATION_GRACE_SECS: u64 = 3600;
+/// Default largest share of a vault's debt one liquidate call may repay (50%)
+const DEFAULT_CLOSE_FACTOR_BPS: u64 = 5000;
+/// Default debt left after a close-factor repay below which full liquidation is allowed
+const DEFAULT_LIQUIDATION_DUST_WAD: u128 = units::WAD;
+/// Default bucket width for borrow/repay flow counters = 1 day
+const DEFAULT_EPOCH_LENGTH_SECS: u64 = 86_400;
+
 // ==========================================
 // Events
 // ==========================================
@@ -56,6 +122,13 @@ pub mod events {
         pub user: Address,
         pub amount_motes: U512,
         pub new_collateral_motes: U512,
+        pub seq: u64,
+    }
+
+    #[odra::event]
+    pub struct ReferralRecorded {
+        pub user: Address,
+        pub referrer: Address,
     }
 
     #[odra::event]
@@ -63,25 +136,43 @@ pub mod events {
         pub user: Address,
         pub amount_wad: U256,
         pub new_debt_wad: U256,
+        pub seq: u64,
     }
 
+    /// `allowance_*` is the user's mCSPR allowance to the vault around the repayment;
+    /// the difference is what burn_from spent (equal when the vault's own
+    /// mCSPR was used, as in deleverage_once)
     #[odra::event]
     pub struct Repaid {
         pub user: Address,
         pub amount_wad: U256,
         pub new_debt_wad: U256,
+        pub allowance_before: U256,
+        pub allowance_after: U256,
+        pub seq: u64,
+    }
+
+    #[odra::event]
+    pub struct Deleveraged {
+        pub user: Address,
+        pub collateral_motes: U512,
+        pub repaid_wad: U256,
+        pub refunded_wad: U256,
     }
 
     #[odra::event]
     pub struct WithdrawRequested {
         pub user: Address,
         pub amount_motes: U512,
+        pub seq: u64,
     }
 
     #[odra::event]
     pub struct WithdrawFinalized {
         pub user: Address,
+        pub recipient: Address,
         pub amount_motes: U512,
+        pub seq: u64,
     }
 
     #[odra::event]
@@ -94,13 +185,142 @@ pub mod events {
         pub amount_motes: U512,
     }
 
+    #[odra::event]
+    pub struct UndelegatedSwept {
+        pub amount_motes: U512,
+        pub total_delegated_motes: U512,
+    }
+
+    #[odra::event]
+    pub struct SlashingApplied {
+        pub loss_motes: U512,
+        pub collateral_index: U512,
+    }
+
     #[odra::event]
     pub struct InterestAccrued {
         pub user: Address,
         pub interest_wad: U256,
+        pub reserve_wad: U256,
+        pub new_debt_wad: U256,
+        pub seq: u64,
+    }
+
+    #[odra::event]
+    pub struct BecameLiquidatable {
+        pub user: Address,
+        pub ltv_bps: u64,
+    }
+
+    /// `seized_motes` is taken from the user; `reserve_motes` of it goes to the
+    /// liquidation reserve and the rest to the liquidator.
+    #[odra::event]
+    pub struct Liquidated {
+        pub user: Address,
+        pub liquidator: Address,
+        pub repaid_wad: U256,
+        pub seized_motes: U512,
+        pub reserve_motes: U512,
+    }
+
+    #[odra::event]
+    pub struct ReserveWithdrawn {
+        pub to: Address,
+        pub amount_wad: U256,
+        pub remaining_wad: U256,
+    }
+
+    #[odra::event]
+    pub struct FeeRecipientChanged {
+        pub old_recipient: Option<Address>,
+        pub new_recipient: Address,
+    }
+
+    #[odra::event]
+    pub struct TokensRescued {
+        pub token: Address,
+        pub to: Address,
+        pub amount: U256,
+    }
+
+    /// Emitted by current_price; `source` is PRICE_SOURCE_ORACLE or PRICE_SOURCE_MOCK
+    #[odra::event]
+    pub struct PriceObserved {
+        pub price_wad: U256,
+        pub source: u8,
+    }
+
+    /// Emitted by refresh_price when the oracle moved more than max_price_move_bps
+    /// from the last accepted price; borrowing is paused until the owner re-enables it
+    #[odra::event]
+    pub struct CircuitBreakerTripped {
+        pub last_good_price_wad: U256,
+        pub price_wad: U256,
+        pub move_bps: u64,
+    }
+
+    #[odra::event]
+    pub struct RepaidWithTcspr {
+        pub user: Address,
+        pub tcspr: Address,
+        pub amount_wad: U256,
         pub new_debt_wad: U256,
     }
 
+    #[odra::event]
+    pub struct CsprRescued {
+        pub to: Address,
+        pub amount_motes: U512,
+    }
+
+    #[odra::event]
+    pub struct RewardsHarvested {
+        pub rewards_motes: U512,
+        pub fee_motes: U512,
+        pub collateral_index: U512,
+    }
+
+    #[odra::event]
+    pub struct PerformanceFeeClaimed {
+        pub to: Address,
+        pub amount_motes: U512,
+    }
+
+    #[odra::event]
+    pub struct LiquidationReserveClaimed {
+        pub to: Address,
+        pub amount_motes: U512,
+    }
+
+    #[odra::event]
+    pub struct ValidatorAdded {
+        pub key: String,
+    }
+
+    #[odra::event]
+    pub struct ValidatorRemoved {
+        pub key: String,
+    }
+
+    #[odra::event]
+    pub struct ValidatorAllowlisted {
+        pub key: String,
+        /// Block time (ms) from which the key may be registered or made primary
+        pub allowed_from: u64,
+    }
+
+    #[odra::event]
+    pub struct ValidatorDisallowed {
+        pub key: String,
+    }
+
+    #[odra::event]
+    pub struct ValidatorChanged {
+        pub old_key: String,
+        pub new_key: String,
+        pub by: Address,
+    }
+
     #[odra::event]
     pub struct Paused {
         pub by: Address,
@@ -110,6 +330,96 @@ pub mod events {
     pub struct Unpaused {
         pub by: Address,
     }
+
+    #[odra::event]
+    pub struct OperationsPauseChanged {
+        pub paused_ops: u8,
+        pub by: Address,
+    }
+
+    #[odra::event]
+    pub struct PositionMigrated {
+        pub user: Address,
+        pub collateral_motes: U512,
+        pub debt_wad: U256,
+    }
+
+    #[odra::event]
+    pub struct MigrationFinalized {
+        pub by: Address,
+    }
+
+    #[odra::event]
+    pub struct VaultRetired {
+        pub by: Address,
+    }
+
+    #[odra::event]
+    pub struct StorageUpgraded {
+        pub from_version: u32,
+        pub to_version: u32,
+    }
+}
+
+/// `value * bps / BPS_DIVISOR`, dividing first so the product can't overflow U256.
+/// Exact: the remainder term restores what the early division dropped.
+fn apply_bps(value: U256, bps: u64, round_up: bool) -> U256 {
+    let divisor = U256::from(BPS_DIVISOR);
+    let bps = U256::from(bps);
+    let rem = value % divisor * bps;
+    let mut result = (value / divisor).saturating_mul(bps).saturating_add(rem / divisor);
+    if round_up && !(rem % divisor).is_zero() {
+        result = result.saturating_add(U256::one());
+    }
+    result
+}
+
+/// `value * BPS_DIVISOR / bps` rounded down, saturating at U256::MAX
+fn div_bps(value: U256, bps: u64) -> U256 {
+    let divisor = U256::from(BPS_DIVISOR);
+    let bps = U256::from(bps);
+    (value / bps).saturating_mul(divisor).saturating_add(value % bps * divisor / bps)
+}
+
+/// `numerator / denominator` in basis points, clamped to u64, without forming
+/// `numerator * BPS_DIVISOR`. When even the remainder can't be scaled, the
+/// fractional part uses `denominator / BPS_DIVISOR` and may round up by a bp.
+fn ratio_bps(numerator: U256, denominator: U256) -> u64 {
+    let divisor = U256::from(BPS_DIVISOR);
+    let rem = numerator % denominator;
+    let fraction = match rem.checked_mul(divisor) {
+        Some(scaled) => scaled / denominator,
+        None => rem / (denominator / divisor),
+    };
+    saturating_u64((numerator / denominator).saturating_mul(divisor).saturating_add(fraction))
+}
+
+/// collateral_index after writing `haircut` motes off `total_collateral`, or None when
+/// the haircut would take the index to zero. A zero index can never recover: every
+/// later deposit would be stored against it and read back as zero collateral.
+pub fn slashed_collateral_index(
+    index: U512,
+    total_collateral: U512,
+    haircut: U512,
+) -> Option<U512> {
+    if total_collateral.is_zero() || haircut > total_collateral {
+        return None;
+    }
+    let new_index = index * (total_collateral - haircut) / total_collateral;
+    if new_index.is_zero() {
+        None
+    } else {
+        Some(new_index)
+    }
+}
+
+/// Narrow a U256 ratio to u64, clamping at u64::MAX instead of overflowing
+fn saturating_u64(value: U256) -> u64 {
+    if value > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        value.as_u64()
+    }
 }
 
 // ==========================================
@@ -126,6 +436,14 @@ pub enum VaultStatus {
     Withdrawing = 2,
 }
 
+/// PositionInfo.status returned by simulate_withdraw when the amount exceeded collateral
+pub const STATUS_CLAMPED: u8 = 3;
+
+/// PriceObserved.source: built-in mock price, no oracle configured
+pub const PRICE_SOURCE_MOCK: u8 = 0;
+/// PriceObserved.source: read from the configured Styks oracle
+pub const PRICE_SOURCE_ORACLE: u8 = 1;
+
 /// Position info returned by get_position
 #[odra::odra_type]
 pub struct PositionInfo {
@@ -138,6 +456,69 @@ pub struct PositionInfo {
     pub status: u8,
 }
 
+/// Wallet snapshot returned by get_account_data: the user's mCSPR balance, their
+/// mCSPR allowance to the vault and their position, as get_position reports it
+#[odra::odra_type]
+pub struct AccountData {
+    pub mcspr_balance_wad: U256,
+    pub vault_allowance_wad: U256,
+    pub position: PositionInfo,
+}
+
+/// Contract CSPR split returned by balance_breakdown.
+/// pending_to_delegate_motes is the part of liquid_motes queued for delegation.
+#[odra::odra_type]
+pub struct BalanceBreakdown {
+    pub liquid_motes: U512,
+    pub delegated_motes: U512,
+    pub pending_to_delegate_motes: U512,
+}
+
+/// Per-user borrow/repay activity returned by user_stats
+#[odra::odra_type]
+pub struct UserStats {
+    pub borrow_count: u64,
+    pub repay_count: u64,
+    pub lifetime_borrowed_wad: U256,
+    pub lifetime_repaid_wad: U256,
+}
+
+/// Tracked vs on-chain stake returned by delegation_drift.
+/// Drift is reported as a magnitude plus direction rather than a signed integer:
+/// actual_above_tracked means the chain holds more (e.g. unharvested rewards),
+/// otherwise the chain holds less (e.g. slashing).
+#[odra::odra_type]
+pub struct DelegationDrift {
+    pub tracked_motes: U512,
+    pub actual_motes: U512,
+    pub drift_motes: U512,
+    pub actual_above_tracked: bool,
+}
+
+/// Vault configuration returned by get_config
+#[odra::odra_type]
+pub struct VaultConfig {
+    pub mcspr: Option<Address>,
+    pub validator_public_key: String,
+    pub ltv_max_bps: u64,
+    pub interest_rate_bps: u64,
+    pub base_rate_bps: u64,
+    pub slope1_bps: u64,
+    pub slope2_bps: u64,
+    pub optimal_utilization_bps: u64,
+    pub liquidation_threshold_bps: u64,
+    pub min_delegation_motes: U512,
+    pub min_deposit_motes: U512,
+    pub reserve_factor_bps: u64,
+    pub fee_recipient: Option<Address>,
+    pub auto_delegate: bool,
+    pub unbonding_period_secs: u64,
+    pub min_delegate_interval_secs: u64,
+    pub paused: bool,
+    pub price_oracle: Option<Address>,
+    pub price_feed_id: String,
+}
+
 // ==========================================
 // Errors
 // ==========================================
@@ -160,6 +541,42 @@ pub enum VaultError {
     ZeroAmount = 14,
     Overflow = 15,
     InsufficientLiquidBalance = 16,
+    InsufficientReserve = 17,
+    InvalidConfig = 18,
+    SupplyInvariantViolated = 19,
+    PriceUnavailable = 20,
+    OperationPaused = 21,
+    DelegationTooSoon = 22,
+    ProtectedToken = 23,
+    InsufficientSurplus = 24,
+    SelfReferral = 25,
+    TooManyValidators = 26,
+    ValidatorAlreadyRegistered = 27,
+    ValidatorNotFound = 28,
+    ValidatorHasStake = 29,
+    SlippageExceeded = 30,
+    NotLiquidatable = 31,
+    GracePeriodActive = 32,
+    AlreadyInitialized = 33,
+    MigrationFinalized = 34,
+    StalePrice = 35,
+    CloseFactorExceeded = 36,
+    StorageVersionCurrent = 37,
+    BelowMinDelegation = 38,
+    ExceedsPendingDelegation = 39,
+    ValidatorStakeCapExceeded = 40,
+    BatchTooLarge = 41,
+    HealthFactorTooLow = 42,
+    TcsprRepayDisabled = 43,
+    InsufficientProtocolMcspr = 44,
+    Retired = 45,
+    ValidatorNotAllowlisted = 46,
+    McsprNotConfigured = 47,
+    ExceedsValidatorStake = 48,
+    BorrowCooldownActive = 49,
+    Reentrant = 50,
+    SlashingExceedsCollateral = 51,
+    PriceMoveExceeded = 52,
 }
 
 // ==========================================
@@ -168,39 +585,146 @@ pub enum VaultError {
 
 #[odra::module(events = [
     events::Deposited,
+    events::ReferralRecorded,
     events::Borrowed,
     events::Repaid,
+    events::Deleveraged,
     events::WithdrawRequested,
     events::WithdrawFinalized,
     events::DelegationBatched,
     events::UndelegationRequested,
+    events::UndelegatedSwept,
+    events::SlashingApplied,
     events::InterestAccrued,
+    events::BecameLiquidatable,
+    events::Liquidated,
+    events::ReserveWithdrawn,
+    events::FeeRecipientChanged,
+    events::TokensRescued,
+    events::RepaidWithTcspr,
+    events::CircuitBreakerTripped,
+    events::PriceObserved,
+    events::CsprRescued,
+    events::RewardsHarvested,
+    events::PerformanceFeeClaimed,
+    events::LiquidationReserveClaimed,
+    events::ValidatorChanged,
+    events::ValidatorAdded,
+    events::ValidatorRemoved,
+    events::ValidatorAllowlisted,
+    events::ValidatorDisallowed,
     events::Paused,
-    events::Unpaused
+    events::Unpaused,
+    events::OperationsPauseChanged,
+    events::PositionMigrated,
+    events::MigrationFinalized,
+    events::VaultRetired,
+    events::StorageUpgraded
 ])]
 pub struct Magni {
     // Token references
     mcspr: Var<Address>,
 
     // Staking config
-    validator_public_key: Var<String>,
+    validator_public_key: Var<String>,       // Validator receiving new delegations
+    validators: Mapping<u32, String>,        // Registered validators; undelegation order
+    validator_count: Var<u32>,
+    validator_bonded: Mapping<String, U512>, // Delegated and not yet requested for undelegation
+    validator_allowlist: Mapping<String, bool>, // Keys the owner may register or make primary
+    validator_allowed_from: Mapping<String, u64>, // When an allowlist entry takes effect (ms)
+    max_validator_stake_bps: Var<u64>,       // Largest share of bonded stake on one validator
+    min_borrow_health_factor: Var<u64>,      // Health factor a borrow must leave (10000 = max LTV)
 
     // Per-user vault state
-    collateral: Mapping<Address, U512>,      // User's collateral in motes
+    collateral: Mapping<Address, U512>,      // User's collateral in motes at user_collateral_index
+    user_collateral_index: Mapping<Address, U512>, // collateral_index when collateral was written
     debt_principal: Mapping<Address, U256>,   // User's debt in wad (18 dec)
+    borrowed_principal: Mapping<Address, U256>, // Part of debt that was borrowed, not accrued
     last_accrual_ts: Mapping<Address, u64>,   // Last interest accrual timestamp
+    first_borrow_ts: Mapping<Address, u64>,   // Block time of the user's first borrow
+    last_borrow_ts: Mapping<Address, u64>,    // Block time of the user's latest borrow
     vault_status: Mapping<Address, VaultStatus>,
     pending_withdraw: Mapping<Address, U512>, // Pending withdrawal amount
+    withdraw_ready_ts: Mapping<Address, u64>, // Block time when pending withdrawal may finalize
+    withdraw_recipient: Mapping<Address, Address>, // Payee of the pending withdrawal
+    was_liquidatable: Mapping<Address, bool>, // Above liquidation threshold at last check
+    liquidatable_since: Mapping<Address, u64>, // When the current crossing was seen, 0 if healthy
+    referrer: Mapping<Address, Address>,      // First referrer credited with the user
+    borrow_count: Mapping<Address, u64>,      // Borrows made by the user
+    repay_count: Mapping<Address, u64>,       // Repayments made by the user (excl. liquidations)
+    lifetime_borrowed_wad: Mapping<Address, U256>, // Sum of all borrows
+    lifetime_repaid_wad: Mapping<Address, U256>,   // Sum of all repayments
+    vault_listed: Mapping<Address, bool>,     // Already appended to vault_owners
+    vault_owners: Mapping<u64, Address>,      // Append-only list of every vault ever opened
 
     // Global state
     total_collateral: Var<U512>,             // Sum of all collateral
+    collateral_index: Var<U512>,             // Cumulative slashing haircut, starts at WAD
     total_debt: Var<U256>,                    // Sum of all debt
+    vault_count: Var<u64>,                    // Length of vault_owners
     pending_to_delegate: Var<U512>,          // CSPR waiting to be delegated (batching)
-    total_delegated: Var<U512>,              // Total delegated to validator
+    total_delegated: Var<U512>,              // Total delegated to validator (incl. unswept undelegations)
+    undelegating_motes: Var<U512>,           // Undelegations requested but not yet swept
+    swept_liquid_motes: Var<U512>,           // Swept undelegations available to pay withdrawals
+    total_pending_withdraw: Var<U512>,       // Sum of all users' pending withdrawals
+    auto_delegate: Var<bool>,                // Delegate from deposit once batch reaches minimum
+    unbonding_period_secs: Var<u64>,         // Wait between withdraw request and finalize
+    min_deposit_motes: Var<U512>,            // Smallest deposit that may open a vault
+    last_delegate_ts: Var<u64>,              // Block time of the last delegation
+    min_delegate_interval_secs: Var<u64>,    // Required gap between delegations (0 = none)
+
+    // Risk parameters
+    ltv_max_bps: Var<u64>,                   // Highest LTV a borrow may reach
+    borrow_cooldown_secs: Var<u64>,          // Required gap between a user's borrows (0 = none)
+    liquidation_grace_secs: Var<u64>,        // Wait after crossing the threshold before liquidation
+    close_factor_bps: Var<u64>,              // Max share of debt repaid per liquidate call
+    liquidation_dust_wad: Var<U256>,         // Remaining debt small enough to close in one call
+
+    // Interest rate model (two-slope on utilization)
+    base_rate_bps: Var<u64>,                 // APR at zero utilization
+    slope1_bps: Var<u64>,                    // APR added from 0 to optimal utilization
+    slope2_bps: Var<u64>,                    // APR added from optimal to full utilization
+    optimal_utilization_bps: Var<u64>,       // Kink between slope1 and slope2
+    interest_free_secs: Var<u64>,            // Promotional window after a first borrow
+
+    // Protocol reserve
+    reserve_wad: Var<U256>,                  // Interest credited to the protocol (wad)
+    reserve_factor_bps: Var<u64>,            // Share of accrued interest sent to reserve
+    total_interest_accrued: Var<U256>,       // Interest ever added to borrower debt
+    total_interest_repaid: Var<U256>,        // Interest portion of all repayments
+    fee_recipient: Var<Address>,             // Destination of all protocol fees
+    performance_fee_bps: Var<u64>,           // Share of harvested rewards kept by the protocol
+    accrued_performance_fee: Var<U512>,      // Harvested fees not yet claimed (motes)
+    liquidation_reserve_share_bps: Var<u64>, // Share of each liquidation bonus kept
+    liquidation_reserve_motes: Var<U512>,    // Kept liquidation bonuses not yet claimed
+
+    // Price feed (mock price when unset)
+    price_oracle: Var<Address>,              // Styks oracle contract
+    price_feed_id: Var<String>,              // CSPR/USD feed id
+    max_price_staleness_secs: Var<u64>,      // Oldest acceptable oracle update (0 = unchecked)
+    max_price_move_bps: Var<u64>,            // Circuit-breaker threshold (0 = disabled)
+    last_good_price_wad: Var<U256>,          // Last oracle price accepted by refresh_price
 
     // Admin
     owner: Var<Address>,
     paused: Var<bool>,
+    paused_ops: Var<u8>,                     // OP_* bits paused individually
+    mcspr_rescue_enabled: Var<bool>,         // Override letting rescue_tokens move mCSPR
+    tcspr_repay_enabled: Var<bool>,          // Test-only 1:1 tCSPR repayment switch
+    migration_finalized: Var<bool>,          // Locks migrate_position for good
+    retired: Var<bool>,                      // Borrowing disabled for good (wind-down)
+    swap_locked: Var<bool>,                  // Set while (de)leverage waits on a swap pool
+
+    // Borrow/repay flow per epoch (epoch = block time / epoch length)
+    epoch_length_secs: Var<u64>,             // Bucket width for the flow counters
+    borrowed_in_epoch: Mapping<u64, U256>,   // mCSPR borrowed per epoch
+    repaid_in_epoch: Mapping<u64, U256>,     // mCSPR repaid per epoch
+
+    // Event ordering
+    event_seq: Var<u64>,                     // Bumped on every emitted event
+
+    initialized: Var<bool>,                  // Set once init has run
+    storage_version: Var<u32>,               // Layout version, 0 for pre-versioning deployments
 }
 
 #[odra::module]
@@ -211,14 +735,55 @@ impl Magni {
 
     /// Initialize the Magni V2 vault contract
     pub fn init(&mut self, mcspr: Address, validator_public_key: String) {
+        if self.initialized.get_or_default() {
+            self.env().revert(VaultError::AlreadyInitialized);
+        }
+        self.validate_validator_key(&validator_public_key);
         self.mcspr.set(mcspr);
+        self.validator_count.set(0);
+        if !validator_public_key.is_empty() {
+            self.allow_validator_now(validator_public_key.clone());
+            self.register_validator(validator_public_key.clone());
+        }
         self.validator_public_key.set(validator_public_key);
         self.total_collateral.set(U512::zero());
+        self.collateral_index.set(U512::from(units::WAD));
         self.total_debt.set(U256::zero());
         self.pending_to_delegate.set(U512::zero());
         self.total_delegated.set(U512::zero());
+        self.total_pending_withdraw.set(U512::zero());
+        self.undelegating_motes.set(U512::zero());
+        self.swept_liquid_motes.set(U512::zero());
+        self.auto_delegate.set(false);
+        self.unbonding_period_secs.set(DEFAULT_UNBONDING_PERIOD_SECS);
+        self.min_deposit_motes.set(U512::from(DEFAULT_MIN_DEPOSIT_MOTES));
+        self.min_delegate_interval_secs.set(0);
+        self.ltv_max_bps.set(DEFAULT_LTV_MAX_BPS);
+        self.liquidation_grace_secs.set(DEFAULT_LIQUIDATION_GRACE_SECS);
+        self.close_factor_bps.set(DEFAULT_CLOSE_FACTOR_BPS);
+        self.liquidation_dust_wad.set(U256::from(DEFAULT_LIQUIDATION_DUST_WAD));
+        self.max_validator_stake_bps.set(BPS_DIVISOR);
+        self.min_borrow_health_factor.set(BPS_DIVISOR);
+        self.base_rate_bps.set(INTEREST_RATE_BPS);
+        self.slope1_bps.set(0);
+        self.slope2_bps.set(0);
+        self.optimal_utilization_bps.set(DEFAULT_OPTIMAL_UTILIZATION_BPS);
+        self.interest_free_secs.set(0);
+        self.reserve_wad.set(U256::zero());
+        self.reserve_factor_bps.set(0);
+        self.total_interest_accrued.set(U256::zero());
+        self.total_interest_repaid.set(U256::zero());
         self.owner.set(self.env().caller());
+        self.fee_recipient.set(self.env().caller());
         self.paused.set(false);
+        self.paused_ops.set(0);
+        self.mcspr_rescue_enabled.set(false);
+        self.tcspr_repay_enabled.set(false);
+        self.migration_finalized.set(false);
+        self.retired.set(false);
+        self.event_seq.set(0);
+        self.storage_version.set(STORAGE_VERSION);
+        self.initialized.set(true);
     }
 
     // ==========================================
@@ -227,59 +792,118 @@ impl Magni {
 
     /// Deposit CSPR as collateral.
     /// Creates vault if none exists, otherwise adds to existing collateral.
+    /// Opening a vault requires at least min_deposit_motes; top-ups may be smaller.
+    /// Returns the resulting position, as get_position would.
     #[odra(payable)]
-    pub fn deposit(&mut self) {
-        self.require_not_paused();
+    pub fn deposit(&mut self) -> PositionInfo {
+        self.require_operation(OP_DEPOSIT);
         let caller = self.env().caller();
         let amount = self.env().attached_value();
 
         if amount == U512::zero() {
             self.env().revert(VaultError::ZeroAmount);
         }
+        let has_vault = self.vault_status.get(&caller).unwrap_or_default() != VaultStatus::None;
+        if !has_vault && amount < self.min_deposit_motes.get_or_default() {
+            self.env().revert(VaultError::BelowMinDeposit);
+        }
 
-        // Update user's collateral
-        let current = self.collateral.get(&caller).unwrap_or_default();
-        let new_collateral = current + amount;
-        self.collateral.set(&caller, new_collateral);
+        self.credit_collateral(caller, amount);
+        self.get_position(caller)
+    }
 
-        // Update global collateral
-        let total = self.total_collateral.get_or_default();
-        self.total_collateral.set(total + amount);
+    /// Alias for deposit - add more collateral to existing vault
+    #[odra(payable)]
+    pub fn add_collateral(&mut self) {
+        self.deposit();
+    }
 
-        // Set vault status to Active if not already
-        let status = self.vault_status.get(&caller).unwrap_or_default();
-        if status == VaultStatus::None {
-            self.vault_status.set(&caller, VaultStatus::Active);
-            self.last_accrual_ts.set(&caller, self.env().get_block_time());
+    /// Deposit CSPR crediting `referrer`. Only the first referrer of a user is kept.
+    #[odra(payable)]
+    pub fn deposit_with_referral(&mut self, referrer: Address) {
+        let caller = self.env().caller();
+        if referrer == caller {
+            self.env().revert(VaultError::SelfReferral);
         }
 
-        // Batch delegation
-        self.batch_delegate(amount);
+        self.deposit();
 
-        self.env().emit_event(events::Deposited {
-            user: caller,
-            amount_motes: amount,
-            new_collateral_motes: new_collateral,
-        });
+        if self.referrer.get(&caller).is_none() {
+            self.referrer.set(&caller, referrer);
+            self.emit(events::ReferralRecorded {
+                user: caller,
+                referrer,
+            });
+        }
     }
 
-    /// Alias for deposit - add more collateral to existing vault
+    /// Borrow mCSPR against collateral.
+    /// Reverts if resulting LTV > ltv_max_bps (80% by default).
+    /// Returns the resulting position.
+    pub fn borrow(&mut self, amount_wad: U256) -> PositionInfo {
+        let caller = self.env().caller();
+        self.borrow_for(caller, amount_wad, caller);
+        self.get_position(caller)
+    }
+
+    /// Open or grow a leveraged position in one call: deposit the attached CSPR, then
+    /// borrow `borrow_wad` mCSPR against the combined collateral. Same checks and
+    /// events as deposit followed by borrow; the whole call reverts if either fails.
     #[odra(payable)]
-    pub fn add_collateral(&mut self) {
+    pub fn deposit_and_borrow(&mut self, borrow_wad: U256) -> PositionInfo {
+        let caller = self.env().caller();
         self.deposit();
+        self.borrow_for(caller, borrow_wad, caller);
+        self.get_position(caller)
     }
 
-    /// Borrow mCSPR against collateral.
-    /// Reverts if resulting LTV > 80%
-    pub fn borrow(&mut self, amount_wad: U256) {
-        self.require_not_paused();
+    /// Borrow against the caller's collateral, minting the mCSPR to `recipient`.
+    /// The debt stays with the caller.
+    pub fn borrow_to(&mut self, amount_wad: U256, recipient: Address) {
+        let caller = self.env().caller();
+        self.borrow_for(caller, amount_wad, recipient);
+    }
+
+    /// One leverage hop in a single call: borrow `borrow_wad` mCSPR into `swap_pool`,
+    /// swap it for CSPR and add the proceeds to the caller's collateral.
+    /// Reverts if fewer than `min_cspr_out` motes come back. User entry points revert
+    /// with Reentrant while the pool call is in flight.
+    pub fn leverage_once(&mut self, borrow_wad: U256, swap_pool: Address, min_cspr_out: U512) {
+        self.require_operation(OP_DEPOSIT);
         let caller = self.env().caller();
 
-        if amount_wad == U256::zero() {
-            self.env().revert(VaultError::ZeroAmount);
+        self.borrow_for(caller, borrow_wad, swap_pool);
+
+        // Measure what actually arrived rather than trusting the pool's return value
+        let balance_before = self.env().self_balance();
+        let mut pool = SwapPoolContractRef::new(self.env().clone(), swap_pool);
+        self.swap_locked.set(true);
+        pool.swap_mcspr_for_cspr(borrow_wad, min_cspr_out);
+        self.swap_locked.set(false);
+        let received = self.env().self_balance().saturating_sub(balance_before);
+        if received < min_cspr_out || received == U512::zero() {
+            self.env().revert(VaultError::SlippageExceeded);
         }
 
-        // Check vault exists and is active
+        self.credit_collateral(caller, received);
+    }
+
+    /// Unwind leverage in a single call: swap `collateral_motes` of the caller's collateral
+    /// for mCSPR via `swap_pool` and repay debt with the proceeds. mCSPR beyond the debt is
+    /// sent to the caller. Only liquid (undelegated) CSPR can be used; the position must
+    /// stay within max LTV afterwards. Guarded against pool re-entry like leverage_once.
+    pub fn deleverage_once(
+        &mut self,
+        collateral_motes: U512,
+        swap_pool: Address,
+        min_mcspr_out: U256,
+    ) {
+        self.require_operation(OP_REPAY | OP_WITHDRAW);
+        let caller = self.env().caller();
+
+        if collateral_motes == U512::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
         let status = self.vault_status.get(&caller).unwrap_or_default();
         if status == VaultStatus::None {
             self.env().revert(VaultError::NoVault);
@@ -287,109 +911,216 @@ impl Magni {
         if status == VaultStatus::Withdrawing {
             self.env().revert(VaultError::WithdrawPending);
         }
+        let current_collateral = self.collateral_of(caller);
+        if collateral_motes > current_collateral {
+            self.env().revert(VaultError::InsufficientCollateral);
+        }
+        let available = self
+            .env()
+            .self_balance()
+            .saturating_sub(self.total_pending_withdraw.get_or_default());
+        if collateral_motes > available {
+            self.env().revert(VaultError::InsufficientLiquidBalance);
+        }
 
-        // Accrue interest first
         self.accrue_interest(caller);
 
-        // Calculate new debt
-        let current_debt = self.debt_principal.get(&caller).unwrap_or_default();
-        let new_debt = current_debt + amount_wad;
+        // Collateral leaves the vault before the external swap
+        let new_collateral = current_collateral - collateral_motes;
+        self.set_collateral(caller, new_collateral);
+        let total = self.total_collateral.get_or_default();
+        self.total_collateral.set(total.saturating_sub(collateral_motes));
+        let pending = self.pending_to_delegate.get_or_default();
+        self.pending_to_delegate.set(pending.saturating_sub(collateral_motes));
 
-        // Check LTV constraint
-        let collateral_motes = self.collateral.get(&caller).unwrap_or_default();
-        let collateral_wad = self.motes_to_wad(collateral_motes);
-        let max_debt = collateral_wad * U256::from(LTV_MAX_BPS) / U256::from(BPS_DIVISOR);
+        let mcspr_addr = self.mcspr_address();
+        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        let self_address = self.env().self_address();
 
+        // Measure what actually arrived rather than trusting the pool's return value
+        let balance_before = mcspr.balance_of(self_address);
+        let mut pool = SwapPoolContractRef::new(self.env().clone(), swap_pool);
+        self.swap_locked.set(true);
+        pool.with_tokens(collateral_motes).swap_cspr_for_mcspr(min_mcspr_out);
+        self.swap_locked.set(false);
+        let received = mcspr.balance_of(self_address).saturating_sub(balance_before);
+        if received < min_mcspr_out || received == U256::zero() {
+            self.env().revert(VaultError::SlippageExceeded);
+        }
+
+        let current_debt = self.debt_principal.get(&caller).unwrap_or_default();
+        let repaid = received.min(current_debt);
+        let refunded = received - repaid;
+        let new_debt = current_debt - repaid;
+
+        let max_debt = self.max_debt_for(self.motes_to_wad(new_collateral));
         if new_debt > max_debt {
             self.env().revert(VaultError::LtvExceeded);
         }
 
-        // Update debt
+        self.record_repayment(caller, current_debt, repaid);
+        if repaid > U256::zero() {
+            self.record_repay_stats(caller, repaid);
+        }
         self.debt_principal.set(&caller, new_debt);
-        let total = self.total_debt.get_or_default();
-        self.total_debt.set(total + amount_wad);
+        self.reduce_total_debt(repaid);
 
-        // Mint mCSPR to user
-        let mcspr_addr = self.mcspr.get().expect("mCSPR not set");
-        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
-        mcspr.mint(caller, amount_wad);
+        if repaid > U256::zero() {
+            mcspr.burn(self_address, repaid);
+        }
+        if refunded > U256::zero() {
+            mcspr.transfer(caller, refunded);
+        }
+        self.assert_supply_invariant();
 
-        self.env().emit_event(events::Borrowed {
+        // Paid from swap proceeds: the caller's allowance is untouched
+        let allowance = mcspr.allowance(caller, self_address);
+        let seq = self.next_event_seq();
+        self.env().emit_event(events::Repaid {
             user: caller,
-            amount_wad,
+            amount_wad: repaid,
             new_debt_wad: new_debt,
+            allowance_before: allowance,
+            allowance_after: allowance,
+            seq,
+        });
+        self.emit(events::Deleveraged {
+            user: caller,
+            collateral_motes,
+            repaid_wad: repaid,
+            refunded_wad: refunded,
         });
+        self.refresh_liquidatable(caller);
     }
 
     /// Repay mCSPR debt.
     /// Uses approve -> transfer_from -> burn pattern.
-    /// If amount > debt, only repays debt.
-    pub fn repay(&mut self, amount_wad: U256) {
-        self.require_not_paused();
+    /// If amount > debt, only repays debt. Returns the resulting position.
+    pub fn repay(&mut self, amount_wad: U256) -> PositionInfo {
+        self.require_operation(OP_REPAY);
         let caller = self.env().caller();
 
         if amount_wad == U256::zero() {
             self.env().revert(VaultError::ZeroAmount);
         }
 
-        // Check vault exists
+        self.repay_capped(caller, amount_wad);
+        self.get_position(caller)
+    }
+
+    /// Repay in a single transaction: `signature` is the caller's mCSPR permit
+    /// (signed with `public_key`) approving this contract for `amount_wad` until `deadline`.
+    /// The key is passed explicitly because an account hash can't be turned back into
+    /// the public key that verifies the signature; it must hash to the caller's account.
+    /// The permit grants exactly `amount_wad`, so a signature over any other amount
+    /// fails with InvalidSignature rather than leaving a smaller allowance.
+    pub fn repay_with_permit(
+        &mut self,
+        amount_wad: U256,
+        deadline: u64,
+        signature: Bytes,
+        public_key: PublicKey,
+    ) {
+        self.require_operation(OP_REPAY);
+        let caller = self.env().caller();
+
+        if amount_wad == U256::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if Address::from(public_key.to_account_hash()) != caller {
+            self.env().revert(VaultError::Unauthorized);
+        }
+
+        let mcspr_addr = self.mcspr_address();
+        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        mcspr.permit(public_key, self.env().self_address(), amount_wad, deadline, signature);
+
+        // settle_repayment re-checks the allowance before burning
+        self.repay_capped(caller, amount_wad);
+    }
+
+    /// Repay debt with tCSPR at a fixed 1:1 peg, for end-to-end scenarios without a
+    /// swap pool. Only available while tcspr_repay_enabled; `tcspr` is not validated,
+    /// so keep it disabled outside test deployments.
+    /// The tCSPR is pulled via allowance and held by the vault. Because the borrower's
+    /// mCSPR stays in circulation, the same amount of vault-held mCSPR is burned to keep
+    /// supply within debt + reserve; reverts InsufficientProtocolMcspr if there is too little.
+    pub fn repay_with_tcspr(&mut self, tcspr: Address, amount: U256) {
+        self.require_operation(OP_REPAY);
+        if !self.tcspr_repay_enabled.get_or_default() {
+            self.env().revert(VaultError::TcsprRepayDisabled);
+        }
+        let caller = self.env().caller();
+        if amount == U256::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        let mcspr_addr = self.mcspr_address();
+        if tcspr == mcspr_addr {
+            self.env().revert(VaultError::ProtectedToken);
+        }
+
         let status = self.vault_status.get(&caller).unwrap_or_default();
         if status == VaultStatus::None {
             self.env().revert(VaultError::NoVault);
         }
-
-        // Accrue interest first
         self.accrue_interest(caller);
-
-        // Get current debt and cap repay amount
         let current_debt = self.debt_principal.get(&caller).unwrap_or_default();
         if current_debt == U256::zero() {
             self.env().revert(VaultError::InsufficientDebt);
         }
+        let repay_amount = amount.min(current_debt);
 
-        let repay_amount = if amount_wad > current_debt {
-            current_debt
-        } else {
-            amount_wad
-        };
-
-        // Transfer mCSPR from user to this contract (requires prior approve)
-        let mcspr_addr = self.mcspr.get().expect("mCSPR not set");
         let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
         let self_address = self.env().self_address();
-
-        // Check allowance first
-        let allowance = mcspr.allowance(caller, self_address);
-        if allowance < repay_amount {
-            self.env().revert(VaultError::InsufficientAllowance);
+        if mcspr.balance_of(self_address) < repay_amount {
+            self.env().revert(VaultError::InsufficientProtocolMcspr);
         }
 
-        // Transfer from user to contract
-        mcspr.transfer_from(caller, self_address, repay_amount);
-
-        // Burn the received mCSPR
-        mcspr.burn(self_address, repay_amount);
-
-        // Update debt
         let new_debt = current_debt - repay_amount;
+        self.record_repayment(caller, current_debt, repay_amount);
+        self.record_repay_stats(caller, repay_amount);
         self.debt_principal.set(&caller, new_debt);
-        let total = self.total_debt.get_or_default();
-        if total >= repay_amount {
-            self.total_debt.set(total - repay_amount);
-        }
+        self.reduce_total_debt(repay_amount);
 
-        self.env().emit_event(events::Repaid {
+        let mut token = Cep18TokenContractRef::new(self.env().clone(), tcspr);
+        token.transfer_from(caller, self_address, repay_amount);
+        mcspr.burn(self_address, repay_amount);
+        self.assert_supply_invariant();
+
+        self.emit(events::RepaidWithTcspr {
             user: caller,
+            tcspr,
             amount_wad: repay_amount,
             new_debt_wad: new_debt,
         });
+        self.refresh_liquidatable(caller);
     }
 
-    /// Request withdrawal of collateral.
-    /// Reverts if resulting LTV > 80%.
-    /// Triggers undelegate if insufficient liquid balance.
-    pub fn request_withdraw(&mut self, amount_motes: U512) {
-        self.require_not_paused();
+    /// Whether repay_with_tcspr is enabled
+    pub fn tcspr_repay_enabled(&self) -> bool {
+        self.tcspr_repay_enabled.get_or_default()
+    }
+
+    /// Enable or disable repay_with_tcspr (owner only)
+    pub fn set_tcspr_repay_enabled(&mut self, enabled: bool) {
+        self.require_owner();
+        self.tcspr_repay_enabled.set(enabled);
+    }
+
+    /// Request withdrawal of collateral.
+    /// Reverts if resulting LTV > ltv_max_bps.
+    /// Triggers undelegate if insufficient liquid balance.
+    /// Returns the resulting position.
+    pub fn request_withdraw(&mut self, amount_motes: U512) -> PositionInfo {
+        let caller = self.env().caller();
+        self.request_withdraw_to(amount_motes, caller);
+        self.get_position(caller)
+    }
+
+    /// Request withdrawal of collateral, to be paid out to `recipient` on finalize.
+    /// Same checks as request_withdraw.
+    pub fn request_withdraw_to(&mut self, amount_motes: U512, recipient: Address) {
+        self.require_operation(OP_WITHDRAW);
         let caller = self.env().caller();
 
         if amount_motes == U512::zero() {
@@ -409,7 +1140,7 @@ impl Magni {
         self.accrue_interest(caller);
 
         // Check collateral sufficient
-        let current_collateral = self.collateral.get(&caller).unwrap_or_default();
+        let current_collateral = self.collateral_of(caller);
         if amount_motes > current_collateral {
             self.env().revert(VaultError::InsufficientCollateral);
         }
@@ -420,99 +1151,33 @@ impl Magni {
 
         if debt > U256::zero() {
             let remaining_wad = self.motes_to_wad(remaining_collateral);
-            let max_debt = remaining_wad * U256::from(LTV_MAX_BPS) / U256::from(BPS_DIVISOR);
+            let max_debt = self.max_debt_for(remaining_wad);
             if debt > max_debt {
                 self.env().revert(VaultError::LtvExceeded);
             }
         }
 
-        // Update collateral (reduce immediately)
-        self.collateral.set(&caller, remaining_collateral);
-        let total = self.total_collateral.get_or_default();
-        if total >= amount_motes {
-            self.total_collateral.set(total - amount_motes);
-        }
-
-        // Store pending withdrawal
-        self.pending_withdraw.set(&caller, amount_motes);
-        self.vault_status.set(&caller, VaultStatus::Withdrawing);
-
-        // Check if we need to undelegate
-        let liquid = self.env().self_balance();
-        if liquid < amount_motes {
-            // Need to undelegate
-            let delegated = self.total_delegated.get_or_default();
-            let undelegate_amount = amount_motes.min(delegated);
-
-            if undelegate_amount > U512::zero() {
-                let validator_key = self.validator_public_key.get_or_default();
-                if !validator_key.is_empty() {
-                    let validator_pk = self.parse_validator_key(&validator_key);
-                    self.env().undelegate(validator_pk, undelegate_amount);
-                    self.total_delegated.set(delegated - undelegate_amount);
-
-                    self.env().emit_event(events::UndelegationRequested {
-                        amount_motes: undelegate_amount,
-                    });
-                }
-            }
-        }
-
-        self.env().emit_event(events::WithdrawRequested {
-            user: caller,
-            amount_motes,
-        });
+        self.queue_withdraw(caller, amount_motes, recipient);
     }
 
     /// Finalize pending withdrawal after unbonding completes.
     pub fn finalize_withdraw(&mut self) {
-        self.require_not_paused();
+        self.require_operation(OP_WITHDRAW);
         let caller = self.env().caller();
+        self.finalize_for(caller);
+    }
 
-        // Check vault is in withdrawing state
-        let status = self.vault_status.get(&caller).unwrap_or_default();
-        if status != VaultStatus::Withdrawing {
-            self.env().revert(VaultError::NoWithdrawPending);
-        }
-
-        // Get pending amount
-        let pending = self.pending_withdraw.get(&caller).unwrap_or_default();
-        if pending == U512::zero() {
-            self.env().revert(VaultError::NoWithdrawPending);
-        }
-
-        // Check liquid balance
-        let liquid = self.env().self_balance();
-        if liquid < pending {
-            self.env().revert(VaultError::UnbondingNotComplete);
-        }
-
-        // Transfer CSPR to user
-        self.env().transfer_tokens(&caller, &pending);
-
-        // Clear pending state
-        self.pending_withdraw.set(&caller, U512::zero());
-
-        // Update vault status
-        let remaining_collateral = self.collateral.get(&caller).unwrap_or_default();
-        let remaining_debt = self.debt_principal.get(&caller).unwrap_or_default();
-
-        if remaining_collateral == U512::zero() && remaining_debt == U256::zero() {
-            self.vault_status.set(&caller, VaultStatus::None);
-        } else {
-            self.vault_status.set(&caller, VaultStatus::Active);
-        }
-
-        self.env().emit_event(events::WithdrawFinalized {
-            user: caller,
-            amount_motes: pending,
-        });
+    /// Finalize `user`'s ready withdrawal on their behalf, paying their recipient (owner only).
+    /// Same checks as finalize_withdraw; usable while withdrawals are paused.
+    pub fn force_finalize(&mut self, user: Address) {
+        self.require_owner();
+        self.finalize_for(user);
     }
 
     /// Repay all debt including accrued interest.
     /// Calculates exact debt at execution time to handle real-time interest.
     pub fn repay_all(&mut self) {
-        self.require_not_paused();
+        self.require_operation(OP_REPAY);
         let caller = self.env().caller();
 
         // Check vault exists
@@ -530,41 +1195,124 @@ impl Magni {
             self.env().revert(VaultError::InsufficientDebt);
         }
 
-        // Transfer mCSPR from user to this contract (requires prior approve)
-        let mcspr_addr = self.mcspr.get().expect("mCSPR not set");
-        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
-        let self_address = self.env().self_address();
+        // Allowance must be >= current debt
+        self.settle_repayment(caller, current_debt, current_debt);
+    }
 
-        // Check allowance - must be >= current debt
-        let allowance = mcspr.allowance(caller, self_address);
-        if allowance < current_debt {
-            self.env().revert(VaultError::InsufficientAllowance);
+    /// Accrue interest on any vault and record whether it is liquidatable (anyone).
+    /// Starts the liquidation grace period for a vault that has drifted over the threshold.
+    pub fn sync_position(&mut self, user: Address) {
+        if self.vault_status.get(&user).unwrap_or_default() == VaultStatus::None {
+            self.env().revert(VaultError::NoVault);
         }
+        self.accrue_interest(user);
+        self.refresh_liquidatable(user);
+    }
 
-        // Transfer from user to contract
-        mcspr.transfer_from(caller, self_address, current_debt);
+    /// sync_position for up to MAX_USER_BATCH users at once (anyone), so stored debt
+    /// and total_debt include all interest to date. Users without a vault are skipped.
+    pub fn accrue_many(&mut self, users: Vec<Address>) {
+        if users.len() > MAX_USER_BATCH {
+            self.env().revert(VaultError::BatchTooLarge);
+        }
+        for user in users {
+            if self.vault_status.get(&user).unwrap_or_default() == VaultStatus::None {
+                continue;
+            }
+            self.accrue_interest(user);
+            self.refresh_liquidatable(user);
+        }
+    }
 
-        // Burn the received mCSPR
-        mcspr.burn(self_address, current_debt);
+    /// Repay up to `amount_wad` of a liquidatable vault's debt from the caller's mCSPR
+    /// (requires prior approve) and take the equivalent collateral plus a 5% bonus into
+    /// the caller's own vault; liquidation_reserve_share_bps of the bonus goes to the
+    /// liquidation reserve instead. Reverts with GracePeriodActive until liquidation_grace_secs
+    /// have passed since the vault was first seen over the liquidation threshold, and with
+    /// CloseFactorExceeded if more than close_factor_bps of the debt is repaid at once
+    /// (unless what that share would leave behind is below liquidation_dust_wad).
+    pub fn liquidate(&mut self, user: Address, amount_wad: U256) {
+        self.require_not_paused();
+        let liquidator = self.env().caller();
 
-        // Update debt to zero
-        self.debt_principal.set(&caller, U256::zero());
-        let total = self.total_debt.get_or_default();
-        if total >= current_debt {
-            self.total_debt.set(total - current_debt);
+        if amount_wad == U256::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if self.vault_status.get(&user).unwrap_or_default() == VaultStatus::None {
+            self.env().revert(VaultError::NoVault);
         }
 
-        self.env().emit_event(events::Repaid {
-            user: caller,
-            amount_wad: current_debt,
-            new_debt_wad: U256::zero(),
+        self.accrue_interest(user);
+        self.refresh_liquidatable(user);
+        if !self.was_liquidatable.get(&user).unwrap_or_default() {
+            self.env().revert(VaultError::NotLiquidatable);
+        }
+        let since = self.liquidatable_since.get(&user).unwrap_or_default();
+        let grace_ms = self.liquidation_grace_secs().saturating_mul(MILLIS_PER_SECOND);
+        if self.env().get_block_time() < since.saturating_add(grace_ms) {
+            self.env().revert(VaultError::GracePeriodActive);
+        }
+
+        let current_debt = self.debt_principal.get(&user).unwrap_or_default();
+        let repaid = amount_wad.min(current_debt);
+        let max_repay =
+            current_debt * U256::from(self.close_factor_bps()) / U256::from(BPS_DIVISOR);
+        if repaid > max_repay && current_debt - max_repay >= self.liquidation_dust_wad() {
+            self.env().revert(VaultError::CloseFactorExceeded);
+        }
+        let seize_wad =
+            repaid * U256::from(BPS_DIVISOR + LIQUIDATION_BONUS_BPS) / U256::from(BPS_DIVISOR);
+        let user_collateral = self.collateral_of(user);
+        let seized = self.wad_to_motes(seize_wad).min(user_collateral);
+        let bonus = seized.saturating_sub(self.wad_to_motes(repaid));
+        let reserve_motes = bonus * U512::from(self.liquidation_reserve_share_bps())
+            / U512::from(BPS_DIVISOR);
+
+        let mcspr_addr = self.mcspr_address();
+        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        let self_address = self.env().self_address();
+        if mcspr.allowance(liquidator, self_address) < repaid {
+            self.env().revert(VaultError::InsufficientAllowance);
+        }
+
+        // Effects: debt and collateral move before any token call
+        self.record_repayment(user, current_debt, repaid);
+        self.debt_principal.set(&user, current_debt - repaid);
+        self.reduce_total_debt(repaid);
+        self.set_collateral(user, user_collateral - seized);
+        let liquidator_collateral = self.collateral_of(liquidator);
+        self.set_collateral(liquidator, liquidator_collateral + seized - reserve_motes);
+        if !reserve_motes.is_zero() {
+            let total = self.total_collateral.get_or_default();
+            self.total_collateral.set(total.saturating_sub(reserve_motes));
+            let kept = self.liquidation_reserve_motes.get_or_default();
+            self.liquidation_reserve_motes.set(kept + reserve_motes);
+        }
+        if self.vault_status.get(&liquidator).unwrap_or_default() == VaultStatus::None {
+            self.vault_status.set(&liquidator, VaultStatus::Active);
+            self.last_accrual_ts.set(&liquidator, self.env().get_block_time());
+            self.list_vault(liquidator);
+        }
+
+        // Interactions: pull from liquidator, then burn
+        mcspr.transfer_from(liquidator, self_address, repaid);
+        mcspr.burn(self_address, repaid);
+        self.assert_supply_invariant();
+
+        self.emit(events::Liquidated {
+            user,
+            liquidator,
+            repaid_wad: repaid,
+            seized_motes: seized,
+            reserve_motes,
         });
+        self.refresh_liquidatable(user);
     }
 
-    /// Withdraw maximum collateral while keeping LTV valid (≤80%).
+    /// Withdraw maximum collateral while keeping LTV valid (≤ ltv_max_bps).
     /// Calculates exact max amount at execution time to handle real-time interest.
     pub fn withdraw_max(&mut self) {
-        self.require_not_paused();
+        self.require_operation(OP_WITHDRAW);
         let caller = self.env().caller();
 
         // Check vault exists and is active
@@ -579,7 +1327,7 @@ impl Magni {
         // Accrue interest first
         self.accrue_interest(caller);
 
-        let current_collateral = self.collateral.get(&caller).unwrap_or_default();
+        let current_collateral = self.collateral_of(caller);
         if current_collateral == U512::zero() {
             self.env().revert(VaultError::InsufficientCollateral);
         }
@@ -588,12 +1336,12 @@ impl Magni {
 
         // Calculate max withdrawable amount
         // If no debt, can withdraw everything
-        // If debt > 0, must keep: collateral_wad >= debt * BPS_DIVISOR / LTV_MAX_BPS
+        // If debt > 0, must keep: collateral_wad >= debt * BPS_DIVISOR / ltv_max_bps
         let max_withdraw_motes = if debt == U256::zero() {
             current_collateral
         } else {
-            // min_collateral_wad = debt * 10000 / 8000 = debt * 1.25
-            let min_collateral_wad = debt * U256::from(BPS_DIVISOR) / U256::from(LTV_MAX_BPS);
+            // min_collateral_wad = debt * 10000 / ltv_max_bps (debt * 1.25 at 80%)
+            let min_collateral_wad = self.min_collateral_for(debt);
             let current_collateral_wad = self.motes_to_wad(current_collateral);
 
             if current_collateral_wad <= min_collateral_wad {
@@ -609,42 +1357,38 @@ impl Magni {
             self.env().revert(VaultError::InsufficientCollateral);
         }
 
-        // Update collateral
-        let remaining_collateral = current_collateral - max_withdraw_motes;
-        self.collateral.set(&caller, remaining_collateral);
-        let total = self.total_collateral.get_or_default();
-        if total >= max_withdraw_motes {
-            self.total_collateral.set(total - max_withdraw_motes);
-        }
+        self.queue_withdraw(caller, max_withdraw_motes, caller);
+    }
 
-        // Store pending withdrawal
-        self.pending_withdraw.set(&caller, max_withdraw_motes);
-        self.vault_status.set(&caller, VaultStatus::Withdrawing);
+    /// Close the vault in one call: repay all debt and request withdrawal of all collateral.
+    /// Requires an mCSPR allowance covering the full accrued debt.
+    /// Only the unbonding wait and finalize_withdraw remain afterwards.
+    pub fn close_vault(&mut self) {
+        self.require_operation(OP_REPAY | OP_WITHDRAW);
+        let caller = self.env().caller();
 
-        // Check if we need to undelegate
-        let liquid = self.env().self_balance();
-        if liquid < max_withdraw_motes {
-            let delegated = self.total_delegated.get_or_default();
-            let undelegate_amount = max_withdraw_motes.min(delegated);
+        // Check vault exists and is active
+        let status = self.vault_status.get(&caller).unwrap_or_default();
+        if status == VaultStatus::None {
+            self.env().revert(VaultError::NoVault);
+        }
+        if status == VaultStatus::Withdrawing {
+            self.env().revert(VaultError::WithdrawPending);
+        }
 
-            if undelegate_amount > U512::zero() {
-                let validator_key = self.validator_public_key.get_or_default();
-                if !validator_key.is_empty() {
-                    let validator_pk = self.parse_validator_key(&validator_key);
-                    self.env().undelegate(validator_pk, undelegate_amount);
-                    self.total_delegated.set(delegated - undelegate_amount);
-
-                    self.env().emit_event(events::UndelegationRequested {
-                        amount_motes: undelegate_amount,
-                    });
-                }
-            }
+        // Accrue interest first to get exact debt
+        self.accrue_interest(caller);
+
+        let current_debt = self.debt_principal.get(&caller).unwrap_or_default();
+        if current_debt > U256::zero() {
+            self.settle_repayment(caller, current_debt, current_debt);
         }
 
-        self.env().emit_event(events::WithdrawRequested {
-            user: caller,
-            amount_motes: max_withdraw_motes,
-        });
+        let current_collateral = self.collateral_of(caller);
+        if current_collateral == U512::zero() {
+            self.env().revert(VaultError::InsufficientCollateral);
+        }
+        self.queue_withdraw(caller, current_collateral, caller);
     }
 
     // ==========================================
@@ -653,48 +1397,161 @@ impl Magni {
 
     /// Get complete position info for user
     pub fn get_position(&self, user: Address) -> PositionInfo {
-        let collateral_motes = self.collateral.get(&user).unwrap_or_default();
-        let collateral_wad = self.motes_to_wad(collateral_motes);
+        let collateral_motes = self.collateral_of(user);
         let debt_wad = self.debt_with_interest(user);
-        let pending_withdraw_motes = self.pending_withdraw.get(&user).unwrap_or_default();
+        let status = self.vault_status.get(&user).unwrap_or_default() as u8;
+        self.position_info(user, collateral_motes, debt_wad, status)
+    }
 
-        let status = match self.vault_status.get(&user).unwrap_or_default() {
-            VaultStatus::None => 0,
-            VaultStatus::Active => 1,
-            VaultStatus::Withdrawing => 2,
-        };
+    /// Get everything a wallet shows for `user` in one call: mCSPR balance, mCSPR
+    /// allowance to this vault and get_position
+    pub fn get_account_data(&self, user: Address) -> AccountData {
+        let mcspr = MCSPRTokenContractRef::new(self.env().clone(), self.mcspr_address());
+        AccountData {
+            mcspr_balance_wad: mcspr.balance_of(user),
+            vault_allowance_wad: mcspr.allowance(user, self.env().self_address()),
+            position: self.get_position(user),
+        }
+    }
 
-        // Calculate LTV (basis points)
-        let ltv_bps = if collateral_wad == U256::zero() {
-            0u64
-        } else {
-            let ltv = debt_wad * U256::from(BPS_DIVISOR) / collateral_wad;
-            ltv.as_u64()
-        };
+    /// Preview the position if CSPR were worth `price_wad` mCSPR (read-only).
+    /// LTV checks value collateral 1:1 with debt, so WAD reproduces get_position and
+    /// WAD / 2 shows the position after a 50% CSPR drop against the debt. collateral_wad
+    /// is reported at the hypothetical price.
+    pub fn get_position_at_price(&self, user: Address, price_wad: U256) -> PositionInfo {
+        let collateral_motes = self.collateral_of(user);
+        let collateral_wad = self
+            .motes_to_wad(collateral_motes)
+            .saturating_mul(price_wad)
+            / U256::from(units::WAD);
+        let debt_wad = self.debt_with_interest(user);
+        let status = self.vault_status.get(&user).unwrap_or_default() as u8;
+        self.position_info_valued(user, collateral_motes, collateral_wad, debt_wad, status)
+    }
 
-        // Calculate health factor (scaled by 10000, >10000 = healthy)
-        let health_factor = if debt_wad == U256::zero() {
-            u64::MAX // Infinite health if no debt
+    /// Preview the position after borrowing `amount_wad` more (read-only).
+    /// Over-LTV results are returned as-is rather than reverting.
+    pub fn simulate_borrow(&self, user: Address, amount_wad: U256) -> PositionInfo {
+        let collateral_motes = self.collateral_of(user);
+        let debt_wad = self.debt_with_interest(user).saturating_add(amount_wad);
+        let status = self.vault_status.get(&user).unwrap_or_default() as u8;
+        self.position_info(user, collateral_motes, debt_wad, status)
+    }
+
+    /// Preview the position after withdrawing `amount_motes` of collateral (read-only).
+    /// Amounts above the collateral are clamped and reported with status STATUS_CLAMPED;
+    /// over-LTV results are returned rather than reverting.
+    pub fn simulate_withdraw(&self, user: Address, amount_motes: U512) -> PositionInfo {
+        let collateral_motes = self.collateral_of(user);
+        let (withdrawn, status) = if amount_motes > collateral_motes {
+            (collateral_motes, STATUS_CLAMPED)
         } else {
-            let max_borrow = collateral_wad * U256::from(LTV_MAX_BPS) / U256::from(BPS_DIVISOR);
-            let hf = max_borrow * U256::from(BPS_DIVISOR) / debt_wad;
-            hf.as_u64()
+            (amount_motes, self.vault_status.get(&user).unwrap_or_default() as u8)
         };
+        let debt_wad = self.debt_with_interest(user);
+        let mut position = self.position_info(user, collateral_motes - withdrawn, debt_wad, status);
+        position.pending_withdraw_motes += withdrawn;
+        position
+    }
 
-        PositionInfo {
-            collateral_motes,
-            collateral_wad,
-            debt_wad,
-            ltv_bps,
-            health_factor,
-            pending_withdraw_motes,
-            status,
+    /// Get the crate version this vault was built from
+    pub fn version(&self) -> String {
+        crate::VERSION.to_string()
+    }
+
+    /// Get the contract discriminator, so tooling can tell the vault from the tokens
+    pub fn contract_kind(&self) -> String {
+        "magni_vault".to_string()
+    }
+
+    /// Get the maximum oracle price age (seconds) accepted; 0 when unchecked
+    pub fn max_price_staleness_secs(&self) -> u64 {
+        self.max_price_staleness_secs.get_or_default()
+    }
+
+    /// Get the largest price move (bps) refresh_price accepts; 0 when the breaker is off
+    pub fn max_price_move_bps(&self) -> u64 {
+        self.max_price_move_bps.get_or_default()
+    }
+
+    /// Get the last oracle price (wad) accepted by refresh_price, if any
+    pub fn last_good_price_wad(&self) -> Option<U256> {
+        self.last_good_price_wad.get()
+    }
+
+    /// Check whether finalize_migration has locked position imports
+    pub fn migration_finalized(&self) -> bool {
+        self.migration_finalized.get_or_default()
+    }
+
+    /// Check whether retire has permanently disabled borrowing
+    pub fn is_retired(&self) -> bool {
+        self.retired.get_or_default()
+    }
+
+    /// Get the storage layout version (0 for deployments predating versioning)
+    pub fn storage_version(&self) -> u32 {
+        self.storage_version.get_or_default()
+    }
+
+    /// Check whether init has run
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.get_or_default()
+    }
+
+    /// Get all vault parameters in one read
+    pub fn get_config(&self) -> VaultConfig {
+        VaultConfig {
+            mcspr: self.mcspr.get(),
+            validator_public_key: self.validator_public_key.get_or_default(),
+            ltv_max_bps: self.ltv_max_bps(),
+            interest_rate_bps: self.current_interest_rate_bps(),
+            base_rate_bps: self.base_rate_bps.get_or_default(),
+            slope1_bps: self.slope1_bps.get_or_default(),
+            slope2_bps: self.slope2_bps.get_or_default(),
+            optimal_utilization_bps: self.optimal_utilization_bps.get_or_default(),
+            liquidation_threshold_bps: LIQUIDATION_THRESHOLD_BPS,
+            min_delegation_motes: U512::from(MIN_DELEGATION_MOTES),
+            min_deposit_motes: self.min_deposit_motes.get_or_default(),
+            reserve_factor_bps: self.reserve_factor_bps.get_or_default(),
+            fee_recipient: self.fee_recipient.get(),
+            auto_delegate: self.auto_delegate.get_or_default(),
+            unbonding_period_secs: self.unbonding_period_secs.get_or_default(),
+            min_delegate_interval_secs: self.min_delegate_interval_secs.get_or_default(),
+            paused: self.paused.get_or_default(),
+            price_oracle: self.price_oracle.get(),
+            price_feed_id: self.price_feed_id.get_or_default(),
         }
     }
 
-    /// Get collateral in motes
+    /// Get the referrer recorded for `user`, if any
+    pub fn referrer_of(&self, user: Address) -> Option<Address> {
+        self.referrer.get(&user)
+    }
+
+    /// Get `user`'s borrow and repay activity
+    pub fn user_stats(&self, user: Address) -> UserStats {
+        UserStats {
+            borrow_count: self.borrow_count.get(&user).unwrap_or_default(),
+            repay_count: self.repay_count.get(&user).unwrap_or_default(),
+            lifetime_borrowed_wad: self.lifetime_borrowed_wad.get(&user).unwrap_or_default(),
+            lifetime_repaid_wad: self.lifetime_repaid_wad.get(&user).unwrap_or_default(),
+        }
+    }
+
+    /// Get collateral in motes, after any slashing haircut
     pub fn collateral_of(&self, user: Address) -> U512 {
-        self.collateral.get(&user).unwrap_or_default()
+        let stored = self.collateral.get(&user).unwrap_or_default();
+        let user_index = self.user_collateral_index.get(&user).unwrap_or(U512::from(units::WAD));
+        if user_index.is_zero() {
+            return U512::zero();
+        }
+        stored * self.collateral_index() / user_index
+    }
+
+    /// Get the slashing accumulator (WAD = no slashing applied yet)
+    pub fn collateral_index(&self) -> U512 {
+        self.collateral_index.get_or(U512::from(units::WAD))
     }
 
     /// Get debt with accrued interest in wad (read-only calculation)
@@ -702,16 +1559,77 @@ impl Magni {
         self.debt_with_interest(user)
     }
 
-    /// Get current LTV in basis points
+    /// Get the mCSPR (wad) to approve before repay_all: current debt with interest
+    /// plus `buffer_bps` of headroom for interest accrued until the repay executes.
+    /// repay_all only pulls the exact debt, so the unused headroom stays approved.
+    pub fn repay_all_amount(&self, user: Address, buffer_bps: u64) -> U256 {
+        let debt = self.debt_with_interest(user);
+        apply_bps(debt, BPS_DIVISOR.saturating_add(buffer_bps), true)
+    }
+
+    /// Get debt with accrued interest in motes, rounded up to the next mote
+    pub fn debt_in_motes(&self, user: Address) -> U512 {
+        units::wad_to_motes_round_up(self.debt_with_interest(user))
+    }
+
+    /// Get interest accrued since the last stored update (wad, read-only)
+    /// Zero when there is no debt or no time has elapsed.
+    pub fn pending_interest_of(&self, user: Address) -> U256 {
+        let principal = self.debt_principal.get(&user).unwrap_or_default();
+        self.debt_with_interest(user) - principal
+    }
+
+    /// Get collateral value in USD (wad): collateral_wad * CSPR price / WAD
+    pub fn collateral_value_usd(&self, user: Address) -> U256 {
+        let collateral_wad = self.motes_to_wad(self.collateral_of(user));
+        let price_wad = self.checked_price_wad();
+        self.usd_value(collateral_wad, price_wad)
+    }
+
+    /// Get total value locked in motes: collateral backing open vaults. Amounts already
+    /// requested for withdrawal have left total_collateral and are not counted.
+    pub fn tvl_motes(&self) -> U512 {
+        self.total_collateral.get_or_default()
+    }
+
+    /// Get total value locked in USD (wad) at the oracle CSPR price
+    pub fn tvl_usd(&self) -> U256 {
+        let tvl_wad = self.motes_to_wad(self.tvl_motes());
+        let price_wad = self.checked_price_wad();
+        self.usd_value(tvl_wad, price_wad)
+    }
+
+    /// Get debt value in USD (wad). mCSPR is treated as $1-pegged for now.
+    pub fn debt_value_usd(&self, user: Address) -> U256 {
+        self.debt_with_interest(user)
+    }
+
+    /// Get current LTV in basis points; u64::MAX for debt without collateral
     pub fn ltv_of(&self, user: Address) -> u64 {
-        let collateral_motes = self.collateral.get(&user).unwrap_or_default();
+        let collateral_motes = self.collateral_of(user);
+        let debt_wad = self.debt_with_interest(user);
         if collateral_motes == U512::zero() {
-            return 0;
+            return if debt_wad.is_zero() { 0 } else { u64::MAX };
         }
         let collateral_wad = self.motes_to_wad(collateral_motes);
-        let debt_wad = self.debt_with_interest(user);
-        let ltv = debt_wad * U256::from(BPS_DIVISOR) / collateral_wad;
-        ltv.as_u64()
+        ratio_bps(debt_wad, collateral_wad)
+    }
+
+    /// Filter up to MAX_USER_BATCH `users` down to those whose LTV with interest to date
+    /// exceeds `threshold_bps`, in input order. Debt without collateral always qualifies.
+    pub fn unhealthy_among(&self, users: Vec<Address>, threshold_bps: u64) -> Vec<Address> {
+        if users.len() > MAX_USER_BATCH {
+            self.env().revert(VaultError::BatchTooLarge);
+        }
+        users
+            .into_iter()
+            .filter(|user| self.get_position(*user).ltv_bps > threshold_bps)
+            .collect()
+    }
+
+    /// Get the health factor (scaled by 10000) a borrow must leave the position at
+    pub fn min_borrow_health_factor(&self) -> u64 {
+        self.min_borrow_health_factor.get_or(BPS_DIVISOR)
     }
 
     /// Get health factor (scaled by 10000)
@@ -720,11 +1638,10 @@ impl Magni {
         if debt_wad == U256::zero() {
             return u64::MAX;
         }
-        let collateral_motes = self.collateral.get(&user).unwrap_or_default();
+        let collateral_motes = self.collateral_of(user);
         let collateral_wad = self.motes_to_wad(collateral_motes);
-        let max_borrow = collateral_wad * U256::from(LTV_MAX_BPS) / U256::from(BPS_DIVISOR);
-        let hf = max_borrow * U256::from(BPS_DIVISOR) / debt_wad;
-        hf.as_u64()
+        let max_borrow = self.max_debt_for(collateral_wad);
+        ratio_bps(max_borrow, debt_wad)
     }
 
     /// Get pending withdraw amount
@@ -732,10 +1649,20 @@ impl Magni {
         self.pending_withdraw.get(&user).unwrap_or_default()
     }
 
+    /// Get block time (ms) at which the pending withdrawal may be finalized
+    pub fn withdraw_ready_ts(&self, user: Address) -> u64 {
+        self.withdraw_ready_ts.get(&user).unwrap_or_default()
+    }
+
+    /// Get who the pending withdrawal will be paid to (the user unless chosen otherwise)
+    pub fn withdraw_recipient_of(&self, user: Address) -> Address {
+        self.withdraw_recipient.get(&user).unwrap_or(user)
+    }
+
     /// Get maximum withdrawable amount while keeping LTV valid
     /// Returns 0 if cannot withdraw anything
     pub fn max_withdraw_of(&self, user: Address) -> U512 {
-        let current_collateral = self.collateral.get(&user).unwrap_or_default();
+        let current_collateral = self.collateral_of(user);
         if current_collateral == U512::zero() {
             return U512::zero();
         }
@@ -746,8 +1673,8 @@ impl Magni {
             return current_collateral;
         }
 
-        // min_collateral_wad = debt * 10000 / 8000
-        let min_collateral_wad = debt * U256::from(BPS_DIVISOR) / U256::from(LTV_MAX_BPS);
+        // min_collateral_wad = debt * 10000 / ltv_max_bps
+        let min_collateral_wad = self.min_collateral_for(debt);
         let current_collateral_wad = self.motes_to_wad(current_collateral);
 
         if current_collateral_wad <= min_collateral_wad {
@@ -758,6 +1685,17 @@ impl Magni {
         self.wad_to_motes(max_withdraw_wad)
     }
 
+    /// Get the number of addresses that have ever opened a vault
+    pub fn vault_count(&self) -> u64 {
+        self.vault_count.get_or_default()
+    }
+
+    /// Get the `index`-th address to open a vault, in opening order.
+    /// Closed vaults stay listed; check status_of or get_position for the current state.
+    pub fn vault_at(&self, index: u64) -> Option<Address> {
+        self.vault_owners.get(&index)
+    }
+
     /// Get vault status
     pub fn status_of(&self, user: Address) -> u8 {
         match self.vault_status.get(&user).unwrap_or_default() {
@@ -772,113 +1710,1711 @@ impl Magni {
         self.env().self_balance()
     }
 
+    /// Get the sum of all users' pending withdrawals (motes)
+    pub fn total_pending_withdraw(&self) -> U512 {
+        self.total_pending_withdraw.get_or_default()
+    }
+
+    /// Get the share (bps) of pending withdrawals the liquid balance could pay right now,
+    /// capped at 10000; 10000 when nothing is pending
+    pub fn liquidity_coverage_bps(&self) -> u64 {
+        let pending = self.total_pending_withdraw();
+        if pending.is_zero() {
+            return BPS_DIVISOR;
+        }
+        let coverage = self.liquid_balance() * U512::from(BPS_DIVISOR) / pending;
+        coverage.min(U512::from(BPS_DIVISOR)).as_u64()
+    }
+
     /// Get total delegated amount (tracked)
     pub fn total_delegated(&self) -> U512 {
         self.total_delegated.get_or_default()
     }
 
-    /// Get actual delegated amount from chain
+    /// Get undelegations requested but not yet swept
+    pub fn undelegating_motes(&self) -> U512 {
+        self.undelegating_motes.get_or_default()
+    }
+
+    /// Get swept undelegated CSPR available to pay pending withdrawals
+    pub fn swept_liquid_motes(&self) -> U512 {
+        self.swept_liquid_motes.get_or_default()
+    }
+
+    /// Get actual delegated amount from chain, summed over registered validators
     pub fn delegated_amount(&self) -> U512 {
-        let validator_key = self.validator_public_key.get_or_default();
-        if validator_key.is_empty() {
-            return U512::zero();
+        let mut total = U512::zero();
+        for key in self.validators() {
+            let validator_pk = self.parse_validator_key(&key);
+            total += self.env().delegated_amount(validator_pk);
         }
-        let validator_pk = self.parse_validator_key(&validator_key);
-        self.env().delegated_amount(validator_pk)
+        total
     }
 
-    /// Get pending to delegate (batching pool)
-    pub fn pending_to_delegate(&self) -> U512 {
-        self.pending_to_delegate.get_or_default()
+    /// Compare tracked stake against delegated_amount() on chain.
+    /// Tracked stake excludes undelegations already requested, which the chain no
+    /// longer reports as delegated.
+    pub fn delegation_drift(&self) -> DelegationDrift {
+        let tracked = self
+            .total_delegated
+            .get_or_default()
+            .saturating_sub(self.undelegating_motes.get_or_default());
+        let actual = self.delegated_amount();
+        let actual_above_tracked = actual >= tracked;
+        let drift_motes = if actual_above_tracked {
+            actual - tracked
+        } else {
+            tracked - actual
+        };
+        DelegationDrift {
+            tracked_motes: tracked,
+            actual_motes: actual,
+            drift_motes,
+            actual_above_tracked,
+        }
     }
 
-    /// Get total collateral across all users
-    pub fn total_collateral(&self) -> U512 {
-        self.total_collateral.get_or_default()
+    /// Check that on-chain CSPR backs everything the contract owes: liquid balance plus
+    /// tracked stake net of undelegations must cover total_collateral, the unclaimed
+    /// performance fee, the liquidation reserve and the pending withdrawals not already
+    /// backed by undelegations in flight. Tracked stake is capped at delegated_amount()
+    /// so slashing shows. False after unabsorbed slashing or an accounting bug.
+    /// Until sweep_undelegated runs, unbonded stake back in the balance still offsets
+    /// pending withdrawals, so the check is that much lenient in between.
+    pub fn solvency_check(&self) -> bool {
+        let undelegating = self.undelegating_motes.get_or_default();
+        let tracked = self.total_delegated.get_or_default().saturating_sub(undelegating);
+        let held = self.env().self_balance() + self.delegated_amount().min(tracked);
+        let unbacked_pending =
+            self.total_pending_withdraw.get_or_default().saturating_sub(undelegating);
+        let owed = self.total_collateral.get_or_default()
+            + unbacked_pending
+            + self.accrued_performance_fee.get_or_default()
+            + self.liquidation_reserve_motes.get_or_default();
+        held >= owed
     }
 
-    /// Get total debt across all users
-    pub fn total_debt(&self) -> U256 {
-        self.total_debt.get_or_default()
+    /// Get registered validators in undelegation order
+    pub fn validators(&self) -> Vec<String> {
+        (0..self.validator_count.get_or_default())
+            .filter_map(|i| self.validators.get(&i))
+            .collect()
     }
 
-    /// Get mCSPR token address
-    pub fn mcspr(&self) -> Option<Address> {
-        self.mcspr.get()
+    /// Check whether `key` is allowlisted and its activation delay has passed
+    pub fn is_validator_allowed(&self, key: String) -> bool {
+        self.validator_allowed(&key)
     }
 
-    /// Get validator public key
-    pub fn validator_public_key(&self) -> String {
-        self.validator_public_key.get_or_default()
+    /// Get when `key`'s allowlist entry takes effect (ms), if it is allowlisted
+    pub fn validator_allowed_from(&self, key: String) -> Option<u64> {
+        if self.validator_allowlist.get(&key).unwrap_or_default() {
+            self.validator_allowed_from.get(&key)
+        } else {
+            None
+        }
     }
 
-    /// Get contract owner
-    pub fn owner(&self) -> Option<Address> {
-        self.owner.get()
+    /// Get stake delegated to `key` and not yet requested for undelegation (tracked)
+    pub fn validator_bonded(&self, key: String) -> U512 {
+        self.validator_bonded.get(&key).unwrap_or_default()
     }
 
-    /// Check if paused
-    pub fn is_paused(&self) -> bool {
-        self.paused.get_or_default()
+    /// Get the largest share (bps) of bonded stake new delegations may leave on one validator
+    pub fn max_validator_stake_bps(&self) -> u64 {
+        self.max_validator_stake_bps.get_or(BPS_DIVISOR)
     }
 
-    // ==========================================
-    // Admin Functions
-    // ==========================================
-
-    /// Set validator public key (owner only)
-    pub fn set_validator_public_key(&mut self, new_key: String) {
+    /// Get pending to delegate (batching pool)
+    pub fn pending_to_delegate(&self) -> U512 {
+        self.pending_to_delegate.get_or_default()
+    }
+
+    /// Get how many more motes the batching pool needs before it can be delegated
+    /// (0 once pending_to_delegate has reached the minimum delegation)
+    pub fn motes_until_delegation(&self) -> U512 {
+        U512::from(MIN_DELEGATION_MOTES).saturating_sub(self.pending_to_delegate.get_or_default())
+    }
+
+    /// Get utilization = total debt / total collateral in basis points (capped at 100%)
+    pub fn utilization_bps(&self) -> u64 {
+        let collateral_wad = self.motes_to_wad(self.total_collateral.get_or_default());
+        if collateral_wad.is_zero() {
+            return 0;
+        }
+        let debt_wad = self.total_debt.get_or_default();
+        ratio_bps(debt_wad, collateral_wad).min(BPS_DIVISOR)
+    }
+
+    /// Get current borrow APR (bps) from the two-slope model at current utilization.
+    /// Accrual samples this rate instantaneously: the rate at the time of each accrual
+    /// is applied to the whole period since the user's previous accrual.
+    pub fn current_interest_rate_bps(&self) -> u64 {
+        let base = self.base_rate_bps.get_or_default();
+        let slope1 = self.slope1_bps.get_or_default();
+        let slope2 = self.slope2_bps.get_or_default();
+        let optimal = self.optimal_utilization_bps.get_or_default();
+        let utilization = self.utilization_bps();
+        if utilization <= optimal {
+            base + slope1 * utilization / optimal
+        } else {
+            base + slope1 + slope2 * (utilization - optimal) / (BPS_DIVISOR - optimal)
+        }
+    }
+
+    /// Get the APR (bps) debt actually grows at: the current rate less the reserve's share
+    pub fn effective_apr_bps(&self) -> u64 {
+        let annual = self.current_interest_rate_bps();
+        annual - annual * self.reserve_factor_bps.get_or_default() / BPS_DIVISOR
+    }
+
+    /// Get liquid / delegated / pending-delegation balances in one snapshot
+    pub fn balance_breakdown(&self) -> BalanceBreakdown {
+        BalanceBreakdown {
+            liquid_motes: self.env().self_balance(),
+            delegated_motes: self.total_delegated.get_or_default(),
+            pending_to_delegate_motes: self.pending_to_delegate.get_or_default(),
+        }
+    }
+
+    /// Get unbonding period applied to new withdrawal requests (seconds)
+    pub fn unbonding_period_secs(&self) -> u64 {
+        self.unbonding_period_secs.get_or_default()
+    }
+
+    /// Get the wait (seconds) between a vault crossing the threshold and liquidation
+    pub fn liquidation_grace_secs(&self) -> u64 {
+        self.liquidation_grace_secs.get_or(DEFAULT_LIQUIDATION_GRACE_SECS)
+    }
+
+    /// Get the largest share (bps) of a vault's debt one liquidate call may repay
+    pub fn close_factor_bps(&self) -> u64 {
+        self.close_factor_bps.get_or(DEFAULT_CLOSE_FACTOR_BPS)
+    }
+
+    /// Get the debt level below which a vault may be liquidated in full
+    pub fn liquidation_dust_wad(&self) -> U256 {
+        self.liquidation_dust_wad.get_or(U256::from(DEFAULT_LIQUIDATION_DUST_WAD))
+    }
+
+    /// Get block time (ms) at which `user` was first seen liquidatable, 0 while healthy
+    pub fn liquidatable_since(&self, user: Address) -> u64 {
+        self.liquidatable_since.get(&user).unwrap_or_default()
+    }
+
+    /// Get the required gap (seconds) between two borrows by the same user
+    pub fn borrow_cooldown_secs(&self) -> u64 {
+        self.borrow_cooldown_secs.get_or_default()
+    }
+
+    /// Get block time (ms) of `user`'s latest borrow; 0 if never borrowed
+    pub fn last_borrow_ts(&self, user: Address) -> u64 {
+        self.last_borrow_ts.get(&user).unwrap_or_default()
+    }
+
+    /// Get the promotional interest-free window (seconds) after a first borrow
+    pub fn interest_free_secs(&self) -> u64 {
+        self.interest_free_secs.get_or_default()
+    }
+
+    /// Get block time (ms) until which `user` accrues no interest; 0 if never borrowed
+    pub fn interest_free_until(&self, user: Address) -> u64 {
+        match self.first_borrow_ts.get(&user) {
+            Some(ts) => ts.saturating_add(self.interest_free_secs().saturating_mul(MILLIS_PER_SECOND)),
+            None => 0,
+        }
+    }
+
+    /// Get the LTV max (bps) enforced on borrows and withdrawals
+    pub fn ltv_max_bps(&self) -> u64 {
+        self.ltv_max_bps.get_or(DEFAULT_LTV_MAX_BPS)
+    }
+
+    /// Get minimum deposit (motes) required to open a vault
+    pub fn min_deposit_motes(&self) -> U512 {
+        self.min_deposit_motes.get_or_default()
+    }
+
+    /// Check if deposits delegate immediately once the batch reaches minimum
+    pub fn auto_delegate(&self) -> bool {
+        self.auto_delegate.get_or_default()
+    }
+
+    /// Get total collateral across all users
+    pub fn total_collateral(&self) -> U512 {
+        self.total_collateral.get_or_default()
+    }
+
+    /// Get total debt across all users
+    pub fn total_debt(&self) -> U256 {
+        self.total_debt.get_or_default()
+    }
+
+    /// Get cumulative interest added to borrower debt (excludes the reserve share)
+    pub fn total_interest_accrued(&self) -> U256 {
+        self.total_interest_accrued.get_or_default()
+    }
+
+    /// Get cumulative interest repaid. Each repayment settles interest (debt above the
+    /// borrowed principal) before principal, so accrued minus repaid is the interest
+    /// still outstanding.
+    pub fn total_interest_repaid(&self) -> U256 {
+        self.total_interest_repaid.get_or_default()
+    }
+
+    /// Get the width (seconds) of the epochs used by epoch_flows
+    pub fn epoch_length_secs(&self) -> u64 {
+        self.epoch_length_secs.get_or(DEFAULT_EPOCH_LENGTH_SECS)
+    }
+
+    /// Epoch the current block falls in: block time divided by the epoch length
+    pub fn current_epoch(&self) -> u64 {
+        self.epoch_of(self.env().get_block_time())
+    }
+
+    /// (borrowed, repaid) mCSPR in `epoch`. Repaid counts user repayments of any kind
+    /// (repay, deleverage, tCSPR); debt cleared by liquidation is not included.
+    /// Epochs are aligned to block time 0 and floored, so a call exactly on a boundary
+    /// counts towards the later epoch. Changing epoch_length_secs re-maps epoch numbers;
+    /// buckets written under the old length keep their old meaning.
+    pub fn epoch_flows(&self, epoch: u64) -> (U256, U256) {
+        (
+            self.borrowed_in_epoch.get(&epoch).unwrap_or_default(),
+            self.repaid_in_epoch.get(&epoch).unwrap_or_default(),
+        )
+    }
+
+    /// Check that mCSPR supply is fully backed by vault debt plus reserve
+    pub fn supply_invariant_ok(&self) -> bool {
+        let mcspr_addr = match self.mcspr.get() {
+            Some(addr) => addr,
+            None => return true,
+        };
+        let mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        let backing = self.total_debt.get_or_default() + self.reserve_wad.get_or_default();
+        mcspr.total_supply() <= backing
+    }
+
+    /// Get protocol reserve balance (wad, not yet minted)
+    pub fn reserve_balance(&self) -> U256 {
+        self.reserve_wad.get_or_default()
+    }
+
+    /// Interest claimed by the protocol reserve (wad), kept separate from borrower debt.
+    /// Same value as `reserve_balance`, named after the underlying storage.
+    pub fn reserve_wad(&self) -> U256 {
+        self.reserve_wad.get_or_default()
+    }
+
+    /// Get share of accrued interest routed to the reserve (bps)
+    pub fn reserve_factor_bps(&self) -> u64 {
+        self.reserve_factor_bps.get_or_default()
+    }
+
+    /// Get share of harvested staking rewards kept as a performance fee (bps)
+    pub fn performance_fee_bps(&self) -> u64 {
+        self.performance_fee_bps.get_or_default()
+    }
+
+    /// Get performance fees harvested but not yet claimed (motes)
+    pub fn accrued_performance_fee(&self) -> U512 {
+        self.accrued_performance_fee.get_or_default()
+    }
+
+    /// Get share of each liquidation bonus kept by the protocol (bps)
+    pub fn liquidation_reserve_share_bps(&self) -> u64 {
+        self.liquidation_reserve_share_bps.get_or_default()
+    }
+
+    /// Get liquidation bonus collateral kept by the protocol, not yet claimed (motes)
+    pub fn liquidation_reserve_motes(&self) -> U512 {
+        self.liquidation_reserve_motes.get_or_default()
+    }
+
+    /// Get mCSPR token address
+    pub fn mcspr(&self) -> Option<Address> {
+        self.mcspr.get()
+    }
+
+    /// Get validator public key
+    pub fn validator_public_key(&self) -> String {
+        self.validator_public_key.get_or_default()
+    }
+
+    /// Get contract owner
+    pub fn owner(&self) -> Option<Address> {
+        self.owner.get()
+    }
+
+    /// Get allowed operations as a bitmask: bit 0 deposit, bit 1 borrow,
+    /// bit 2 repay, bit 3 withdraw. Zero while the whole contract is paused;
+    /// the borrow bit stays clear once the vault is retired.
+    pub fn operation_status(&self) -> u8 {
+        if self.paused.get_or_default() {
+            return 0;
+        }
+        let mut unavailable = self.paused_ops.get_or_default();
+        if self.retired.get_or_default() {
+            unavailable |= OP_BORROW;
+        }
+        OP_ALL & !unavailable
+    }
+
+    /// Get protocol fee recipient
+    pub fn fee_recipient(&self) -> Option<Address> {
+        self.fee_recipient.get()
+    }
+
+    /// Get the sequence number of the most recently emitted event
+    pub fn event_seq(&self) -> u64 {
+        self.event_seq.get_or_default()
+    }
+
+    /// Check if paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.get_or_default()
+    }
+
+    // ==========================================
+    // Admin Functions
+    // ==========================================
+
+    /// Set validator public key (owner only)
+    /// An empty key explicitly unsets the validator; any other key must be allowlisted.
+    pub fn set_validator_public_key(&mut self, new_key: String) {
         self.require_owner();
-        self.validator_public_key.set(new_key);
+        self.validate_validator_key(&new_key);
+        if !new_key.is_empty() {
+            self.require_validator_allowed(&new_key);
+        }
+        if !new_key.is_empty() && self.validator_index(&new_key).is_none() {
+            self.register_validator(new_key.clone());
+        }
+        let old_key = self.validator_public_key.get_or_default();
+        self.validator_public_key.set(new_key.clone());
+        self.emit(events::ValidatorChanged {
+            old_key,
+            new_key,
+            by: self.env().caller(),
+        });
+    }
+
+    /// Cap the share of bonded stake any one validator may reach through new delegations
+    /// (owner only); 10000 disables the cap. Delegations go to the next registered
+    /// validator with room, and delegate_exact reverts when none has any.
+    pub fn set_max_validator_stake_bps(&mut self, max_stake_bps: u64) {
+        self.require_owner();
+        if max_stake_bps == 0 || max_stake_bps > BPS_DIVISOR {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.max_validator_stake_bps.set(max_stake_bps);
+    }
+
+    /// Register an additional validator (owner only)
+    pub fn add_validator(&mut self, key: String) {
+        self.require_owner();
+        if key.is_empty() {
+            self.env().revert(VaultError::InvalidValidatorKey);
+        }
+        self.validate_validator_key(&key);
+        if self.validator_index(&key).is_some() {
+            self.env().revert(VaultError::ValidatorAlreadyRegistered);
+        }
+        self.require_validator_allowed(&key);
+        self.register_validator(key);
+    }
+
+    /// Allowlist a validator key (owner only). It can be registered or made primary
+    /// only VALIDATOR_ALLOWLIST_DELAY_SECS later, so a new stake destination is public
+    /// for longer than it takes depositors to withdraw.
+    pub fn add_allowed_validator(&mut self, key: String) {
+        self.require_owner();
+        if key.is_empty() {
+            self.env().revert(VaultError::InvalidValidatorKey);
+        }
+        self.validate_validator_key(&key);
+        let delay_ms = VALIDATOR_ALLOWLIST_DELAY_SECS * MILLIS_PER_SECOND;
+        let allowed_from = self.env().get_block_time().saturating_add(delay_ms);
+        self.validator_allowlist.set(&key, true);
+        self.validator_allowed_from.set(&key, allowed_from);
+        self.emit(events::ValidatorAllowlisted { key, allowed_from });
+    }
+
+    /// Drop a key from the allowlist (owner only); takes effect immediately.
+    /// An already registered validator stays registered and keeps its stake.
+    pub fn remove_allowed_validator(&mut self, key: String) {
+        self.require_owner();
+        self.validator_allowlist.set(&key, false);
+        self.emit(events::ValidatorDisallowed { key });
+    }
+
+    /// Remove a validator with no stake left on it (owner only).
+    /// Later validators shift up, keeping their relative undelegation order.
+    /// If it was the delegation target, the first remaining validator takes over.
+    pub fn remove_validator(&mut self, key: String) {
+        self.require_owner();
+        let index = match self.validator_index(&key) {
+            Some(i) => i,
+            None => self.env().revert(VaultError::ValidatorNotFound),
+        };
+        let validator_pk = self.parse_validator_key(&key);
+        if self.validator_bonded.get(&key).unwrap_or_default() > U512::zero()
+            || self.env().delegated_amount(validator_pk) > U512::zero()
+        {
+            self.env().revert(VaultError::ValidatorHasStake);
+        }
+
+        let count = self.validator_count.get_or_default();
+        for i in index..count - 1 {
+            let next = self.validators.get(&(i + 1)).unwrap_or_default();
+            self.validators.set(&i, next);
+        }
+        self.validator_count.set(count - 1);
+        self.emit(events::ValidatorRemoved { key: key.clone() });
+
+        if self.validator_public_key.get_or_default() == key {
+            let new_key = self.validators.get(&0).filter(|_| count > 1).unwrap_or_default();
+            self.validator_public_key.set(new_key.clone());
+            self.emit(events::ValidatorChanged {
+                old_key: key,
+                new_key,
+                by: self.env().caller(),
+            });
+        }
+    }
+
+    /// Pause contract (owner only)
+    pub fn pause(&mut self) {
+        self.require_owner();
+        if self.paused.get_or_default() {
+            self.env().revert(VaultError::ContractPaused);
+        }
+        self.paused.set(true);
+        self.emit(events::Paused {
+            by: self.env().caller(),
+        });
+    }
+
+    /// Transfer CEP-18 tokens sent to this contract by mistake (owner only).
+    /// mCSPR is refused unless set_mcspr_rescue_enabled(true) was called first.
+    pub fn rescue_tokens(&mut self, token: Address, to: Address, amount: U256) {
+        self.require_owner();
+        if amount == U256::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if self.mcspr.get() == Some(token) && !self.mcspr_rescue_enabled.get_or_default() {
+            self.env().revert(VaultError::ProtectedToken);
+        }
+
+        let mut cep18 = Cep18TokenContractRef::new(self.env().clone(), token);
+        cep18.transfer(to, amount);
+
+        self.emit(events::TokensRescued { token, to, amount });
+    }
+
+    /// Get CSPR held beyond what users and the protocol are owed (motes): liquid +
+    /// delegated minus total_collateral, pending withdrawals and unclaimed performance
+    /// fees and liquidation reserve. Staking rewards and stray transfers.
+    /// Undelegations are left out of delegated: once unbonded they are already liquid,
+    /// and until then they are not counted at all.
+    pub fn cspr_surplus(&self) -> U512 {
+        let delegated = self
+            .total_delegated
+            .get_or_default()
+            .saturating_sub(self.undelegating_motes.get_or_default());
+        let held = self.env().self_balance() + delegated;
+        let owed = self.total_collateral.get_or_default()
+            + self.total_pending_withdraw.get_or_default()
+            + self.accrued_performance_fee.get_or_default()
+            + self.liquidation_reserve_motes.get_or_default();
+        held.saturating_sub(owed)
+    }
+
+    /// Credit staking rewards to depositors (owner only).
+    /// On-chain stake above tracked stake is first taken into total_delegated; the
+    /// whole cspr_surplus is then the harvest. performance_fee_bps of it is set aside
+    /// for claim_performance_fee and the rest raises collateral_index, growing every
+    /// vault pro-rata. Only surplus is touched, so collateral and pending withdrawals
+    /// are never charged; unbonded stake awaiting sweep_undelegated is not surplus.
+    /// Does nothing while no collateral is deposited.
+    pub fn harvest_rewards(&mut self) {
+        self.require_owner();
+        let total_collateral = self.total_collateral.get_or_default();
+        if total_collateral.is_zero() {
+            return;
+        }
+        let undelegating = self.undelegating_motes.get_or_default();
+        let tracked = self.total_delegated.get_or_default().saturating_sub(undelegating);
+        let actual = self.delegated_amount();
+        if actual > tracked {
+            self.total_delegated.set(actual + undelegating);
+            for key in self.validators() {
+                let validator_pk = self.parse_validator_key(&key);
+                let on_chain = self.env().delegated_amount(validator_pk);
+                self.validator_bonded.set(&key, on_chain);
+            }
+        }
+
+        let rewards = self.cspr_surplus();
+        if rewards.is_zero() {
+            return;
+        }
+        let fee = rewards * U512::from(self.performance_fee_bps()) / U512::from(BPS_DIVISOR);
+        let distributed = rewards - fee;
+        let index = self.collateral_index();
+        let new_index = index * (total_collateral + distributed) / total_collateral;
+        self.collateral_index.set(new_index);
+        self.total_collateral.set(total_collateral + distributed);
+        let accrued = self.accrued_performance_fee.get_or_default();
+        self.accrued_performance_fee.set(accrued + fee);
+
+        self.emit(events::RewardsHarvested {
+            rewards_motes: rewards,
+            fee_motes: fee,
+            collateral_index: new_index,
+        });
+    }
+
+    /// Pay accrued performance fees to the fee recipient (owner only).
+    /// Reverts if the contract doesn't hold them liquid yet.
+    pub fn claim_performance_fee(&mut self) {
+        self.require_owner();
+        let amount_motes = self.accrued_performance_fee.get_or_default();
+        if amount_motes.is_zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if amount_motes > self.env().self_balance() {
+            self.env().revert(VaultError::InsufficientLiquidBalance);
+        }
+        self.accrued_performance_fee.set(U512::zero());
+
+        let to = self.fee_recipient_or_owner();
+        self.env().transfer_tokens(&to, &amount_motes);
+        self.emit(events::PerformanceFeeClaimed { to, amount_motes });
+    }
+
+    /// Pay the liquidation reserve to the fee recipient (owner only).
+    /// Reverts if the contract doesn't hold it liquid yet.
+    pub fn claim_liquidation_reserve(&mut self) {
+        self.require_owner();
+        let amount_motes = self.liquidation_reserve_motes.get_or_default();
+        if amount_motes.is_zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if amount_motes > self.env().self_balance() {
+            self.env().revert(VaultError::InsufficientLiquidBalance);
+        }
+        self.liquidation_reserve_motes.set(U512::zero());
+
+        let to = self.fee_recipient_or_owner();
+        self.env().transfer_tokens(&to, &amount_motes);
+        self.emit(events::LiquidationReserveClaimed { to, amount_motes });
+    }
+
+    /// Transfer surplus CSPR out of the contract (owner only).
+    /// Reverts if `amount_motes` would dip into collateral or pending withdrawals,
+    /// or if the surplus is not liquid yet.
+    pub fn rescue_cspr(&mut self, to: Address, amount_motes: U512) {
+        self.require_owner();
+        if amount_motes == U512::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if amount_motes > self.cspr_surplus() {
+            self.env().revert(VaultError::InsufficientSurplus);
+        }
+        if amount_motes > self.env().self_balance() {
+            self.env().revert(VaultError::InsufficientLiquidBalance);
+        }
+
+        self.env().transfer_tokens(&to, &amount_motes);
+        self.emit(events::CsprRescued { to, amount_motes });
+    }
+
+    /// Allow or forbid rescue_tokens on mCSPR (owner only)
+    pub fn set_mcspr_rescue_enabled(&mut self, enabled: bool) {
+        self.require_owner();
+        self.mcspr_rescue_enabled.set(enabled);
+    }
+
+    /// Pause or resume individual operations (owner only).
+    /// `ops` is a mask of OP_* bits; independent of the global pause.
+    pub fn set_operations_paused(&mut self, ops: u8, paused: bool) {
+        self.require_owner();
+        if ops == 0 || ops & !OP_ALL != 0 {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        let current = self.paused_ops.get_or_default();
+        let paused_ops = if paused { current | ops } else { current & !ops };
+        self.paused_ops.set(paused_ops);
+        self.emit(events::OperationsPauseChanged {
+            paused_ops,
+            by: self.env().caller(),
+        });
+    }
+
+    /// Unpause contract (owner only)
+    pub fn unpause(&mut self) {
+        self.require_owner();
+        if !self.paused.get_or_default() {
+            self.env().revert(VaultError::ContractPaused);
+        }
+        self.paused.set(false);
+        self.emit(events::Unpaused {
+            by: self.env().caller(),
+        });
+    }
+
+    /// Set share of accrued interest routed to the reserve (owner only)
+    pub fn set_reserve_factor_bps(&mut self, reserve_factor_bps: u64) {
+        self.require_owner();
+        if reserve_factor_bps > MAX_RESERVE_FACTOR_BPS {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.reserve_factor_bps.set(reserve_factor_bps);
+    }
+
+    /// Set the share of harvested rewards kept as a performance fee (owner only),
+    /// at most 20%
+    pub fn set_performance_fee_bps(&mut self, performance_fee_bps: u64) {
+        self.require_owner();
+        if performance_fee_bps > MAX_PERFORMANCE_FEE_BPS {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.performance_fee_bps.set(performance_fee_bps);
+    }
+
+    /// Set the share of each liquidation bonus kept by the protocol (owner only),
+    /// at most 50% so liquidators keep an incentive
+    pub fn set_liquidation_reserve_share_bps(&mut self, share_bps: u64) {
+        self.require_owner();
+        if share_bps > MAX_LIQUIDATION_RESERVE_SHARE_BPS {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.liquidation_reserve_share_bps.set(share_bps);
+    }
+
+    /// Set the LTV max (owner only), at most the liquidation threshold.
+    /// Positions already above a lowered max are not forced out: repay keeps working
+    /// and only new debt or collateral removal is held to the new limit.
+    pub fn set_ltv_max_bps(&mut self, ltv_max_bps: u64) {
+        self.require_owner();
+        if ltv_max_bps == 0 || ltv_max_bps > LTV_MAX_CEILING_BPS {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.ltv_max_bps.set(ltv_max_bps);
+    }
+
+    /// Set the minimum post-borrow health factor (owner only), scaled by 10000.
+    /// 10000 lets borrows reach max LTV; 11000 keeps a 10% buffer below it.
+    pub fn set_min_borrow_health_factor(&mut self, min_health_factor: u64) {
+        self.require_owner();
+        if min_health_factor < BPS_DIVISOR {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.min_borrow_health_factor.set(min_health_factor);
+    }
+
+    /// Set the liquidation grace period in seconds (owner only); 0 disables it
+    pub fn set_liquidation_grace_secs(&mut self, grace_secs: u64) {
+        self.require_owner();
+        self.liquidation_grace_secs.set(grace_secs);
+    }
+
+    /// Set the epoch width used by epoch_flows in seconds (owner only); must be nonzero
+    pub fn set_epoch_length_secs(&mut self, epoch_length_secs: u64) {
+        self.require_owner();
+        if epoch_length_secs == 0 {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.epoch_length_secs.set(epoch_length_secs);
+    }
+
+    /// Set the close factor in bps (owner only); 10000 allows full liquidation in one call
+    pub fn set_close_factor_bps(&mut self, close_factor_bps: u64) {
+        self.require_owner();
+        if close_factor_bps == 0 || close_factor_bps > BPS_DIVISOR {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.close_factor_bps.set(close_factor_bps);
+    }
+
+    /// Set the remaining-debt level under which the close factor is waived (owner only)
+    pub fn set_liquidation_dust_wad(&mut self, dust_wad: U256) {
+        self.require_owner();
+        self.liquidation_dust_wad.set(dust_wad);
+    }
+
+    /// Set the interest-free window granted after each user's first borrow (owner only).
+    /// The window starts at the first borrow, never restarts (not even after a full
+    /// repay) and covers the whole debt, including later borrows made inside it.
+    /// Changes apply to every user's window, including ones already running.
+    pub fn set_interest_free_secs(&mut self, interest_free_secs: u64) {
+        self.require_owner();
+        self.interest_free_secs.set(interest_free_secs);
+    }
+
+    /// Set the borrow cooldown in seconds (owner only); 0 disables it.
+    /// Applies to every path that borrows, including leverage_once and deposit_and_borrow.
+    pub fn set_borrow_cooldown_secs(&mut self, cooldown_secs: u64) {
+        self.require_owner();
+        self.borrow_cooldown_secs.set(cooldown_secs);
+    }
+
+    /// Configure the two-slope interest rate model (owner only).
+    /// Reverts if the kink is not strictly between 0 and 100%, or if a rate is above
+    /// its MAX_*_BPS cap, which keeps the rate math far from u64 overflow.
+    pub fn set_interest_rate_model(
+        &mut self,
+        base_rate_bps: u64,
+        slope1_bps: u64,
+        slope2_bps: u64,
+        optimal_utilization_bps: u64,
+    ) {
+        self.require_owner();
+        if optimal_utilization_bps == 0 || optimal_utilization_bps >= BPS_DIVISOR {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        if base_rate_bps > MAX_BASE_RATE_BPS
+            || slope1_bps > MAX_SLOPE1_BPS
+            || slope2_bps > MAX_SLOPE2_BPS
+        {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.base_rate_bps.set(base_rate_bps);
+        self.slope1_bps.set(slope1_bps);
+        self.slope2_bps.set(slope2_bps);
+        self.optimal_utilization_bps.set(optimal_utilization_bps);
+    }
+
+    /// Set the address receiving protocol fees (owner only).
+    /// Reverts for the zero account or this contract, where fees would be locked.
+    pub fn set_fee_recipient(&mut self, recipient: Address) {
+        self.require_owner();
+        if recipient == Address::from(AccountHash::new([0u8; 32]))
+            || recipient == self.env().self_address()
+        {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        let old_recipient = self.fee_recipient.get();
+        self.fee_recipient.set(recipient);
+        self.emit(events::FeeRecipientChanged {
+            old_recipient,
+            new_recipient: recipient,
+        });
+    }
+
+    /// Mint reserve mCSPR to the fee recipient (owner only).
+    /// There is no per-call destination: use set_fee_recipient to redirect it.
+    pub fn withdraw_reserve(&mut self, amount_wad: U256) {
+        self.require_owner();
+        if amount_wad == U256::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+
+        let reserve = self.reserve_wad.get_or_default();
+        if amount_wad > reserve {
+            self.env().revert(VaultError::InsufficientReserve);
+        }
+        let remaining = reserve - amount_wad;
+        self.reserve_wad.set(remaining);
+
+        let to = self.fee_recipient_or_owner();
+        let mcspr_addr = self.mcspr_address();
+        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        mcspr.mint(to, amount_wad);
+        self.assert_supply_invariant();
+
+        self.emit(events::ReserveWithdrawn {
+            to,
+            amount_wad,
+            remaining_wad: remaining,
+        });
+    }
+
+    /// Set unbonding period for future withdrawal requests (owner only)
+    pub fn set_unbonding_period_secs(&mut self, period_secs: u64) {
+        self.require_owner();
+        self.unbonding_period_secs.set(period_secs);
+    }
+
+    /// Set minimum deposit (motes) required to open a vault (owner only)
+    pub fn set_min_deposit_motes(&mut self, min_motes: U512) {
+        self.require_owner();
+        self.min_deposit_motes.set(min_motes);
+    }
+
+    /// Set minimum time between delegations (owner only); 0 disables the check
+    pub fn set_min_delegate_interval_secs(&mut self, interval_secs: u64) {
+        self.require_owner();
+        self.min_delegate_interval_secs.set(interval_secs);
+    }
+
+    /// Enable or disable same-transaction delegation from deposit (owner only)
+    /// Only enable on chains where delegating within the deposit tx is safe.
+    pub fn set_auto_delegate(&mut self, enabled: bool) {
+        self.require_owner();
+        self.auto_delegate.set(enabled);
+    }
+
+    /// Point the vault at a Styks price feed (owner only)
+    pub fn set_price_oracle(&mut self, oracle: Address, feed_id: String) {
+        self.require_owner();
+        if feed_id.is_empty() {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.price_oracle.set(oracle);
+        self.price_feed_id.set(feed_id);
+    }
+
+    /// Reject oracle prices older than `max_staleness_secs` (owner only); 0 disables the check
+    pub fn set_max_price_staleness_secs(&mut self, max_staleness_secs: u64) {
+        self.require_owner();
+        self.max_price_staleness_secs.set(max_staleness_secs);
+    }
+
+    /// Set the circuit-breaker threshold (owner only); 0 disables it
+    pub fn set_max_price_move_bps(&mut self, max_move_bps: u64) {
+        self.require_owner();
+        self.max_price_move_bps.set(max_move_bps);
+    }
+
+    /// Fetch the CSPR/USD price the vault's USD views and circuit breaker use (anyone may
+    /// call) and emit PriceObserved with its source, for debugging the oracle on testnet.
+    /// Same checks as those reads: reverts with StalePrice, PriceUnavailable or
+    /// PriceMoveExceeded.
+    /// LTV and health checks value collateral 1:1 with debt and don't read this price.
+    pub fn current_price(&mut self) -> U256 {
+        let price_wad = self.checked_price_wad();
+        let source = if self.price_oracle.get().is_some() {
+            PRICE_SOURCE_ORACLE
+        } else {
+            PRICE_SOURCE_MOCK
+        };
+        self.emit(events::PriceObserved { price_wad, source });
+        price_wad
+    }
+
+    /// Read the oracle and compare it with the last accepted price (anyone may call).
+    /// A move above max_price_move_bps pauses borrowing (OP_BORROW) and emits
+    /// CircuitBreakerTripped instead of accepting the price; returns false in that case.
+    /// Otherwise the price becomes last_good_price_wad and true is returned.
+    /// Every other price read applies the same band and reverts with PriceMoveExceeded
+    /// outside it, so no consumer acts on a price the breaker would reject.
+    /// The breaker stays tripped until the owner calls reset_last_good_price and
+    /// set_operations_paused(OP_BORROW, false).
+    pub fn refresh_price(&mut self) -> bool {
+        let price_wad = self.oracle_price_wad();
+        if let Some((last_good, move_bps)) = self.excess_price_move(price_wad) {
+            let paused_ops = self.paused_ops.get_or_default() | OP_BORROW;
+            self.paused_ops.set(paused_ops);
+            self.emit(events::CircuitBreakerTripped {
+                last_good_price_wad: last_good,
+                price_wad,
+                move_bps,
+            });
+            return false;
+        }
+        self.last_good_price_wad.set(price_wad);
+        true
+    }
+
+    /// Accept the current oracle price as last_good_price_wad (owner only),
+    /// e.g. after a genuine move tripped the circuit breaker
+    pub fn reset_last_good_price(&mut self) {
+        self.require_owner();
+        let price_wad = self.oracle_price_wad();
+        self.last_good_price_wad.set(price_wad);
+    }
+
+    /// Reconcile tracked delegation against the chain (owner only).
+    /// Stake that has left the validator is removed from total_delegated and
+    /// recorded as liquid available for pending withdrawals.
+    pub fn sweep_undelegated(&mut self) {
+        self.require_owner();
+        let tracked = self.total_delegated.get_or_default();
+        let actual = self.delegated_amount();
+        if actual >= tracked {
+            return;
+        }
+
+        let swept = tracked - actual;
+        self.total_delegated.set(actual);
+        let undelegating = self.undelegating_motes.get_or_default();
+        self.undelegating_motes.set(undelegating.saturating_sub(swept));
+        let liquid = self.swept_liquid_motes.get_or_default();
+        self.swept_liquid_motes.set(liquid + swept);
+
+        self.emit(events::UndelegatedSwept {
+            amount_motes: swept,
+            total_delegated_motes: actual,
+        });
+    }
+
+    /// Seed `user`'s vault from a prior deployment's state dump (owner only).
+    /// Overwrites any earlier import for the same user and keeps the totals in step.
+    /// Imported debt counts as borrowed principal; no mCSPR is minted and no CSPR
+    /// moves, so the collateral must be funded separately.
+    pub fn migrate_position(
+        &mut self,
+        user: Address,
+        collateral: U512,
+        debt: U256,
+        accrual_ts: u64,
+    ) {
+        self.require_owner();
+        if self.migration_finalized.get_or_default() {
+            self.env().revert(VaultError::MigrationFinalized);
+        }
+
+        let old_collateral = self.collateral_of(user);
+        let total_collateral = self.total_collateral.get_or_default();
+        self.total_collateral.set(total_collateral.saturating_sub(old_collateral) + collateral);
+        self.set_collateral(user, collateral);
+
+        let old_debt = self.debt_principal.get(&user).unwrap_or_default();
+        let total_debt = self.total_debt.get_or_default();
+        self.total_debt.set(self.checked_add_wad(total_debt.saturating_sub(old_debt), debt));
+        self.debt_principal.set(&user, debt);
+        self.borrowed_principal.set(&user, debt);
+        self.last_accrual_ts.set(&user, accrual_ts);
+
+        let status = if collateral.is_zero() && debt.is_zero() {
+            VaultStatus::None
+        } else {
+            VaultStatus::Active
+        };
+        self.vault_status.set(&user, status);
+        if status != VaultStatus::None {
+            self.list_vault(user);
+        }
+        self.refresh_liquidatable(user);
+
+        self.emit(events::PositionMigrated {
+            user,
+            collateral_motes: collateral,
+            debt_wad: debt,
+        });
+    }
+
+    /// Permanently disable migrate_position (owner only)
+    pub fn finalize_migration(&mut self) {
+        self.require_owner();
+        if self.migration_finalized.get_or_default() {
+            self.env().revert(VaultError::MigrationFinalized);
+        }
+        self.migration_finalized.set(true);
+        self.emit(events::MigrationFinalized {
+            by: self.env().caller(),
+        });
+    }
+
+    /// Permanently disable borrowing ahead of a move to a new deployment (owner only).
+    /// Repay, withdraw, finalize and liquidation keep working so positions can wind down.
+    pub fn retire(&mut self) {
+        self.require_owner();
+        if self.retired.get_or_default() {
+            self.env().revert(VaultError::Retired);
+        }
+        self.retired.set(true);
+        self.emit(events::VaultRetired {
+            by: self.env().caller(),
+        });
+    }
+
+    /// Migrate storage written by an older release up to STORAGE_VERSION (owner only).
+    /// Call once after upgrading the contract code; reverts with StorageVersionCurrent
+    /// when there is nothing to migrate.
+    pub fn on_upgrade(&mut self) {
+        self.require_owner();
+        let from_version = self.storage_version();
+        if from_version >= STORAGE_VERSION {
+            self.env().revert(VaultError::StorageVersionCurrent);
+        }
+        for version in from_version..STORAGE_VERSION {
+            self.migrate_storage_from(version);
+        }
+        self.storage_version.set(STORAGE_VERSION);
+        self.emit(events::StorageUpgraded {
+            from_version,
+            to_version: STORAGE_VERSION,
+        });
+    }
+
+    /// Mark storage as written by an older layout so on_upgrade replays its migrations
+    /// (owner only), e.g. after importing positions from a pre-versioning deployment
+    pub fn set_storage_version(&mut self, version: u32) {
+        self.require_owner();
+        if version >= STORAGE_VERSION {
+            self.env().revert(VaultError::InvalidConfig);
+        }
+        self.storage_version.set(version);
+    }
+
+    /// Write down collateral after a validator slash (owner only).
+    /// A shortfall of on-chain stake below tracked stake (excluding requested
+    /// undelegations) larger than dust is taken from every vault pro-rata by lowering
+    /// collateral_index. Run before sweep_undelegated, which would otherwise count
+    /// the shortfall as returned stake. Reverts with SlashingExceedsCollateral if the
+    /// loss would wipe out all collateral, which needs a manual migration instead.
+    pub fn apply_slashing(&mut self) {
+        self.require_owner();
+        let undelegating = self.undelegating_motes.get_or_default();
+        let total_delegated = self.total_delegated.get_or_default();
+        let tracked = total_delegated.saturating_sub(undelegating);
+        let actual = self.delegated_amount();
+        if actual + U512::from(SLASHING_DUST_MOTES) >= tracked {
+            return;
+        }
+
+        let loss = tracked - actual;
+        let total_collateral = self.total_collateral.get_or_default();
+        let haircut = loss.min(total_collateral);
+        if haircut > U512::zero() {
+            let index = self.collateral_index();
+            let new_index = slashed_collateral_index(index, total_collateral, haircut)
+                .unwrap_or_else(|| self.env().revert(VaultError::SlashingExceedsCollateral));
+            self.collateral_index.set(new_index);
+            self.total_collateral.set(total_collateral - haircut);
+        }
+        self.total_delegated.set(actual + undelegating);
+        for key in self.validators() {
+            let validator_pk = self.parse_validator_key(&key);
+            let on_chain = self.env().delegated_amount(validator_pk);
+            self.validator_bonded.set(&key, on_chain);
+        }
+
+        self.emit(events::SlashingApplied {
+            loss_motes: loss,
+            collateral_index: self.collateral_index(),
+        });
+    }
+
+    /// Manually trigger delegation batch (owner only, for testing)
+    pub fn force_delegate(&mut self) {
+        self.require_owner();
+        let pending = self.pending_to_delegate.get_or_default();
+        if pending > U512::zero() {
+            if !self.delegate_interval_elapsed() {
+                self.env().revert(VaultError::DelegationTooSoon);
+            }
+            self.execute_delegate(pending);
+        }
+    }
+
+    /// Delegate exactly `amount` of the batching pool (owner only).
+    /// `amount` must be at least the minimum delegation, no more than pending_to_delegate,
+    /// and covered by liquid CSPR not owed to pending withdrawals. Routed like batch
+    /// delegation under max_validator_stake_bps, to allowlisted validators only.
+    pub fn delegate_exact(&mut self, amount: U512) {
+        self.require_owner();
+        if amount < U512::from(MIN_DELEGATION_MOTES) {
+            self.env().revert(VaultError::BelowMinDelegation);
+        }
+        if amount > self.pending_to_delegate.get_or_default() {
+            self.env().revert(VaultError::ExceedsPendingDelegation);
+        }
+        let available = self
+            .env()
+            .self_balance()
+            .saturating_sub(self.total_pending_withdraw.get_or_default());
+        if amount > available {
+            self.env().revert(VaultError::InsufficientLiquidBalance);
+        }
+        if !self.delegate_interval_elapsed() {
+            self.env().revert(VaultError::DelegationTooSoon);
+        }
+        if self.validator_public_key.get_or_default().is_empty() {
+            self.env().revert(VaultError::InvalidValidatorKey);
+        }
+        match self.pick_validator(amount) {
+            Some(validator_key) => self.delegate_to(validator_key, amount),
+            None => self.env().revert(VaultError::ValidatorStakeCapExceeded),
+        }
+    }
+
+    /// Undelegate `amount` from one registered validator (owner only), e.g. ahead of
+    /// the validator exiting. Independent of pending withdrawals: the stake is tracked
+    /// as undelegating and becomes liquid through sweep_undelegated. Reverts with
+    /// ExceedsValidatorStake above what is bonded to that validator.
+    pub fn force_undelegate(&mut self, validator: String, amount: U512) {
+        self.require_owner();
+        if amount == U512::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+        if self.validator_index(&validator).is_none() {
+            self.env().revert(VaultError::ValidatorNotFound);
+        }
+        let bonded = self.validator_bonded.get(&validator).unwrap_or_default();
+        if amount > bonded {
+            self.env().revert(VaultError::ExceedsValidatorStake);
+        }
+
+        let validator_pk = self.parse_validator_key(&validator);
+        self.env().undelegate(validator_pk, amount);
+        self.validator_bonded.set(&validator, bonded - amount);
+        // total_delegated is reconciled by sweep_undelegated
+        let undelegating = self.undelegating_motes.get_or_default();
+        self.undelegating_motes.set(undelegating + amount);
+
+        self.emit(events::UndelegationRequested {
+            amount_motes: amount,
+        });
+    }
+
+    // ==========================================
+    // Internal Functions
+    // ==========================================
+
+    fn require_not_paused(&self) {
+        if self.paused.get_or_default() {
+            self.env().revert(VaultError::ContractPaused);
+        }
+        // A swap pool calling back in mid-swap would be counted in the balance delta
+        if self.swap_locked.get_or_default() {
+            self.env().revert(VaultError::Reentrant);
+        }
+    }
+
+    /// Revert unless the contract is unpaused and none of the `ops` bits are paused
+    fn require_operation(&self, ops: u8) {
+        self.require_not_paused();
+        if self.paused_ops.get_or_default() & ops != 0 {
+            self.env().revert(VaultError::OperationPaused);
+        }
+    }
+
+    /// Configured mCSPR token; reverts with McsprNotConfigured when unset
+    fn mcspr_address(&self) -> Address {
+        self.mcspr
+            .get()
+            .unwrap_or_else(|| self.env().revert(VaultError::McsprNotConfigured))
+    }
+
+    /// Fee destination; falls back to owner for vaults initialized before fee_recipient existed
+    fn fee_recipient_or_owner(&self) -> Address {
+        self.fee_recipient
+            .get()
+            .or_else(|| self.owner.get())
+            .unwrap_or_else(|| self.env().revert(VaultError::Unauthorized))
+    }
+
+    /// Bump and return the global event sequence number
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = self.event_seq.get_or_default() + 1;
+        self.event_seq.set(seq);
+        seq
+    }
+
+    /// Emit an event that carries no seq field, still advancing the sequence
+    fn emit<T: ToBytes + EventInstance>(&mut self, event: T) {
+        self.next_event_seq();
+        self.env().emit_event(event);
+    }
+
+    /// Store `user`'s collateral against the current slashing index
+    fn set_collateral(&mut self, user: Address, amount: U512) {
+        self.collateral.set(&user, amount);
+        self.user_collateral_index.set(&user, self.collateral_index());
+    }
+
+    fn require_owner(&self) {
+        if self.owner.get() != Some(self.env().caller()) {
+            self.env().revert(VaultError::Unauthorized);
+        }
+    }
+
+    /// Debug-build guard that mint/burn and debt accounting haven't diverged
+    fn assert_supply_invariant(&self) {
+        if cfg!(debug_assertions) && !self.supply_invariant_ok() {
+            self.env().revert(VaultError::SupplyInvariantViolated);
+        }
+    }
+
+    /// Build PositionInfo for `user` from the given collateral and debt
+    fn position_info(
+        &self,
+        user: Address,
+        collateral_motes: U512,
+        debt_wad: U256,
+        status: u8,
+    ) -> PositionInfo {
+        let collateral_wad = self.motes_to_wad(collateral_motes);
+        self.position_info_valued(user, collateral_motes, collateral_wad, debt_wad, status)
+    }
+
+    /// position_info with collateral valued at `collateral_wad` mCSPR instead of 1:1
+    fn position_info_valued(
+        &self,
+        user: Address,
+        collateral_motes: U512,
+        collateral_wad: U256,
+        debt_wad: U256,
+        status: u8,
+    ) -> PositionInfo {
+        let pending_withdraw_motes = self.pending_withdraw.get(&user).unwrap_or_default();
+
+        // Calculate LTV (basis points); unbounded when debt has no collateral
+        let ltv_bps = if collateral_wad == U256::zero() {
+            if debt_wad == U256::zero() {
+                0u64
+            } else {
+                u64::MAX
+            }
+        } else {
+            ratio_bps(debt_wad, collateral_wad)
+        };
+
+        // Calculate health factor (scaled by 10000, >10000 = healthy)
+        let health_factor = if debt_wad == U256::zero() {
+            u64::MAX // Infinite health if no debt
+        } else {
+            ratio_bps(self.max_debt_for(collateral_wad), debt_wad)
+        };
+
+        PositionInfo {
+            collateral_motes,
+            collateral_wad,
+            debt_wad,
+            ltv_bps,
+            health_factor,
+            pending_withdraw_motes,
+            status,
+        }
+    }
+
+    /// Add `amount` of received CSPR to `user`'s collateral and queue it for delegation
+    fn credit_collateral(&mut self, user: Address, amount: U512) {
+        // Update user's collateral
+        let current = self.collateral_of(user);
+        let new_collateral = current + amount;
+        self.set_collateral(user, new_collateral);
+
+        // Update global collateral
+        let total = self.total_collateral.get_or_default();
+        self.total_collateral.set(total + amount);
+
+        // Set vault status to Active if not already
+        let status = self.vault_status.get(&user).unwrap_or_default();
+        if status == VaultStatus::None {
+            self.vault_status.set(&user, VaultStatus::Active);
+            self.last_accrual_ts.set(&user, self.env().get_block_time());
+            self.list_vault(user);
+        }
+
+        self.refresh_liquidatable(user);
+
+        // Batch delegation
+        self.batch_delegate(amount);
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(events::Deposited {
+            user,
+            amount_motes: amount,
+            new_collateral_motes: new_collateral,
+            seq,
+        });
     }
 
-    /// Pause contract (owner only)
-    pub fn pause(&mut self) {
-        self.require_owner();
-        if self.paused.get_or_default() {
-            self.env().revert(VaultError::ContractPaused);
+    /// Add `amount_wad` to `caller`'s debt and mint it to `recipient`.
+    /// Reverts if resulting LTV > ltv_max_bps
+    fn borrow_for(&mut self, caller: Address, amount_wad: U256, recipient: Address) {
+        self.require_operation(OP_BORROW);
+        if self.retired.get_or_default() {
+            self.env().revert(VaultError::Retired);
         }
-        self.paused.set(true);
-        self.env().emit_event(events::Paused {
-            by: self.env().caller(),
+
+        if amount_wad == U256::zero() {
+            self.env().revert(VaultError::ZeroAmount);
+        }
+
+        // Check vault exists and is active
+        let status = self.vault_status.get(&caller).unwrap_or_default();
+        if status == VaultStatus::None {
+            self.env().revert(VaultError::NoVault);
+        }
+        if status == VaultStatus::Withdrawing {
+            self.env().revert(VaultError::WithdrawPending);
+        }
+
+        // Enforce the gap since this user's previous borrow
+        let now = self.env().get_block_time();
+        let cooldown_ms = self.borrow_cooldown_secs().saturating_mul(MILLIS_PER_SECOND);
+        if let Some(last) = self.last_borrow_ts.get(&caller) {
+            if cooldown_ms > 0 && now < last.saturating_add(cooldown_ms) {
+                self.env().revert(VaultError::BorrowCooldownActive);
+            }
+        }
+
+        // Accrue interest first
+        self.accrue_interest(caller);
+
+        // Calculate new debt
+        let current_debt = self.debt_principal.get(&caller).unwrap_or_default();
+        let new_debt = self.checked_add_wad(current_debt, amount_wad);
+
+        // Check LTV constraint
+        let collateral_motes = self.collateral_of(caller);
+        let collateral_wad = self.motes_to_wad(collateral_motes);
+        let max_debt = self.max_debt_for(collateral_wad);
+
+        if new_debt > max_debt {
+            self.env().revert(VaultError::LtvExceeded);
+        }
+        // Health factor after the borrow = max_debt * 10000 / new_debt
+        let min_health_factor = self.min_borrow_health_factor();
+        if max_debt < apply_bps(new_debt, min_health_factor, true) {
+            self.env().revert(VaultError::HealthFactorTooLow);
+        }
+
+        // Update debt
+        if self.first_borrow_ts.get(&caller).is_none() {
+            self.first_borrow_ts.set(&caller, now);
+        }
+        self.last_borrow_ts.set(&caller, now);
+        self.debt_principal.set(&caller, new_debt);
+        let borrowed = self.borrowed_principal.get(&caller).unwrap_or_default();
+        self.borrowed_principal.set(&caller, self.checked_add_wad(borrowed, amount_wad));
+        let borrow_count = self.borrow_count.get(&caller).unwrap_or_default();
+        self.borrow_count.set(&caller, borrow_count + 1);
+        let lifetime = self.lifetime_borrowed_wad.get(&caller).unwrap_or_default();
+        self.lifetime_borrowed_wad.set(&caller, self.checked_add_wad(lifetime, amount_wad));
+        let total = self.total_debt.get_or_default();
+        self.total_debt.set(self.checked_add_wad(total, amount_wad));
+        let epoch = self.current_epoch();
+        let borrowed_in_epoch = self.borrowed_in_epoch.get(&epoch).unwrap_or_default();
+        self.borrowed_in_epoch.set(&epoch, borrowed_in_epoch + amount_wad);
+
+        // Mint mCSPR to recipient
+        let mcspr_addr = self.mcspr_address();
+        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        mcspr.mint(recipient, amount_wad);
+        self.assert_supply_invariant();
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(events::Borrowed {
+            user: caller,
+            amount_wad,
+            new_debt_wad: new_debt,
+            seq,
         });
     }
 
-    /// Unpause contract (owner only)
-    pub fn unpause(&mut self) {
-        self.require_owner();
-        if !self.paused.get_or_default() {
-            self.env().revert(VaultError::ContractPaused);
+    /// Accrue, cap `amount_wad` at the current debt and settle the repayment.
+    fn repay_capped(&mut self, user: Address, amount_wad: U256) {
+        // Check vault exists
+        let status = self.vault_status.get(&user).unwrap_or_default();
+        if status == VaultStatus::None {
+            self.env().revert(VaultError::NoVault);
         }
-        self.paused.set(false);
-        self.env().emit_event(events::Unpaused {
-            by: self.env().caller(),
+
+        // Accrue interest first
+        self.accrue_interest(user);
+
+        // Get current debt and cap repay amount
+        let current_debt = self.debt_principal.get(&user).unwrap_or_default();
+        if current_debt == U256::zero() {
+            self.env().revert(VaultError::InsufficientDebt);
+        }
+
+        let repay_amount = if amount_wad > current_debt {
+            current_debt
+        } else {
+            amount_wad
+        };
+
+        self.settle_repayment(user, current_debt, repay_amount);
+    }
+
+    /// Burn `repay_amount` mCSPR straight from `user`'s balance against their allowance to
+    /// the vault (requires prior approve) and reduce debt. The vault never holds the tokens.
+    /// Checks-effects-interactions: allowance is validated and debt written to its final
+    /// value before the token call, so a reverting burn_from can't leave the two out of step.
+    fn settle_repayment(&mut self, user: Address, current_debt: U256, repay_amount: U256) {
+        let mcspr_addr = self.mcspr_address();
+        let mut mcspr = MCSPRTokenContractRef::new(self.env().clone(), mcspr_addr);
+        let self_address = self.env().self_address();
+
+        // Checks
+        let allowance = mcspr.allowance(user, self_address);
+        if allowance < repay_amount {
+            self.env().revert(VaultError::InsufficientAllowance);
+        }
+
+        // Effects
+        let new_debt = current_debt
+            .checked_sub(repay_amount)
+            .unwrap_or_else(|| self.env().revert(VaultError::InsufficientDebt));
+        self.record_repayment(user, current_debt, repay_amount);
+        self.record_repay_stats(user, repay_amount);
+        self.debt_principal.set(&user, new_debt);
+        self.reduce_total_debt(repay_amount);
+
+        // Interactions: burn from the user, spending their allowance
+        mcspr.burn_from(user, repay_amount);
+        self.assert_supply_invariant();
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(events::Repaid {
+            user,
+            amount_wad: repay_amount,
+            new_debt_wad: new_debt,
+            allowance_before: allowance,
+            allowance_after: mcspr.allowance(user, self_address),
+            seq,
         });
+        self.refresh_liquidatable(user);
     }
 
-    /// Manually trigger delegation batch (owner only, for testing)
-    pub fn force_delegate(&mut self) {
-        self.require_owner();
-        let pending = self.pending_to_delegate.get_or_default();
-        if pending > U512::zero() {
-            self.execute_delegate(pending);
+    /// Split a repayment of `repaid` against `current_debt` into interest and principal.
+    /// Interest is everything above the borrowed principal and is paid off first; only
+    /// the remainder reduces the borrowed principal.
+    fn record_repayment(&mut self, user: Address, current_debt: U256, repaid: U256) {
+        let borrowed = self.borrowed_principal.get(&user).unwrap_or_default();
+        let outstanding_interest = current_debt.saturating_sub(borrowed);
+        let interest_paid = repaid.min(outstanding_interest);
+        self.borrowed_principal
+            .set(&user, borrowed.saturating_sub(repaid - interest_paid));
+        let total = self.total_interest_repaid.get_or_default();
+        self.total_interest_repaid.set(total + interest_paid);
+    }
+
+    /// Largest debt (wad) `collateral_wad` supports at ltv_max_bps
+    fn max_debt_for(&self, collateral_wad: U256) -> U256 {
+        apply_bps(collateral_wad, self.ltv_max_bps(), false)
+    }
+
+    /// Smallest collateral (wad) that keeps `debt_wad` within ltv_max_bps
+    fn min_collateral_for(&self, debt_wad: U256) -> U256 {
+        div_bps(debt_wad, self.ltv_max_bps())
+    }
+
+    /// `a + b`, reverting with Overflow instead of panicking
+    fn checked_add_wad(&self, a: U256, b: U256) -> U256 {
+        a.checked_add(b).unwrap_or_else(|| self.env().revert(VaultError::Overflow))
+    }
+
+    /// Take `amount` off total_debt; per-user debt is the source of truth, so a
+    /// rounding shortfall in the total floors at zero
+    fn reduce_total_debt(&mut self, amount: U256) {
+        let total = self.total_debt.get_or_default();
+        self.total_debt.set(total.saturating_sub(amount));
+    }
+
+    /// Append `user` to vault_owners the first time they open a vault
+    fn list_vault(&mut self, user: Address) {
+        if self.vault_listed.get(&user).unwrap_or_default() {
+            return;
         }
+        let count = self.vault_count.get_or_default();
+        self.vault_owners.set(&count, user);
+        self.vault_listed.set(&user, true);
+        self.vault_count.set(count + 1);
     }
 
-    // ==========================================
-    // Internal Functions
-    // ==========================================
+    /// Count a repayment of `amount` made by `user` towards their activity stats
+    fn record_repay_stats(&mut self, user: Address, amount: U256) {
+        let count = self.repay_count.get(&user).unwrap_or_default();
+        self.repay_count.set(&user, count + 1);
+        let lifetime = self.lifetime_repaid_wad.get(&user).unwrap_or_default();
+        self.lifetime_repaid_wad.set(&user, lifetime + amount);
+        let epoch = self.current_epoch();
+        let repaid_in_epoch = self.repaid_in_epoch.get(&epoch).unwrap_or_default();
+        self.repaid_in_epoch.set(&epoch, repaid_in_epoch + amount);
+    }
 
-    fn require_not_paused(&self) {
-        if self.paused.get_or_default() {
-            self.env().revert(VaultError::ContractPaused);
+    /// Pay out `user`'s pending withdrawal once unbonded and liquid
+    fn finalize_for(&mut self, user: Address) {
+        // Check vault is in withdrawing state
+        let status = self.vault_status.get(&user).unwrap_or_default();
+        if status != VaultStatus::Withdrawing {
+            self.env().revert(VaultError::NoWithdrawPending);
+        }
+
+        // Get pending amount
+        let pending = self.pending_withdraw.get(&user).unwrap_or_default();
+        if pending == U512::zero() {
+            self.env().revert(VaultError::NoWithdrawPending);
+        }
+
+        // Check the user's own stake has had time to unbond
+        let ready_ts = self.withdraw_ready_ts.get(&user).unwrap_or_default();
+        if self.env().get_block_time() < ready_ts {
+            self.env().revert(VaultError::UnbondingNotComplete);
+        }
+
+        // Check liquid balance covers this payout AND everyone else's pending,
+        // so an early finalizer can't consume CSPR earmarked for another user
+        let liquid = self.env().self_balance();
+        let total_pending = self.total_pending_withdraw.get_or_default();
+        let others_pending = total_pending.saturating_sub(pending);
+        if liquid < pending || liquid - pending < others_pending {
+            self.env().revert(VaultError::UnbondingNotComplete);
+        }
+
+        // Transfer CSPR to the recipient chosen at request time
+        let recipient = self.withdraw_recipient_of(user);
+        self.env().transfer_tokens(&recipient, &pending);
+
+        // Clear pending state
+        self.pending_withdraw.set(&user, U512::zero());
+        self.total_pending_withdraw.set(others_pending);
+        let swept = self.swept_liquid_motes.get_or_default();
+        self.swept_liquid_motes.set(swept.saturating_sub(pending));
+
+        // Update vault status; rounding dust left on both sides closes the vault
+        self.accrue_interest(user);
+        let remaining_collateral = self.collateral_of(user);
+        let remaining_debt = self.debt_principal.get(&user).unwrap_or_default();
+
+        if remaining_collateral < U512::from(VAULT_DUST_MOTES)
+            && remaining_debt < U256::from(VAULT_DUST_WAD)
+        {
+            self.clear_vault_dust(user, remaining_collateral, remaining_debt);
+            self.vault_status.set(&user, VaultStatus::None);
+        } else {
+            self.vault_status.set(&user, VaultStatus::Active);
         }
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(events::WithdrawFinalized {
+            user,
+            recipient,
+            amount_motes: pending,
+            seq,
+        });
     }
 
-    fn require_owner(&self) {
-        if self.owner.get() != Some(self.env().caller()) {
-            self.env().revert(VaultError::Unauthorized);
+    /// Epoch containing block time `time_ms`
+    fn epoch_of(&self, time_ms: u64) -> u64 {
+        time_ms / self.epoch_length_secs().saturating_mul(MILLIS_PER_SECOND)
+    }
+
+    /// Zero a closing vault's leftover collateral and debt. Dust collateral stays in the
+    /// contract as surplus; dust debt moves to the reserve so outstanding mCSPR stays backed.
+    fn clear_vault_dust(&mut self, user: Address, collateral: U512, debt: U256) {
+        if !collateral.is_zero() {
+            self.set_collateral(user, U512::zero());
+            let total = self.total_collateral.get_or_default();
+            self.total_collateral.set(total.saturating_sub(collateral));
+        }
+        if !debt.is_zero() {
+            self.debt_principal.set(&user, U256::zero());
+            let total = self.total_debt.get_or_default();
+            self.total_debt.set(total.saturating_sub(debt));
+            let reserve = self.reserve_wad.get_or_default();
+            self.reserve_wad.set(reserve + debt);
+        }
+    }
+
+    /// Move `amount_motes` of `user`'s collateral into pending withdrawal.
+    /// Triggers undelegate if insufficient liquid balance.
+    fn queue_withdraw(&mut self, user: Address, amount_motes: U512, recipient: Address) {
+        // Update collateral (reduce immediately)
+        let current_collateral = self.collateral_of(user);
+        self.set_collateral(user, current_collateral - amount_motes);
+        let total = self.total_collateral.get_or_default();
+        if total >= amount_motes {
+            self.total_collateral.set(total - amount_motes);
+        }
+
+        // Store pending withdrawal
+        self.pending_withdraw.set(&user, amount_motes);
+        self.vault_status.set(&user, VaultStatus::Withdrawing);
+        let unbonding_ms =
+            self.unbonding_period_secs.get_or_default().saturating_mul(MILLIS_PER_SECOND);
+        let ready_ts = self.env().get_block_time().saturating_add(unbonding_ms);
+        self.withdraw_ready_ts.set(&user, ready_ts);
+        self.withdraw_recipient.set(&user, recipient);
+
+        // Check if we need to undelegate (liquid already owed to other pending withdrawals doesn't count)
+        let total_pending = self.total_pending_withdraw.get_or_default();
+        let available = self.env().self_balance().saturating_sub(total_pending);
+        self.total_pending_withdraw.set(total_pending + amount_motes);
+        if available < amount_motes {
+            // Need to undelegate (stake already being undelegated can't be requested again)
+            let undelegating = self.undelegating_motes.get_or_default();
+            let bonded = self.total_delegated.get_or_default().saturating_sub(undelegating);
+            let undelegate_amount = amount_motes.min(bonded);
+
+            if undelegate_amount > U512::zero() {
+                let requested = self.undelegate_in_order(undelegate_amount);
+                if requested > U512::zero() {
+                    // total_delegated is reconciled by sweep_undelegated
+                    self.undelegating_motes.set(undelegating + requested);
+
+                    self.emit(events::UndelegationRequested {
+                        amount_motes: requested,
+                    });
+                }
+            }
+        }
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(events::WithdrawRequested {
+            user,
+            amount_motes,
+            seq,
+        });
+    }
+
+    /// Undelegate up to `amount` walking validators in registry order.
+    /// Returns the amount actually requested.
+    fn undelegate_in_order(&mut self, amount: U512) -> U512 {
+        let mut remaining = amount;
+        for key in self.validators() {
+            if remaining == U512::zero() {
+                break;
+            }
+            let bonded = self.validator_bonded.get(&key).unwrap_or_default();
+            let take = remaining.min(bonded);
+            if take == U512::zero() {
+                continue;
+            }
+            let validator_pk = self.parse_validator_key(&key);
+            self.env().undelegate(validator_pk, take);
+            self.validator_bonded.set(&key, bonded - take);
+            remaining -= take;
         }
+        amount - remaining
+    }
+
+    /// Position of `key` in the validator registry
+    fn validator_index(&self, key: &str) -> Option<u32> {
+        (0..self.validator_count.get_or_default())
+            .find(|i| self.validators.get(i).as_deref() == Some(key))
+    }
+
+    /// Whether `key` is allowlisted and its activation delay has passed
+    fn validator_allowed(&self, key: &str) -> bool {
+        let key = key.to_string();
+        self.validator_allowlist.get(&key).unwrap_or_default()
+            && self.validator_allowed_from.get(&key).unwrap_or_default()
+                <= self.env().get_block_time()
+    }
+
+    fn require_validator_allowed(&self, key: &str) {
+        if !self.validator_allowed(key) {
+            self.env().revert(VaultError::ValidatorNotAllowlisted);
+        }
+    }
+
+    /// Allowlist `key` with no delay: for the deployment validator and migrated registries
+    fn allow_validator_now(&mut self, key: String) {
+        let now = self.env().get_block_time();
+        self.validator_allowlist.set(&key, true);
+        self.validator_allowed_from.set(&key, now);
+        self.emit(events::ValidatorAllowlisted { key, allowed_from: now });
+    }
+
+    /// Append `key` to the registry, enforcing MAX_VALIDATORS
+    fn register_validator(&mut self, key: String) {
+        let count = self.validator_count.get_or_default();
+        if count >= MAX_VALIDATORS {
+            self.env().revert(VaultError::TooManyValidators);
+        }
+        self.validators.set(&count, key.clone());
+        self.validator_count.set(count + 1);
+        self.emit(events::ValidatorAdded { key });
     }
 
     /// Accrue interest for user (updates state)
@@ -896,32 +3432,90 @@ impl Magni {
             return;
         }
 
-        let elapsed = now - last_ts;
-
-        // interest = principal * rate * elapsed / (year * BPS_DIVISOR)
-        // Using checked math to prevent overflow
-        let interest = principal
-            .checked_mul(U256::from(INTEREST_RATE_BPS))
-            .and_then(|x| x.checked_mul(U256::from(elapsed)))
-            .map(|x| x / U256::from(SECONDS_PER_YEAR as u128 * BPS_DIVISOR as u128))
-            .unwrap_or_default();
+        let elapsed = self.chargeable_elapsed(user, last_ts, now);
+        let interest = self.interest_for(principal, elapsed);
 
         if interest > U256::zero() {
-            let new_principal = principal + interest;
+            // Reserve share is carved out of the interest, not charged on top of it
+            let reserve_share = self.reserve_share_of(interest);
+            let borrower_interest = interest - reserve_share;
+
+            let new_principal = principal + borrower_interest;
             self.debt_principal.set(&user, new_principal);
 
             // Update global debt
             let total = self.total_debt.get_or_default();
-            self.total_debt.set(total + interest);
+            self.total_debt.set(total + borrower_interest);
+            let accrued = self.total_interest_accrued.get_or_default();
+            self.total_interest_accrued.set(accrued + borrower_interest);
+
+            // Credit the protocol reserve
+            let reserve = self.reserve_wad.get_or_default();
+            self.reserve_wad.set(reserve + reserve_share);
 
+            let seq = self.next_event_seq();
             self.env().emit_event(events::InterestAccrued {
                 user,
-                interest_wad: interest,
+                interest_wad: borrower_interest,
+                reserve_wad: reserve_share,
                 new_debt_wad: new_principal,
+                seq,
             });
         }
 
         self.last_accrual_ts.set(&user, now);
+        self.refresh_liquidatable(user);
+    }
+
+    /// Emit BecameLiquidatable once when `user` crosses the liquidation threshold;
+    /// re-arm once the position is back under it.
+    fn refresh_liquidatable(&mut self, user: Address) {
+        let ltv_bps = self.ltv_of(user);
+        let liquidatable = ltv_bps > LIQUIDATION_THRESHOLD_BPS;
+        let was = self.was_liquidatable.get(&user).unwrap_or_default();
+        if liquidatable == was {
+            return;
+        }
+        self.was_liquidatable.set(&user, liquidatable);
+        let since = if liquidatable {
+            self.env().get_block_time()
+        } else {
+            0
+        };
+        self.liquidatable_since.set(&user, since);
+        if liquidatable {
+            self.emit(events::BecameLiquidatable { user, ltv_bps });
+        }
+    }
+
+    /// Bring storage from layout `version` to `version + 1`
+    fn migrate_storage_from(&mut self, version: u32) {
+        if version == 0 {
+            // Pre-versioning deployments may never have written the risk parameters
+            if self.ltv_max_bps.get().is_none() {
+                self.ltv_max_bps.set(DEFAULT_LTV_MAX_BPS);
+            }
+            if self.liquidation_grace_secs.get().is_none() {
+                self.liquidation_grace_secs.set(DEFAULT_LIQUIDATION_GRACE_SECS);
+            }
+            if self.close_factor_bps.get().is_none() {
+                self.close_factor_bps.set(DEFAULT_CLOSE_FACTOR_BPS);
+            }
+            if self.liquidation_dust_wad.get().is_none() {
+                self.liquidation_dust_wad.set(U256::from(DEFAULT_LIQUIDATION_DUST_WAD));
+            }
+            if self.collateral_index.get().is_none() {
+                self.collateral_index.set(U512::from(units::WAD));
+            }
+        }
+        if version == 1 {
+            // Validators registered before the allowlist existed stay usable
+            for key in self.validators() {
+                if !self.validator_allowlist.get(&key).unwrap_or_default() {
+                    self.allow_validator_now(key);
+                }
+            }
+        }
     }
 
     /// Calculate debt with interest (read-only, doesn't update state)
@@ -938,25 +3532,68 @@ impl Magni {
             return principal;
         }
 
-        let elapsed = now - last_ts;
-        let interest = principal
-            .checked_mul(U256::from(INTEREST_RATE_BPS))
+        let elapsed = self.chargeable_elapsed(user, last_ts, now);
+        let interest = self.interest_for(principal, elapsed);
+
+        principal + interest - self.reserve_share_of(interest)
+    }
+
+    /// Part of `last_ts..now` that falls after `user`'s interest-free window
+    fn chargeable_elapsed(&self, user: Address, last_ts: u64, now: u64) -> u64 {
+        let start = last_ts.max(self.interest_free_until(user));
+        now.saturating_sub(start)
+    }
+
+    /// Total interest owed on `principal` over `elapsed` block-time units (borrower +
+    /// reserve share) at the current model rate
+    fn interest_for(&self, principal: U256, elapsed: u64) -> U256 {
+        // interest = principal * rate * elapsed / (year * TIME_UNIT_DIVISOR * BPS_DIVISOR)
+        // Using checked math to prevent overflow
+        let year_units = SECONDS_PER_YEAR as u128 * TIME_UNIT_DIVISOR as u128;
+        principal
+            .checked_mul(U256::from(self.current_interest_rate_bps()))
             .and_then(|x| x.checked_mul(U256::from(elapsed)))
-            .map(|x| x / U256::from(SECONDS_PER_YEAR as u128 * BPS_DIVISOR as u128))
-            .unwrap_or_default();
+            .map(|x| x / U256::from(year_units * BPS_DIVISOR as u128))
+            .unwrap_or_default()
+    }
 
-        principal + interest
+    /// Portion of `interest` credited to the protocol reserve
+    fn reserve_share_of(&self, interest: U256) -> U256 {
+        let factor = self.reserve_factor_bps.get_or_default();
+        interest * U256::from(factor) / U256::from(BPS_DIVISOR)
     }
 
     /// Batch delegation - accumulate deposits until MIN_DELEGATION_MOTES
-    /// Note: Does NOT execute delegation immediately. Use force_delegate() to trigger.
+    /// Note: By default does NOT execute delegation immediately. Use force_delegate() to trigger.
     /// This avoids issues with delegation in the same transaction as deposit.
+    /// With auto_delegate enabled, the batch is delegated as soon as it reaches the minimum.
     fn batch_delegate(&mut self, amount: U512) {
         let pending = self.pending_to_delegate.get_or_default();
         let new_pending = pending + amount;
         self.pending_to_delegate.set(new_pending);
-        // Delegation is now triggered manually via force_delegate() by owner
+        // Delegation is otherwise triggered manually via force_delegate() by owner
         // This avoids "DelegationAmountTooSmall" errors from same-tx delegation
+        // Within the minimum interval the batch just keeps accumulating
+        if self.auto_delegate.get_or_default()
+            && new_pending >= U512::from(MIN_DELEGATION_MOTES)
+            && self.delegate_interval_elapsed()
+        {
+            self.execute_delegate(new_pending);
+        }
+    }
+
+    /// True once min_delegate_interval_secs has passed since the last delegation
+    fn delegate_interval_elapsed(&self) -> bool {
+        match self.last_delegate_ts.get() {
+            None => true,
+            Some(last) => {
+                let interval_ms = self
+                    .min_delegate_interval_secs
+                    .get_or_default()
+                    .saturating_mul(MILLIS_PER_SECOND);
+                self.env().get_block_time() >= last.saturating_add(interval_ms)
+            }
+        }
     }
 
     /// Execute delegation to validator
@@ -967,22 +3604,60 @@ impl Magni {
             return;
         }
 
-        // Check liquid balance
+        // Check liquid balance, keeping enough back for pending withdrawals
         let liquid = self.env().self_balance();
-        let delegate_amount = amount.min(liquid);
+        let available = liquid.saturating_sub(self.total_pending_withdraw.get_or_default());
+        let delegate_amount = amount.min(available);
 
         if delegate_amount >= U512::from(MIN_DELEGATION_MOTES) {
-            let validator_pk = self.parse_validator_key(&validator_key);
-            self.env().delegate(validator_pk, delegate_amount);
-
-            let delegated = self.total_delegated.get_or_default();
-            self.total_delegated.set(delegated + delegate_amount);
-            self.pending_to_delegate.set(U512::zero());
+            // No allowed validator with room: the batch waits in pending_to_delegate
+            if let Some(validator_key) = self.pick_validator(delegate_amount) {
+                self.delegate_to(validator_key, delegate_amount);
+            }
+        }
+    }
 
-            self.env().emit_event(events::DelegationBatched {
-                amount_motes: delegate_amount,
-            });
+    /// Validator to receive `amount`: the primary validator if that keeps it within
+    /// max_validator_stake_bps of bonded stake, else the first registered one that stays
+    /// within it. The very first delegation is exempt, as nothing can be spread yet.
+    /// Validators no longer allowlisted are skipped; None if no allowed one has room.
+    fn pick_validator(&self, amount: U512) -> Option<String> {
+        let primary = self.validator_public_key.get_or_default();
+        let validators = self.validators();
+        let total_bonded = validators
+            .iter()
+            .fold(U512::zero(), |sum, key| sum + self.validator_bonded(key.clone()));
+        let mut candidates = core::iter::once(primary)
+            .chain(validators)
+            .filter(|key| self.validator_allowed(key));
+        if total_bonded.is_zero() {
+            return candidates.next();
         }
+        let cap = U512::from(self.max_validator_stake_bps());
+        let total_after = total_bonded + amount;
+        candidates.find(|key| {
+            let bonded_after = self.validator_bonded(key.clone()) + amount;
+            bonded_after * U512::from(BPS_DIVISOR) <= cap * total_after
+        })
+    }
+
+    /// Delegate `amount` of liquid CSPR to `validator_key` and move exactly that much
+    /// out of pending_to_delegate; whatever liquidity fell short stays pending
+    fn delegate_to(&mut self, validator_key: String, amount: U512) {
+        let validator_pk = self.parse_validator_key(&validator_key);
+        self.env().delegate(validator_pk, amount);
+
+        let delegated = self.total_delegated.get_or_default();
+        self.total_delegated.set(delegated + amount);
+        let bonded = self.validator_bonded.get(&validator_key).unwrap_or_default();
+        self.validator_bonded.set(&validator_key, bonded + amount);
+        let pending = self.pending_to_delegate.get_or_default();
+        self.pending_to_delegate.set(pending.saturating_sub(amount));
+        self.last_delegate_ts.set(self.env().get_block_time());
+
+        self.emit(events::DelegationBatched {
+            amount_motes: amount,
+        });
     }
 
     // ==========================================
@@ -990,24 +3665,96 @@ impl Magni {
     // ==========================================
 
     /// Convert motes (U512, 9 decimals) to wad (U256, 18 decimals)
-    /// 1 CSPR (1e9 motes) = 1e18 wad
+    /// Reverts with Overflow if the amount doesn't fit in U256.
     fn motes_to_wad(&self, motes: U512) -> U256 {
-        let motes_u128 = motes.as_u128();
-        U256::from(motes_u128) * U256::from(MOTES_TO_WAD_FACTOR)
+        units::motes_to_wad(motes).unwrap_or_else(|| self.env().revert(VaultError::Overflow))
     }
 
     /// Convert wad (U256, 18 decimals) to motes (U512, 9 decimals)
     /// Round down (conservative for protocol)
-    #[allow(dead_code)]
     fn wad_to_motes(&self, wad: U256) -> U512 {
-        let motes_u256 = wad / U256::from(MOTES_TO_WAD_FACTOR);
-        U512::from(motes_u256.as_u128())
+        units::wad_to_motes(wad)
     }
 
     // ==========================================
     // Validator Key Parsing
     // ==========================================
 
+    /// Fail fast on a malformed key; empty means "no validator set"
+    fn validate_validator_key(&self, validator_key: &str) {
+        if !validator_key.is_empty() {
+            self.parse_validator_key(validator_key);
+        }
+    }
+
+    /// Price for every consumer: oracle_price_wad, refused with PriceMoveExceeded while
+    /// it is outside the circuit-breaker band. Each call is an external read; LTV,
+    /// borrow and withdraw checks are mCSPR-denominated and never read the oracle.
+    fn checked_price_wad(&self) -> U256 {
+        let price_wad = self.oracle_price_wad();
+        if self.excess_price_move(price_wad).is_some() {
+            self.env().revert(VaultError::PriceMoveExceeded);
+        }
+        price_wad
+    }
+
+    /// Last accepted price and the move (bps) away from it, if `price_wad` moved further
+    /// than max_price_move_bps; None while the breaker is disabled or has no reference
+    fn excess_price_move(&self, price_wad: U256) -> Option<(U256, u64)> {
+        let max_move_bps = self.max_price_move_bps();
+        let last_good = self.last_good_price_wad.get()?;
+        if max_move_bps == 0 {
+            return None;
+        }
+        let moved = if price_wad > last_good {
+            price_wad - last_good
+        } else {
+            last_good - price_wad
+        };
+        let move_bps = saturating_u64(moved * U256::from(BPS_DIVISOR) / last_good);
+        (move_bps > max_move_bps).then_some((last_good, move_bps))
+    }
+
+    /// CSPR/USD price (wad) from the oracle TWAP, falling back to the latest price.
+    /// With a staleness limit set, reads the latest timestamped price instead and
+    /// reverts with StalePrice if it was published more than the limit ago.
+    /// Uses the mock price when no oracle is configured; reverts on a missing or zero price.
+    /// Not checked against the circuit breaker: only refresh_price and
+    /// reset_last_good_price read it directly.
+    fn oracle_price_wad(&self) -> U256 {
+        let price = match self.price_oracle.get() {
+            None => Some(mock::get_mock_price()),
+            Some(oracle) => {
+                let feed_id = self.price_feed_id.get_or_default();
+                let oracle = StyksOracleContractRef::new(self.env().clone(), oracle);
+                let max_staleness_secs = self.max_price_staleness_secs();
+                if max_staleness_secs == 0 {
+                    oracle
+                        .get_twap_price(feed_id.clone())
+                        .or_else(|| oracle.get_latest_price(feed_id))
+                } else {
+                    oracle.get_price_with_timestamp(feed_id).map(|(price, updated_at)| {
+                        let max_age_ms = max_staleness_secs.saturating_mul(MILLIS_PER_SECOND);
+                        let now = self.env().get_block_time();
+                        if now.saturating_sub(updated_at) > max_age_ms {
+                            self.env().revert(VaultError::StalePrice);
+                        }
+                        price
+                    })
+                }
+            }
+        };
+        match price {
+            Some(p) if !p.is_zero() => p,
+            _ => self.env().revert(VaultError::PriceUnavailable),
+        }
+    }
+
+    /// USD value (wad) of `cspr_wad` at an already fetched `price_wad`
+    fn usd_value(&self, cspr_wad: U256, price_wad: U256) -> U256 {
+        cspr_wad * price_wad / U256::from(units::WAD)
+    }
+
     fn parse_validator_key(&self, validator_key: &str) -> PublicKey {
         let bytes = self.hex_decode(validator_key);
         if bytes.is_empty() {
@@ -0,0 +1,279 @@
+//! Test doubles for exercising Magni against misbehaving dependencies
+//!
+//! - ObservingBurnToken: mCSPR stand-in that records the vault's view of the debt mid-burn
+//! - MockSwapPool: fixed-rate mCSPR/CSPR pool
+//! - ReentrantSwapPool: 1:1 pool that deposits into the vault mid-swap, both directions
+//! - CountingStyksOracle: fixed-price oracle that emits PriceRead on every read
+//! - MockStyksOracle: deployable Styks oracle with per-feed settable prices
+
+use odra::casper_types::{U256, U512};
+use odra::prelude::*;
+
+use crate::magni::MagniContractRef;
+use crate::tokens::Cep18TokenContractRef;
+use crate::units;
+
+/// Errors raised by the mocks
+#[odra::odra_error]
+pub enum MockError {
+    InsufficientBalance = 61002,
+    InsufficientAllowance = 61003,
+    SlippageExceeded = 61004,
+    McsprNotConfigured = 61005,
+    VaultNotConfigured = 61006,
+}
+
+/// Minimal mCSPR-compatible token whose `burn_from` asks the calling vault for the owner's
+/// debt before burning, so tests can see which state the vault committed before the call
+#[odra::module(errors = MockError)]
+pub struct ObservingBurnToken {
+    total_supply: Var<U256>,
+    balances: Mapping<Address, U256>,
+    allowances: Mapping<(Address, Address), U256>,
+    debt_seen_at_burn: Var<Option<U256>>,
+}
+
+#[odra::module]
+impl ObservingBurnToken {
+    /// Initialize with zero supply
+    pub fn init(&mut self) {
+        self.total_supply.set(U256::zero());
+    }
+
+    /// Total supply
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get_or_default()
+    }
+
+    /// Balance of an address
+    pub fn balance_of(&self, owner: Address) -> U256 {
+        self.balances.get(&owner).unwrap_or_default()
+    }
+
+    /// Allowance from owner to spender
+    pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowances.get(&(owner, spender)).unwrap_or_default()
+    }
+
+    /// Approve spender
+    pub fn approve(&mut self, spender: Address, amount: U256) {
+        let owner = self.env().caller();
+        self.allowances.set(&(owner, spender), amount);
+    }
+
+    /// Transfer from (with allowance)
+    pub fn transfer_from(&mut self, owner: Address, recipient: Address, amount: U256) {
+        let spender = self.env().caller();
+        let allowance = self.allowance(owner, spender);
+        if allowance < amount {
+            self.env().revert(MockError::InsufficientAllowance);
+        }
+        let balance = self.balance_of(owner);
+        if balance < amount {
+            self.env().revert(MockError::InsufficientBalance);
+        }
+        self.allowances.set(&(owner, spender), allowance - amount);
+        self.balances.set(&owner, balance - amount);
+        self.balances.set(&recipient, self.balance_of(recipient) + amount);
+    }
+
+    /// Mint tokens (unrestricted)
+    pub fn mint(&mut self, to: Address, amount: U256) {
+        self.balances.set(&to, self.balance_of(to) + amount);
+        self.total_supply.set(self.total_supply() + amount);
+    }
+
+    /// Burn without allowance checks
+    pub fn burn(&mut self, from: Address, amount: U256) {
+        self.burn_balance(from, amount);
+    }
+
+    /// Burn from allowance, recording the caller's `debt_of(owner)` first
+    pub fn burn_from(&mut self, owner: Address, amount: U256) {
+        let spender = self.env().caller();
+        let debt = MagniContractRef::new(self.env(), spender).debt_of(owner);
+        self.debt_seen_at_burn.set(Some(debt));
+        let allowance = self.allowance(owner, spender);
+        if allowance < amount {
+            self.env().revert(MockError::InsufficientAllowance);
+        }
+        self.allowances.set(&(owner, spender), allowance - amount);
+        self.burn_balance(owner, amount);
+    }
+
+    /// Debt the vault reported during the last `burn_from`
+    pub fn debt_seen_at_burn(&self) -> Option<U256> {
+        self.debt_seen_at_burn.get().flatten()
+    }
+
+    fn burn_balance(&mut self, from: Address, amount: U256) {
+        let balance = self.balance_of(from);
+        if balance < amount {
+            self.env().revert(MockError::InsufficientBalance);
+        }
+        self.balances.set(&from, balance - amount);
+        self.total_supply.set(self.total_supply() - amount);
+    }
+}
+
+/// Fixed-rate swap pool: pays `rate_bps` of the 1:1 value in either direction
+#[odra::module(errors = MockError)]
+pub struct MockSwapPool {
+    rate_bps: Var<u64>,
+    mcspr: Var<Address>,
+}
+
+#[odra::module]
+impl MockSwapPool {
+    /// Initialize with the payout rate (10_000 = 1 CSPR per mCSPR) and the mCSPR token
+    pub fn init(&mut self, rate_bps: u64, mcspr: Address) {
+        self.rate_bps.set(rate_bps);
+        self.mcspr.set(mcspr);
+    }
+
+    /// Add CSPR liquidity
+    #[odra(payable)]
+    pub fn fund(&mut self) {}
+
+    /// Pay CSPR for mCSPR already pushed to the pool
+    pub fn swap_mcspr_for_cspr(&mut self, amount_in: U256, min_cspr_out: U512) -> U512 {
+        let out = units::wad_to_motes(amount_in) * U512::from(self.rate_bps.get_or_default())
+            / U512::from(10_000u64);
+        if out < min_cspr_out {
+            self.env().revert(MockError::SlippageExceeded);
+        }
+        self.env().transfer_tokens(&self.env().caller(), &out);
+        out
+    }
+
+    /// Pay mCSPR from the pool's inventory for the attached CSPR
+    #[odra(payable)]
+    pub fn swap_cspr_for_mcspr(&mut self, min_mcspr_out: U256) -> U256 {
+        let value_wad = units::motes_to_wad(self.env().attached_value()).unwrap_or_default();
+        let out = value_wad * U256::from(self.rate_bps.get_or_default()) / U256::from(10_000u64);
+        if out < min_mcspr_out {
+            self.env().revert(MockError::SlippageExceeded);
+        }
+        let mcspr = self
+            .mcspr
+            .get()
+            .unwrap_or_else(|| self.env().revert(MockError::McsprNotConfigured));
+        Cep18TokenContractRef::new(self.env().clone(), mcspr).transfer(self.env().caller(), out);
+        out
+    }
+}
+
+/// 1:1 swap pool that calls back into the vault with a deposit before returning
+#[odra::module(errors = MockError)]
+pub struct ReentrantSwapPool {
+    vault: Var<Address>,
+}
+
+#[odra::module]
+impl ReentrantSwapPool {
+    /// Initialize with the vault to re-enter
+    pub fn init(&mut self, vault: Address) {
+        self.vault.set(vault);
+    }
+
+    /// Add CSPR liquidity
+    #[odra(payable)]
+    pub fn fund(&mut self) {}
+
+    /// Pay CSPR for mCSPR, then deposit the same amount into the vault from the pool
+    pub fn swap_mcspr_for_cspr(&mut self, amount_in: U256, _min_cspr_out: U512) -> U512 {
+        let out = units::wad_to_motes(amount_in);
+        self.env().transfer_tokens(&self.env().caller(), &out);
+        self.reenter(out);
+        out
+    }
+
+    /// Deposit the attached CSPR straight back into the vault instead of paying mCSPR
+    #[odra(payable)]
+    pub fn swap_cspr_for_mcspr(&mut self, _min_mcspr_out: U256) -> U256 {
+        let value = self.env().attached_value();
+        self.reenter(value);
+        U256::zero()
+    }
+
+    fn reenter(&self, motes: U512) {
+        let vault = self
+            .vault
+            .get()
+            .unwrap_or_else(|| self.env().revert(MockError::VaultNotConfigured));
+        MagniContractRef::new(self.env().clone(), vault)
+            .with_tokens(motes)
+            .deposit();
+    }
+}
+
+/// Emitted by CountingStyksOracle for every price read
+#[odra::event]
+pub struct PriceRead {
+    pub feed_id: String,
+}
+
+/// Styks-compatible oracle returning a fixed price. The getters keep the `&self`
+/// signatures of the StyksOracle interface, so reads are counted as PriceRead events.
+#[odra::module(events = [PriceRead])]
+pub struct CountingStyksOracle {
+    price: Var<U256>,
+}
+
+#[odra::module]
+impl CountingStyksOracle {
+    /// Initialize with the price (wad) returned for every feed
+    pub fn init(&mut self, price: U256) {
+        self.price.set(price);
+    }
+
+    pub fn get_twap_price(&self, feed_id: String) -> Option<U256> {
+        self.env().emit_event(PriceRead { feed_id });
+        self.price.get()
+    }
+
+    pub fn get_latest_price(&self, feed_id: String) -> Option<U256> {
+        self.env().emit_event(PriceRead { feed_id });
+        self.price.get()
+    }
+
+    pub fn get_price_with_timestamp(&self, feed_id: String) -> Option<(U256, u64)> {
+        self.env().emit_event(PriceRead { feed_id });
+        self.price.get().map(|price| (price, self.env().get_block_time()))
+    }
+}
+
+/// Deployable Styks oracle: TWAP and latest price are whatever set_price stored
+#[odra::module]
+pub struct MockStyksOracle {
+    prices: Mapping<String, U256>,
+    updated_at: Mapping<String, u64>,
+}
+
+#[odra::module]
+impl MockStyksOracle {
+    /// Set the price (wad) reported for `feed_id`, stamped with the current block time
+    pub fn set_price(&mut self, feed_id: String, price: U256) {
+        let now = self.env().get_block_time();
+        self.set_price_at(feed_id, price, now);
+    }
+
+    /// Set the price (wad) reported for `feed_id` as if published at `timestamp` (ms)
+    pub fn set_price_at(&mut self, feed_id: String, price: U256, timestamp: u64) {
+        self.prices.set(&feed_id, price);
+        self.updated_at.set(&feed_id, timestamp);
+    }
+
+    pub fn get_twap_price(&self, feed_id: String) -> Option<U256> {
+        self.prices.get(&feed_id)
+    }
+
+    pub fn get_latest_price(&self, feed_id: String) -> Option<U256> {
+        self.prices.get(&feed_id)
+    }
+
+    pub fn get_price_with_timestamp(&self, feed_id: String) -> Option<(U256, u64)> {
+        let price = self.prices.get(&feed_id)?;
+        Some((price, self.updated_at.get(&feed_id).unwrap_or_default()))
+    }
+}
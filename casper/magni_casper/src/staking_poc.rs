@@ -39,6 +39,28 @@ pub mod events {
         pub validator: String,
         pub amount: U512,
     }
+
+    #[odra::event]
+    pub struct RewardsWithdrawn {
+        pub to: Address,
+        pub amount: U512,
+    }
+
+    #[odra::event]
+    pub struct UnstakedWithdrawn {
+        pub to: Address,
+        pub amount: U512,
+    }
+
+    #[odra::event]
+    pub struct Paused {
+        pub by: Address,
+    }
+
+    #[odra::event]
+    pub struct Unpaused {
+        pub by: Address,
+    }
 }
 
 /// Errors for staking operations
@@ -49,26 +71,49 @@ pub enum StakingPocError {
     DelegationFailed = 3,
     UndelegationFailed = 4,
     InsufficientDelegation = 5,
+    AlreadyInitialized = 6,
+    Paused = 7,
+    Unauthorized = 8,
+    ExceedsPendingUndelegation = 9,
+    UnstakeNotReturned = 10,
 }
 
 /// StakingPoC: Minimal contract to test native CSPR delegation from a stored contract
 ///
 /// This contract exists purely for research purposes (T11) to determine if
 /// Casper 2.0 / Odra 2.4 allows WASM contracts to delegate to validators.
-#[odra::module(events = [events::Staked, events::UnstakeRequested, events::DelegatedAmountQueried])]
+#[odra::module(events = [
+    events::Staked,
+    events::UnstakeRequested,
+    events::DelegatedAmountQueried,
+    events::RewardsWithdrawn,
+    events::UnstakedWithdrawn,
+    events::Paused,
+    events::Unpaused
+])]
 pub struct StakingPoC {
     /// Owner of the contract (for restricted operations)
     owner: Var<Address>,
     /// Total amount delegated through this contract (tracking)
     total_delegated: Var<U512>,
+    /// Set once init has run
+    initialized: Var<bool>,
+    /// Halts stake/request_unstake while set
+    paused: Var<bool>,
+    /// Undelegated principal that is (or will be) back in the purse; never withdrawn as rewards
+    pending_undelegations: Var<U512>,
 }
 
 #[odra::module]
 impl StakingPoC {
     /// Initialize the StakingPoC contract
     pub fn init(&mut self) {
+        if self.initialized.get_or_default() {
+            self.env().revert(StakingPocError::AlreadyInitialized);
+        }
         self.owner.set(self.env().caller());
         self.total_delegated.set(U512::zero());
+        self.initialized.set(true);
     }
 
     /// Parse a validator public key from hex string
@@ -148,6 +193,7 @@ impl StakingPoC {
     /// Minimum delegation on Casper is 500 CSPR = 500_000_000_000 motes
     #[odra(payable)]
     pub fn stake(&mut self, validator_public_key: String) {
+        self.require_not_paused();
         let amount = self.env().attached_value();
 
         // Validate amount (minimum 500 CSPR = 500e9 motes)
@@ -179,7 +225,9 @@ impl StakingPoC {
         });
     }
 
-    /// Request to unstake CSPR from a validator
+    /// Request to unstake CSPR from a validator (owner only)
+    ///
+    /// Stake is pooled, so only the owner may undelegate it.
     ///
     /// # Arguments
     /// * `validator_public_key` - The validator's public key (hex string)
@@ -187,7 +235,10 @@ impl StakingPoC {
     ///
     /// # Note
     /// Undelegation has a ~14 hour delay (7 eras) on Casper.
+    /// Reverts with InsufficientDelegation if `amount` exceeds total_delegated.
     pub fn request_unstake(&mut self, validator_public_key: String, amount: U512) {
+        self.require_owner();
+        self.require_not_paused();
         if amount == U512::zero() {
             self.env().revert(StakingPocError::ZeroAmount);
         }
@@ -199,14 +250,19 @@ impl StakingPoC {
 
         let caller = self.env().caller();
 
+        // Fail with a clear error instead of a chain-level undelegate failure
+        let current = self.total_delegated.get_or_default();
+        if amount > current {
+            self.env().revert(StakingPocError::InsufficientDelegation);
+        }
+
         // Attempt to undelegate via Odra's staking API
         self.env().undelegate(validator_pk, amount);
 
         // Update tracking
-        let current = self.total_delegated.get_or_default();
-        if current >= amount {
-            self.total_delegated.set(current - amount);
-        }
+        self.total_delegated.set(current - amount);
+        let pending = self.pending_undelegations.get_or_default();
+        self.pending_undelegations.set(pending + amount);
 
         // Emit event
         self.env().emit_event(events::UnstakeRequested {
@@ -249,6 +305,111 @@ impl StakingPoC {
     pub fn owner(&self) -> Option<Address> {
         self.owner.get()
     }
+
+    /// Get the undelegated principal reserved from reward withdrawals
+    pub fn pending_undelegations(&self) -> U512 {
+        self.pending_undelegations.get_or_default()
+    }
+
+    /// Get the liquid CSPR that can be withdrawn as rewards
+    ///
+    /// Delegated funds are not in the purse, so everything liquid counts except
+    /// principal released by request_unstake and not yet paid out by withdraw_unstaked.
+    pub fn withdrawable_rewards(&self) -> U512 {
+        self.env()
+            .self_balance()
+            .saturating_sub(self.pending_undelegations.get_or_default())
+    }
+
+    /// Transfer the withdrawable rewards to `to` (owner only)
+    pub fn withdraw_rewards(&mut self, to: Address) {
+        self.require_owner();
+        let amount = self.withdrawable_rewards();
+        if amount == U512::zero() {
+            self.env().revert(StakingPocError::ZeroAmount);
+        }
+
+        self.env().transfer_tokens(&to, &amount);
+        self.env().emit_event(events::RewardsWithdrawn { to, amount });
+    }
+
+    /// Transfer `amount` of returned unstaked principal to `to` (owner only)
+    ///
+    /// Releases it from pending_undelegations. Reverts with UnstakeNotReturned
+    /// while the purse does not hold it yet.
+    pub fn withdraw_unstaked(&mut self, to: Address, amount: U512) {
+        self.require_owner();
+        if amount == U512::zero() {
+            self.env().revert(StakingPocError::ZeroAmount);
+        }
+        let pending = self.pending_undelegations.get_or_default();
+        if amount > pending {
+            self.env().revert(StakingPocError::ExceedsPendingUndelegation);
+        }
+        if amount > self.env().self_balance() {
+            self.env().revert(StakingPocError::UnstakeNotReturned);
+        }
+
+        self.pending_undelegations.set(pending - amount);
+        self.env().transfer_tokens(&to, &amount);
+        self.env().emit_event(events::UnstakedWithdrawn { to, amount });
+    }
+
+    /// Halt stake and request_unstake (owner only)
+    pub fn pause(&mut self) {
+        self.require_owner();
+        if self.paused.get_or_default() {
+            self.env().revert(StakingPocError::Paused);
+        }
+        self.paused.set(true);
+        self.env().emit_event(events::Paused {
+            by: self.env().caller(),
+        });
+    }
+
+    /// Resume stake and request_unstake (owner only)
+    pub fn unpause(&mut self) {
+        self.require_owner();
+        if !self.paused.get_or_default() {
+            self.env().revert(StakingPocError::Paused);
+        }
+        self.paused.set(false);
+        self.env().emit_event(events::Unpaused {
+            by: self.env().caller(),
+        });
+    }
+
+    /// Check whether the contract is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.get_or_default()
+    }
+
+    /// Check whether init has run
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.get_or_default()
+    }
+
+    /// Get the crate version this PoC was built from
+    pub fn version(&self) -> String {
+        crate::VERSION.to_string()
+    }
+
+    /// Get the contract discriminator
+    pub fn contract_kind(&self) -> String {
+        "staking_poc".to_string()
+    }
+
+    fn require_owner(&self) {
+        if self.owner.get() != Some(self.env().caller()) {
+            self.env().revert(StakingPocError::Unauthorized);
+        }
+    }
+
+    fn require_not_paused(&self) {
+        if self.paused.get_or_default() {
+            self.env().revert(StakingPocError::Paused);
+        }
+    }
 }
 
 // Tests moved to tests/ directory for better separation
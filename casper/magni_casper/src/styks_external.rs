@@ -34,6 +34,16 @@ pub trait StyksOracle {
     /// # Returns
     /// The latest price as U256 (18 decimals), or None if not available
     fn get_latest_price(&self, feed_id: String) -> Option<U256>;
+
+    /// Get the latest price together with the time it was published
+    ///
+    /// # Arguments
+    /// * `feed_id` - The unique identifier for the price feed
+    ///
+    /// # Returns
+    /// The latest price as U256 (18 decimals) and its update time as block time
+    /// (milliseconds), or None if not available
+    fn get_price_with_timestamp(&self, feed_id: String) -> Option<(U256, u64)>;
 }
 
 /// Helper to create a Styks Oracle reference from a package hash
@@ -0,0 +1,31 @@
+//! Swap Pool External Contract Interface
+//!
+//! Interface Magni uses for one-transaction leverage loops against an
+//! mCSPR/CSPR pool. mCSPR input is pushed to the pool before the call;
+//! CSPR input is attached to the call.
+
+use odra::prelude::*;
+use odra::casper_types::{U256, U512};
+
+/// mCSPR/CSPR swap pool
+#[odra::external_contract]
+pub trait SwapPool {
+    /// Swap `amount_in` mCSPR, already transferred to the pool, for CSPR sent to the caller
+    ///
+    /// # Arguments
+    /// * `amount_in` - mCSPR (wad) the caller pushed to the pool
+    /// * `min_cspr_out` - Revert if fewer motes would be paid out
+    ///
+    /// # Returns
+    /// CSPR paid to the caller (motes)
+    fn swap_mcspr_for_cspr(&mut self, amount_in: U256, min_cspr_out: U512) -> U512;
+
+    /// Swap the attached CSPR for mCSPR transferred to the caller (payable)
+    ///
+    /// # Arguments
+    /// * `min_mcspr_out` - Revert if less mCSPR (wad) would be paid out
+    ///
+    /// # Returns
+    /// mCSPR paid to the caller (wad)
+    fn swap_cspr_for_mcspr(&mut self, min_mcspr_out: U256) -> U256;
+}
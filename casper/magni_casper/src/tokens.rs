@@ -2,9 +2,14 @@
 //!
 //! - tCSPR: Test token with faucet mint capability
 //! - mCSPR: Synthetic token - only Magni (minter) can mint/burn
+//!
+//! Both tokens reject self-transfers (`transfer` to the caller, `transfer_from`
+//! with `owner == recipient`) with `CannotTargetSelfUser`.
 
 use alloc::string::String;
-use odra::casper_types::U256;
+use alloc::vec::Vec;
+use odra::casper_types::bytesrepr::{Bytes, ToBytes};
+use odra::casper_types::{PublicKey, U256};
 use odra::prelude::*;
 use odra_modules::cep18::events::{
     Burn, DecreaseAllowance, IncreaseAllowance, Mint, SetAllowance, Transfer, TransferFrom,
@@ -29,8 +34,9 @@ fn extract_hash_hex(debug_str: &str) -> Option<String> {
     None
 }
 
-/// Additional events for mCSPR
+/// Additional token events
 pub mod events {
+    use odra::casper_types::U256;
     use odra::prelude::*;
 
     #[odra::event]
@@ -38,6 +44,59 @@ pub mod events {
         pub old_minter: Option<Address>,
         pub new_minter: Address,
     }
+
+    #[odra::event]
+    pub struct MinterProposed {
+        pub proposed_minter: Address,
+        /// Block time (ms) from which apply_minter succeeds
+        pub ready_at: u64,
+    }
+
+    #[odra::event]
+    pub struct MinterTimelockChanged {
+        pub old_timelock_secs: u64,
+        pub new_timelock_secs: u64,
+    }
+
+    #[odra::event]
+    pub struct MinterTimelockDecreaseQueued {
+        pub new_timelock_secs: u64,
+        /// Block time (ms) from which apply_minter_timelock_secs succeeds
+        pub ready_at: u64,
+    }
+
+    #[odra::event]
+    pub struct AdminTransferStarted {
+        pub admin: Address,
+        pub pending_admin: Address,
+    }
+
+    #[odra::event]
+    pub struct AdminTransferred {
+        pub old_admin: Option<Address>,
+        pub new_admin: Address,
+    }
+
+    #[odra::event]
+    pub struct MetadataChanged {
+        pub name: String,
+        pub symbol: String,
+        pub decimals: u8,
+    }
+
+    #[odra::event]
+    pub struct FaucetMinted {
+        pub to: Address,
+        pub amount: U256,
+    }
+}
+
+/// Minimal CEP-18 interface for moving arbitrary tokens held by a contract
+#[odra::external_contract]
+pub trait Cep18Token {
+    fn balance_of(&self, owner: Address) -> U256;
+    fn transfer(&mut self, recipient: Address, amount: U256);
+    fn transfer_from(&mut self, owner: Address, recipient: Address, amount: U256);
 }
 
 /// Errors for token operations (aligned with CEP-18 codes where applicable)
@@ -47,8 +106,22 @@ pub enum TokenError {
     InsufficientAllowance = 60002,
     CannotTargetSelfUser = 60003,
     Unauthorized = 60004,
+    PermitExpired = 60005,
+    InvalidSignature = 60006,
+    SupplyExists = 60007,
+    AlreadyInitialized = 60008,
+    FaucetCapReached = 60009,
+    MinterTimelocked = 60010,
+    NoMinterProposed = 60011,
+    NoTimelockQueued = 60012,
 }
 
+/// Domain tag prefixed to every mCSPR permit payload
+const PERMIT_DOMAIN: &[u8] = b"mCSPR permit v1";
+
+/// Odra block time is reported in milliseconds
+const MILLIS_PER_SECOND: u64 = 1000;
+
 /// tCSPR: Test CSPR token with faucet mint capability
 /// Anyone can call faucet_mint to get test tokens
 #[odra::module(
@@ -59,7 +132,9 @@ pub enum TokenError {
         IncreaseAllowance,
         DecreaseAllowance,
         Transfer,
-        TransferFrom
+        TransferFrom,
+        events::MetadataChanged,
+        events::FaucetMinted
     ],
     errors = TokenError
 )]
@@ -70,18 +145,74 @@ pub struct TCSPRToken {
     total_supply: SubModule<Cep18TotalSupplyStorage>,
     balances: SubModule<Cep18BalancesStorage>,
     allowances: SubModule<Cep18AllowancesStorage>,
+    admin: Var<Address>,
+    initialized: Var<bool>,
+    /// Lifetime amount handed out by faucet_mint
+    total_faucet_minted: Var<U256>,
+    /// Lifetime faucet limit; unset means uncapped
+    faucet_total_cap: Var<U256>,
 }
 
 #[odra::module]
 impl TCSPRToken {
     /// Initialize the token
     pub fn init(&mut self) {
+        if self.initialized.get_or_default() {
+            self.env().revert(TokenError::AlreadyInitialized);
+        }
         self.name.set("Test CSPR".to_string());
         self.symbol.set("tCSPR".to_string());
         self.decimals.set(18u8);
         self.total_supply.set(U256::zero());
         self.allowances.init();
         self.balances.init();
+        self.admin.set(self.env().caller());
+        self.initialized.set(true);
+    }
+
+    /// Check whether init has run
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.get_or_default()
+    }
+
+    /// Get current admin (the deployer)
+    pub fn admin(&self) -> Option<Address> {
+        self.admin.get()
+    }
+
+    /// Rename the token (only admin can call)
+    pub fn set_name(&mut self, name: String) {
+        self.require_admin();
+        self.name.set(name);
+        self.emit_metadata_changed();
+    }
+
+    /// Change the token symbol (only admin can call)
+    pub fn set_symbol(&mut self, symbol: String) {
+        self.require_admin();
+        self.symbol.set(symbol);
+        self.emit_metadata_changed();
+    }
+
+    /// Change decimals (only admin can call); only while no tokens exist, since
+    /// it would change the meaning of every balance
+    pub fn set_decimals(&mut self, decimals: u8) {
+        self.require_admin();
+        if !self.total_supply.get().is_zero() {
+            self.env().revert(TokenError::SupplyExists);
+        }
+        self.decimals.set(decimals);
+        self.emit_metadata_changed();
+    }
+
+    /// Crate version this contract was built from
+    pub fn version(&self) -> String {
+        crate::VERSION.to_string()
+    }
+
+    /// Contract discriminator, constant across versions
+    pub fn contract_kind(&self) -> String {
+        "tcspr_token".to_string()
     }
 
     /// Token name
@@ -199,9 +330,54 @@ impl TCSPRToken {
         });
     }
 
-    /// Faucet mint - anyone can call to get test tokens
+    /// Faucet mint - anyone can call to get test tokens, until the lifetime cap is reached.
+    /// A total past U256::MAX counts as reaching the cap.
     pub fn faucet_mint(&mut self, to: Address, amount: U256) {
+        let minted = self
+            .total_faucet_minted
+            .get_or_default()
+            .checked_add(amount)
+            .unwrap_or_else(|| self.env().revert(TokenError::FaucetCapReached));
+        if let Some(cap) = self.faucet_total_cap.get() {
+            if minted > cap {
+                self.env().revert(TokenError::FaucetCapReached);
+            }
+        }
+        self.total_faucet_minted.set(minted);
         self.raw_mint(&to, &amount);
+        self.env().emit_event(events::FaucetMinted { to, amount });
+    }
+
+    /// Total amount minted through the faucet
+    pub fn total_faucet_minted(&self) -> U256 {
+        self.total_faucet_minted.get_or_default()
+    }
+
+    /// Lifetime faucet limit, if one is set
+    pub fn faucet_total_cap(&self) -> Option<U256> {
+        self.faucet_total_cap.get()
+    }
+
+    /// Cap the total faucet_mint can ever hand out (only admin can call)
+    pub fn set_faucet_total_cap(&mut self, cap: U256) {
+        self.require_admin();
+        self.faucet_total_cap.set(cap);
+    }
+
+    // Revert unless caller is admin
+    fn require_admin(&self) {
+        if self.admin.get() != Some(self.env().caller()) {
+            self.env().revert(TokenError::Unauthorized);
+        }
+    }
+
+    // Emit the full metadata after a change
+    fn emit_metadata_changed(&self) {
+        self.env().emit_event(events::MetadataChanged {
+            name: self.name.get(),
+            symbol: self.symbol.get(),
+            decimals: self.decimals.get(),
+        });
     }
 
     // Internal transfer
@@ -251,25 +427,105 @@ impl TCSPRToken {
         DecreaseAllowance,
         Transfer,
         TransferFrom,
-        events::MinterSet
+        events::MinterSet,
+        events::MinterProposed,
+        events::MinterTimelockChanged,
+        events::MinterTimelockDecreaseQueued,
+        events::AdminTransferStarted,
+        events::AdminTransferred,
+        events::MetadataChanged
     ],
     errors = TokenError
 )]
 pub struct MCSPRToken {
     token: SubModule<Cep18>,
     minter: Var<Address>,
+    /// Delay between propose_minter and apply_minter; 0 keeps rotation instant
+    minter_timelock_secs: Var<u64>,
+    /// Candidate recorded by propose_minter and the block time (ms) it becomes applicable
+    proposed_minter: Var<Option<(Address, u64)>>,
+    /// Lower delay queued by set_minter_timelock_secs and the block time (ms) it may apply
+    queued_minter_timelock: Var<Option<(u64, u64)>>,
+    admin: Var<Address>,
+    pending_admin: Var<Option<Address>>,
+    /// Same CEP-18 named dictionary as `token`'s allowances; used by `permit`
+    allowances: SubModule<Cep18AllowancesStorage>,
+    /// Per-owner permit nonce
+    permit_nonces: Mapping<Address, u64>,
+    /// Hash of the permit domain tag and this contract's address, fixed at init
+    domain_separator: Var<[u8; 32]>,
+    /// Same CEP-18 named keys as `token`'s metadata; used by the metadata setters
+    name: SubModule<Cep18NameStorage>,
+    symbol: SubModule<Cep18SymbolStorage>,
+    decimals: SubModule<Cep18DecimalsStorage>,
+    /// Accounts with a nonzero balance
+    holder_count: Var<u64>,
+    initialized: Var<bool>,
 }
 
 #[odra::module]
 impl MCSPRToken {
-    /// Initialize the token with minter address
+    /// Initialize the token with minter address; the deployer becomes admin.
+    /// `minter` may be a contract address: when the vault's address is already known
+    /// (e.g. redeploying mCSPR for a live vault) pass it here and skip set_minter.
     pub fn init(&mut self, minter: Address) {
+        if self.initialized.get_or_default() {
+            self.env().revert(TokenError::AlreadyInitialized);
+        }
         self.token.init("mCSPR".to_string(), "Magni CSPR".to_string(), 18u8, U256::zero());
         self.minter.set(minter);
+        self.domain_separator.set(self.compute_domain_separator());
+        let admin = self.env().caller();
+        self.admin.set(admin);
         self.env().emit_event(events::MinterSet {
             old_minter: None,
             new_minter: minter,
         });
+        self.env().emit_event(events::AdminTransferred {
+            old_admin: None,
+            new_admin: admin,
+        });
+        self.initialized.set(true);
+    }
+
+    /// Check whether init has run
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.get_or_default()
+    }
+
+    /// Get current admin
+    pub fn admin(&self) -> Option<Address> {
+        self.admin.get()
+    }
+
+    /// Get admin nominated by transfer_admin, if any
+    pub fn pending_admin(&self) -> Option<Address> {
+        self.pending_admin.get().flatten()
+    }
+
+    /// Nominate a new admin (only admin can call); takes effect on accept_admin
+    pub fn transfer_admin(&mut self, new_admin: Address) {
+        let admin = self.require_admin();
+        self.pending_admin.set(Some(new_admin));
+        self.env().emit_event(events::AdminTransferStarted {
+            admin,
+            pending_admin: new_admin,
+        });
+    }
+
+    /// Accept a pending admin nomination (only the nominee can call)
+    pub fn accept_admin(&mut self) {
+        let caller = self.env().caller();
+        if self.pending_admin() != Some(caller) {
+            self.env().revert(TokenError::Unauthorized);
+        }
+        let old_admin = self.admin.get();
+        self.admin.set(caller);
+        self.pending_admin.set(None);
+        self.env().emit_event(events::AdminTransferred {
+            old_admin,
+            new_admin: caller,
+        });
     }
 
     /// Get current minter
@@ -277,20 +533,138 @@ impl MCSPRToken {
         self.minter.get()
     }
 
-    /// Set new minter (only current minter can call)
+    /// Hand minting over to `new_minter` (caller must be both admin and current minter,
+    /// e.g. the deployer before handing over to the vault).
+    /// Reverts with MinterTimelocked while a minter timelock is configured; use
+    /// propose_minter/apply_minter instead.
     pub fn set_minter(&mut self, new_minter: Address) {
-        let caller = self.env().caller();
-        let current_minter = self.minter.get();
-        if current_minter != Some(caller) {
+        self.require_admin();
+        if self.minter.get() != Some(self.env().caller()) {
             self.env().revert(TokenError::Unauthorized);
         }
-        self.minter.set(new_minter);
-        self.env().emit_event(events::MinterSet {
-            old_minter: current_minter,
-            new_minter,
+        self.require_no_minter_timelock();
+        self.install_minter(new_minter);
+    }
+
+    /// Replace the minter without its cooperation (only admin can call), e.g. when it
+    /// was pointed at a dead or wrong address and can no longer call set_minter.
+    /// Bound by the minter timelock like set_minter.
+    pub fn force_set_minter(&mut self, new_minter: Address) {
+        self.require_admin();
+        self.require_no_minter_timelock();
+        self.install_minter(new_minter);
+    }
+
+    /// Get the delay (seconds) between propose_minter and apply_minter
+    pub fn minter_timelock_secs(&self) -> u64 {
+        self.minter_timelock_secs.get_or_default()
+    }
+
+    /// Set the minter rotation delay (only admin can call). Increases apply at once.
+    /// A decrease only takes effect through apply_minter_timelock_secs after the current
+    /// delay, so the admin can't drop the timelock and rotate the minter in one go.
+    /// Each call replaces any decrease queued earlier.
+    pub fn set_minter_timelock_secs(&mut self, timelock_secs: u64) {
+        self.require_admin();
+        let old_timelock_secs = self.minter_timelock_secs();
+        if timelock_secs < old_timelock_secs {
+            let ready_at = self.ready_at_after(old_timelock_secs);
+            self.queued_minter_timelock.set(Some((timelock_secs, ready_at)));
+            self.env().emit_event(events::MinterTimelockDecreaseQueued {
+                new_timelock_secs: timelock_secs,
+                ready_at,
+            });
+            return;
+        }
+        self.queued_minter_timelock.set(None);
+        self.install_minter_timelock(timelock_secs);
+    }
+
+    /// Get the queued timelock decrease and the block time (ms) from which it can be applied
+    pub fn queued_minter_timelock(&self) -> Option<(u64, u64)> {
+        self.queued_minter_timelock.get().flatten()
+    }
+
+    /// Apply the queued timelock decrease once the previous delay has passed
+    /// (only admin can call)
+    pub fn apply_minter_timelock_secs(&mut self) {
+        self.require_admin();
+        let (timelock_secs, ready_at) = self
+            .queued_minter_timelock()
+            .unwrap_or_else(|| self.env().revert(TokenError::NoTimelockQueued));
+        if self.env().get_block_time() < ready_at {
+            self.env().revert(TokenError::MinterTimelocked);
+        }
+        self.queued_minter_timelock.set(None);
+        self.install_minter_timelock(timelock_secs);
+    }
+
+    /// Get the proposed minter and the block time (ms) from which it can be applied
+    pub fn proposed_minter(&self) -> Option<(Address, u64)> {
+        self.proposed_minter.get().flatten()
+    }
+
+    /// Record `new_minter` as the next minter (only admin can call). The current minter
+    /// keeps working until apply_minter runs after the timelock. A new proposal
+    /// replaces the previous one and restarts the clock.
+    pub fn propose_minter(&mut self, new_minter: Address) {
+        self.require_admin();
+        let ready_at = self.ready_at_after(self.minter_timelock_secs());
+        self.proposed_minter.set(Some((new_minter, ready_at)));
+        self.env().emit_event(events::MinterProposed {
+            proposed_minter: new_minter,
+            ready_at,
         });
     }
 
+    /// Activate the proposed minter once its timelock has passed (only admin can call)
+    pub fn apply_minter(&mut self) {
+        self.require_admin();
+        let (new_minter, ready_at) = self
+            .proposed_minter()
+            .unwrap_or_else(|| self.env().revert(TokenError::NoMinterProposed));
+        if self.env().get_block_time() < ready_at {
+            self.env().revert(TokenError::MinterTimelocked);
+        }
+        self.proposed_minter.set(None);
+        self.install_minter(new_minter);
+    }
+
+    /// Rename the token (only admin can call)
+    pub fn set_name(&mut self, name: String) {
+        self.require_admin();
+        self.name.set(name);
+        self.emit_metadata_changed();
+    }
+
+    /// Change the token symbol (only admin can call)
+    pub fn set_symbol(&mut self, symbol: String) {
+        self.require_admin();
+        self.symbol.set(symbol);
+        self.emit_metadata_changed();
+    }
+
+    /// Change decimals (only admin can call); only while no tokens exist, since
+    /// it would change the meaning of every balance
+    pub fn set_decimals(&mut self, decimals: u8) {
+        self.require_admin();
+        if !self.token.total_supply().is_zero() {
+            self.env().revert(TokenError::SupplyExists);
+        }
+        self.decimals.set(decimals);
+        self.emit_metadata_changed();
+    }
+
+    /// Crate version this contract was built from
+    pub fn version(&self) -> String {
+        crate::VERSION.to_string()
+    }
+
+    /// Contract discriminator, constant across versions
+    pub fn contract_kind(&self) -> String {
+        "mcspr_token".to_string()
+    }
+
     /// Token name
     pub fn name(&self) -> String {
         self.token.name()
@@ -316,14 +690,32 @@ impl MCSPRToken {
         self.token.balance_of(&owner)
     }
 
+    /// Balances of several addresses, in the order given
+    pub fn balances_of(&self, owners: Vec<Address>) -> Vec<U256> {
+        owners.iter().map(|owner| self.token.balance_of(owner)).collect()
+    }
+
+    /// Number of accounts holding a nonzero balance
+    pub fn holder_count(&self) -> u64 {
+        self.holder_count.get_or_default()
+    }
+
     /// Allowance from owner to spender
     pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
         self.token.allowance(&owner, &spender)
     }
 
-    /// Transfer tokens
+    /// Transfer tokens; reverts with CannotTargetSelfUser when sending to the caller
     pub fn transfer(&mut self, recipient: Address, amount: U256) {
+        let sender = self.env().caller();
+        if sender == recipient {
+            self.env().revert(TokenError::CannotTargetSelfUser);
+        }
+        let sender_before = self.token.balance_of(&sender);
+        let recipient_before = self.token.balance_of(&recipient);
         self.token.transfer(&recipient, &amount);
+        self.update_holder(&sender, sender_before);
+        self.update_holder(&recipient, recipient_before);
     }
 
     /// Approve spender
@@ -343,7 +735,77 @@ impl MCSPRToken {
 
     /// Transfer from (with allowance)
     pub fn transfer_from(&mut self, owner: Address, recipient: Address, amount: U256) {
+        if owner == recipient {
+            self.env().revert(TokenError::CannotTargetSelfUser);
+        }
+        let owner_before = self.token.balance_of(&owner);
+        let recipient_before = self.token.balance_of(&recipient);
         self.token.transfer_from(&owner, &recipient, &amount);
+        self.update_holder(&owner, owner_before);
+        self.update_holder(&recipient, recipient_before);
+    }
+
+    /// Current permit nonce of `owner`
+    pub fn nonces(&self, owner: Address) -> u64 {
+        self.permit_nonces.get(&owner).unwrap_or_default()
+    }
+
+    /// Permit domain separator: hash of the domain tag and this contract's package
+    /// address. The package hash derives from a deploy hash that covers the chain name,
+    /// so the separator also differs per chain.
+    pub fn domain_separator(&self) -> [u8; 32] {
+        self.domain_separator.get().unwrap_or_else(|| self.compute_domain_separator())
+    }
+
+    /// Payload `owner` must sign to approve `spender` for `amount` until `deadline`.
+    /// Binds the domain separator, the owner's current nonce and the deadline
+    /// (block time, ms).
+    pub fn permit_message(
+        &self,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+        deadline: u64,
+    ) -> Bytes {
+        let mut payload: Vec<u8> = self.domain_separator().to_vec();
+        payload.extend(self.serialize(&owner));
+        payload.extend(self.serialize(&spender));
+        payload.extend(self.serialize(&amount));
+        payload.extend(self.serialize(&self.nonces(owner)));
+        payload.extend(self.serialize(&deadline));
+        Bytes::from(payload)
+    }
+
+    /// Set `spender`'s allowance over the tokens of `owner_key`'s account from a signed
+    /// `permit_message`, so the approval doesn't need its own transaction.
+    pub fn permit(
+        &mut self,
+        owner_key: PublicKey,
+        spender: Address,
+        amount: U256,
+        deadline: u64,
+        signature: Bytes,
+    ) {
+        if self.env().get_block_time() > deadline {
+            self.env().revert(TokenError::PermitExpired);
+        }
+        let owner = Address::from(owner_key.to_account_hash());
+        if owner == spender {
+            self.env().revert(TokenError::CannotTargetSelfUser);
+        }
+
+        let message = self.permit_message(owner, spender, amount, deadline);
+        if !self.env().verify_signature(&message, &signature, &owner_key) {
+            self.env().revert(TokenError::InvalidSignature);
+        }
+
+        self.permit_nonces.set(&owner, self.nonces(owner) + 1);
+        self.allowances.set(&owner, &spender, amount);
+        self.env().emit_event(SetAllowance {
+            owner,
+            spender,
+            allowance: amount,
+        });
     }
 
     /// Mint tokens (only minter can call)
@@ -353,7 +815,9 @@ impl MCSPRToken {
         if !self.is_authorized_minter(&caller) {
             self.env().revert(TokenError::Unauthorized);
         }
+        let before = self.token.balance_of(&to);
         self.token.raw_mint(&to, &amount);
+        self.update_holder(&to, before);
     }
 
     /// Burn tokens (only minter can call, burns from target address)
@@ -363,7 +827,104 @@ impl MCSPRToken {
         if !self.is_authorized_minter(&caller) {
             self.env().revert(TokenError::Unauthorized);
         }
+        let before = self.token.balance_of(&from);
         self.token.raw_burn(&from, &amount);
+        self.update_holder(&from, before);
+    }
+
+    /// Burn `amount` from `owner`, spending the caller's allowance (only minter can call).
+    /// Lets the minter settle repayments in place instead of pulling the tokens first.
+    pub fn burn_from(&mut self, owner: Address, amount: U256) {
+        let caller = self.env().caller();
+        if !self.is_authorized_minter(&caller) {
+            self.env().revert(TokenError::Unauthorized);
+        }
+        let allowance = self.token.allowance(&owner, &caller);
+        if allowance < amount {
+            self.env().revert(TokenError::InsufficientAllowance);
+        }
+        self.allowances.set(&owner, &caller, allowance - amount);
+        let before = self.token.balance_of(&owner);
+        self.token.raw_burn(&owner, &amount);
+        self.update_holder(&owner, before);
+    }
+
+    // Hash the permit domain tag with this contract's address
+    fn compute_domain_separator(&self) -> [u8; 32] {
+        let mut domain: Vec<u8> = PERMIT_DOMAIN.to_vec();
+        domain.extend(self.serialize(&self.env().self_address()));
+        self.env().hash(domain)
+    }
+
+    // Serialize a permit payload field
+    fn serialize<T: ToBytes>(&self, value: &T) -> Vec<u8> {
+        value
+            .to_bytes()
+            .unwrap_or_else(|_| self.env().revert(TokenError::InvalidSignature))
+    }
+
+    // Revert unless caller is admin; returns the admin
+    fn require_admin(&self) -> Address {
+        let caller = self.env().caller();
+        if self.admin.get() != Some(caller) {
+            self.env().revert(TokenError::Unauthorized);
+        }
+        caller
+    }
+
+    // Instant minter changes are closed while a minter timelock is configured
+    fn require_no_minter_timelock(&self) {
+        if self.minter_timelock_secs() > 0 {
+            self.env().revert(TokenError::MinterTimelocked);
+        }
+    }
+
+    // Replace the minter and emit MinterSet
+    fn install_minter(&mut self, new_minter: Address) {
+        let old_minter = self.minter.get();
+        self.minter.set(new_minter);
+        self.env().emit_event(events::MinterSet {
+            old_minter,
+            new_minter,
+        });
+    }
+
+    // Replace the minter timelock and emit MinterTimelockChanged
+    fn install_minter_timelock(&mut self, timelock_secs: u64) {
+        let old_timelock_secs = self.minter_timelock_secs();
+        self.minter_timelock_secs.set(timelock_secs);
+        self.env().emit_event(events::MinterTimelockChanged {
+            old_timelock_secs,
+            new_timelock_secs: timelock_secs,
+        });
+    }
+
+    // Block time (ms) `delay_secs` from now
+    fn ready_at_after(&self, delay_secs: u64) -> u64 {
+        let delay_ms = delay_secs.saturating_mul(MILLIS_PER_SECOND);
+        self.env().get_block_time().saturating_add(delay_ms)
+    }
+
+    // Adjust holder_count for `account` whose balance was `before` the operation.
+    // Called once per distinct account, so self-transfers and mints to existing
+    // holders leave the count unchanged.
+    fn update_holder(&mut self, account: &Address, before: U256) {
+        let after = self.token.balance_of(account);
+        let count = self.holder_count.get_or_default();
+        if before.is_zero() && !after.is_zero() {
+            self.holder_count.set(count + 1);
+        } else if !before.is_zero() && after.is_zero() {
+            self.holder_count.set(count.saturating_sub(1));
+        }
+    }
+
+    // Emit the full metadata after a change
+    fn emit_metadata_changed(&self) {
+        self.env().emit_event(events::MetadataChanged {
+            name: self.token.name(),
+            symbol: self.token.symbol(),
+            decimals: self.token.decimals(),
+        });
     }
 
     // Check if caller is authorized minter
@@ -0,0 +1,52 @@
+//! Unit conversion between CSPR motes and 18-decimal token amounts
+//!
+//! - CSPR: motes (U512), 1 CSPR = 1e9 motes
+//! - mCSPR / tCSPR: wad (U256), 18 decimals, 1 token = 1e18 wad
+
+use odra::casper_types::{U256, U512};
+
+/// 1 CSPR = 1e9 motes
+pub const MOTES_PER_CSPR: u64 = 1_000_000_000;
+/// Conversion factor from motes (9 dec) to wad (18 dec) = 1e9
+pub const MOTES_TO_WAD_FACTOR: u128 = 1_000_000_000;
+/// 1 wad = 1e18
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// Convert whole CSPR to motes
+pub fn cspr_to_motes(cspr: u64) -> U512 {
+    U512::from(cspr) * U512::from(MOTES_PER_CSPR)
+}
+
+/// Convert motes (U512, 9 decimals) to wad (U256, 18 decimals)
+/// 1 CSPR (1e9 motes) = 1e18 wad
+/// Returns None if the result does not fit in U256.
+pub fn motes_to_wad(motes: U512) -> Option<U256> {
+    let mut bytes = [0u8; 64];
+    motes.to_little_endian(&mut bytes);
+    if bytes[32..].iter().any(|b| *b != 0) {
+        return None;
+    }
+    U256::from_little_endian(&bytes[..32]).checked_mul(U256::from(MOTES_TO_WAD_FACTOR))
+}
+
+/// Convert wad (U256, 18 decimals) to motes (U512, 9 decimals)
+/// Round down (conservative for protocol): up to 1e9 - 1 wad (less than one mote) is
+/// dropped. Total for every input: the quotient is at most U256::MAX / 1e9 and is
+/// widened byte-wise, never through a narrowing cast, so there is no overflow case.
+pub fn wad_to_motes(wad: U256) -> U512 {
+    let motes = wad / U256::from(MOTES_TO_WAD_FACTOR);
+    let mut bytes = [0u8; 32];
+    motes.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+/// Convert wad to motes rounding up, for amounts owed to the protocol
+/// (a displayed repay figure must never undershoot the debt)
+pub fn wad_to_motes_round_up(wad: U256) -> U512 {
+    let motes = wad_to_motes(wad);
+    if wad % U256::from(MOTES_TO_WAD_FACTOR) == U256::zero() {
+        motes
+    } else {
+        motes + U512::one()
+    }
+}
@@ -3,29 +3,39 @@
 //! Tests for Magni V2 CSPR Vault (deposit/borrow/repay/withdraw)
 
 use odra::prelude::*;
-use odra::host::{Deployer, HostRef};
+use odra::host::{Deployer, HostRef, NoArgs};
 use odra::casper_types::{PublicKey, U256, U512};
 use odra::casper_types::bytesrepr::ToBytes;
 
-use magni_casper::magni::{Magni, MagniHostRef, MagniInitArgs};
-use magni_casper::tokens::{MCSPRToken, MCSPRTokenHostRef, MCSPRTokenInitArgs};
+use magni_casper::magni::events::{
+    BecameLiquidatable, Borrowed, CircuitBreakerTripped, Deposited, FeeRecipientChanged,
+    Liquidated, PriceObserved, ReferralRecorded, Repaid, RepaidWithTcspr, RewardsHarvested,
+    StorageUpgraded, TokensRescued, UndelegationRequested, ValidatorChanged, WithdrawFinalized,
+    WithdrawRequested,
+};
+use magni_casper::magni::{
+    slashed_collateral_index, Magni, MagniHostRef, MagniInitArgs, OP_BORROW, OP_DEPOSIT,
+    OP_REPAY, OP_WITHDRAW, PRICE_SOURCE_MOCK, PRICE_SOURCE_ORACLE, STATUS_CLAMPED,
+    STORAGE_VERSION, VALIDATOR_ALLOWLIST_DELAY_SECS, VaultError,
+};
+use magni_casper::mocks::{
+    CountingStyksOracle, CountingStyksOracleInitArgs, MockStyksOracle, MockSwapPool,
+    MockSwapPoolHostRef, MockSwapPoolInitArgs, ObservingBurnToken, ObservingBurnTokenHostRef,
+    PriceRead, ReentrantSwapPool, ReentrantSwapPoolHostRef, ReentrantSwapPoolInitArgs,
+};
+use magni_casper::tokens::{MCSPRToken, MCSPRTokenHostRef, MCSPRTokenInitArgs, TCSPRToken};
+use magni_casper::staking_poc::StakingPoC;
+use magni_casper::styks_external::mock::MOCK_CSPR_USD_PRICE;
+use magni_casper::units::{cspr_to_motes, WAD};
 
 /// Constants for testing
-const MOTES_PER_CSPR: u64 = 1_000_000_000;
-const MOTES_TO_WAD_FACTOR: u128 = 1_000_000_000;
 const LTV_MAX_BPS: u64 = 8000;
 const BPS_DIVISOR: u64 = 10_000;
-const WAD: u128 = 1_000_000_000_000_000_000;
-
-/// Convert CSPR to motes
-fn cspr_to_motes(cspr: u64) -> U512 {
-    U512::from(cspr) * U512::from(MOTES_PER_CSPR)
-}
+const UNBONDING_PERIOD_SECS: u64 = 50_400;
 
 /// Convert motes to wad
 fn motes_to_wad(motes: U512) -> U256 {
-    let motes_u128 = motes.as_u128();
-    U256::from(motes_u128) * U256::from(MOTES_TO_WAD_FACTOR)
+    magni_casper::units::motes_to_wad(motes).expect("motes fit in wad")
 }
 
 /// Calculate max borrow for given collateral
@@ -40,6 +50,12 @@ fn public_key_to_hex(public_key: &PublicKey) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
+/// Allowlist `key` as the caller (owner) and wait out the activation delay
+fn allow_validator(env: &odra::host::HostEnv, magni: &mut MagniHostRef, key: &str) {
+    magni.add_allowed_validator(key.to_string());
+    env.advance_block_time(VALIDATOR_ALLOWLIST_DELAY_SECS * 1000);
+}
+
 // ==========================================
 // Helper: Deploy contracts
 // ==========================================
@@ -89,270 +105,265 @@ fn test_deposit_creates_vault() {
 }
 
 #[test]
-fn test_deposit_adds_to_existing_collateral() {
+fn test_deposit_with_referral_records_first_referrer() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
+    let referrer = env.get_account(2);
 
     env.set_caller(user);
-    let first_deposit = cspr_to_motes(100);
-    let second_deposit = cspr_to_motes(50);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit_with_referral(referrer);
 
-    magni_mut.with_tokens(first_deposit).deposit();
-    magni_mut.with_tokens(second_deposit).add_collateral();
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(100));
+    assert_eq!(magni.referrer_of(user), Some(referrer));
+    assert!(env.emitted_event(&magni, ReferralRecorded { user, referrer }));
+    assert!(env.emitted(&magni, "Deposited"));
 
-    assert_eq!(magni_mut.collateral_of(user), first_deposit + second_deposit);
+    // First deposit wins
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit_with_referral(env.get_account(3));
+    assert_eq!(magni.referrer_of(user), Some(referrer));
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(200));
 }
 
 #[test]
-fn test_borrow_success() {
+#[should_panic(expected = "SelfReferral")]
+fn test_deposit_with_self_referral_reverts() {
     let env = odra_test::env();
-    let (mcspr, magni, _) = deploy_contracts(&env);
+    let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000); // 1000 CSPR
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Borrow 50% of max (well within LTV limit)
-    let max_borrow = max_borrow_wad(deposit_amount);
-    let borrow_amount = max_borrow / U256::from(2u64);
-    magni_mut.borrow(borrow_amount);
-
-    // Check debt and mCSPR balance
-    assert_eq!(magni_mut.debt_of(user), borrow_amount);
-    let mcspr_ref = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
-    assert_eq!(mcspr_ref.balance_of(user), borrow_amount);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit_with_referral(user);
 }
 
 #[test]
-fn test_borrow_max_ltv() {
+#[should_panic(expected = "BelowMinDeposit")]
+fn test_deposit_below_minimum_reverts() {
     let env = odra_test::env();
-    let (mcspr, magni, _) = deploy_contracts(&env);
-    let user = env.get_account(1);
+    let (_, magni, _) = deploy_contracts(&env);
 
-    env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
+    env.set_caller(env.get_account(1));
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Borrow exactly max LTV (80%)
-    let max_borrow = max_borrow_wad(deposit_amount);
-    magni_mut.borrow(max_borrow);
-
-    assert_eq!(magni_mut.debt_of(user), max_borrow);
-    let mcspr_ref = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
-    assert_eq!(mcspr_ref.balance_of(user), max_borrow);
+    magni_mut.with_tokens(cspr_to_motes(1) - U512::one()).deposit();
 }
 
 #[test]
-#[should_panic(expected = "LtvExceeded")]
-fn test_borrow_exceeds_ltv_reverts() {
+fn test_deposit_minimum_and_small_top_up() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
     let user = env.get_account(1);
 
-    env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
+    env.set_caller(owner);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.set_min_deposit_motes(cspr_to_motes(10));
+    assert_eq!(magni.min_deposit_motes(), cspr_to_motes(10));
 
-    // Try to borrow more than 80%
-    let max_borrow = max_borrow_wad(deposit_amount);
-    let excess_borrow = max_borrow + U256::one();
-    magni_mut.borrow(excess_borrow);
+    env.set_caller(user);
+    assert!(magni_mut.with_tokens(cspr_to_motes(9)).try_deposit().is_err());
+    magni_mut.with_tokens(cspr_to_motes(10)).deposit();
+
+    // Existing vaults can top up below the minimum
+    magni_mut.with_tokens(U512::from(1u64)).deposit();
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(10) + U512::from(1u64));
 }
 
 #[test]
-#[should_panic(expected = "NoVault")]
-fn test_borrow_without_vault_reverts() {
+fn test_deposit_adds_to_existing_collateral() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
+    let first_deposit = cspr_to_motes(100);
+    let second_deposit = cspr_to_motes(50);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.borrow(U256::from(100u64));
-}
 
-// ==========================================
-// T18: Repay Tests
-// ==========================================
+    magni_mut.with_tokens(first_deposit).deposit();
+    magni_mut.with_tokens(second_deposit).add_collateral();
+
+    assert_eq!(magni_mut.collateral_of(user), first_deposit + second_deposit);
+}
 
 #[test]
-fn test_repay_partial() {
+fn test_borrow_success() {
     let env = odra_test::env();
     let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
+    let deposit_amount = cspr_to_motes(1000); // 1000 CSPR
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
     magni_mut.with_tokens(deposit_amount).deposit();
 
-    // Borrow
-    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
+    // Borrow 50% of max (well within LTV limit)
+    let max_borrow = max_borrow_wad(deposit_amount);
+    let borrow_amount = max_borrow / U256::from(2u64);
     magni_mut.borrow(borrow_amount);
 
-    // Approve and repay half
-    let repay_amount = borrow_amount / U256::from(2u64);
-    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
-    mcspr_mut.approve(magni.address(), repay_amount);
-    magni_mut.repay(repay_amount);
-
-    // Check debt decreased
-    assert_eq!(magni_mut.debt_of(user), borrow_amount - repay_amount);
+    // Check debt and mCSPR balance
+    assert_eq!(magni_mut.debt_of(user), borrow_amount);
+    let mcspr_ref = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    assert_eq!(mcspr_ref.balance_of(user), borrow_amount);
 }
 
 #[test]
-fn test_repay_full() {
+fn test_borrow_to_mints_to_recipient_debt_on_caller() {
     let env = odra_test::env();
     let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
+    let recipient = env.get_account(2);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Borrow
-    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
-    magni_mut.borrow(borrow_amount);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
 
-    // Approve and repay full
-    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
-    mcspr_mut.approve(magni.address(), borrow_amount);
-    magni_mut.repay(borrow_amount);
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow_to(borrow_amount, recipient);
+
+    assert_eq!(mcspr.balance_of(recipient), borrow_amount);
+    assert_eq!(mcspr.balance_of(user), U256::zero());
+    assert_eq!(magni.debt_of(user), borrow_amount);
+    assert_eq!(magni.debt_of(recipient), U256::zero());
+    assert!(env.emitted_event(&magni, Borrowed {
+        user,
+        amount_wad: borrow_amount,
+        new_debt_wad: borrow_amount,
+        seq: magni.event_seq(),
+    }));
+}
 
-    // Check debt is zero
-    assert_eq!(magni_mut.debt_of(user), U256::zero());
+/// Seq of the event at `index` if it is one of the sequenced vault events
+fn sequenced_event_seq(
+    env: &odra::host::HostEnv,
+    magni: &MagniHostRef,
+    index: i32,
+) -> Option<u64> {
+    env.get_event::<Deposited>(magni, index)
+        .map(|e| e.seq)
+        .or_else(|_| env.get_event::<Borrowed>(magni, index).map(|e| e.seq))
+        .or_else(|_| env.get_event::<Repaid>(magni, index).map(|e| e.seq))
+        .or_else(|_| env.get_event::<WithdrawRequested>(magni, index).map(|e| e.seq))
+        .ok()
 }
 
 #[test]
-fn test_repay_more_than_debt_caps_at_debt() {
+fn test_event_seq_increments_monotonically() {
     let env = odra_test::env();
     let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let deposit_amount = cspr_to_motes(1000);
     magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Borrow
     let borrow_amount = U256::from(100u64) * U256::from(WAD);
     magni_mut.borrow(borrow_amount);
-
-    // Approve more than debt and repay
     let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
-    let large_amount = borrow_amount * U256::from(2u64);
-    mcspr_mut.approve(magni.address(), large_amount);
-    magni_mut.repay(large_amount);
+    mcspr_mut.approve(magni.address(), borrow_amount);
+    magni_mut.repay(borrow_amount);
+    magni_mut.request_withdraw(deposit_amount);
 
-    // Debt should be zero, not negative
-    assert_eq!(magni_mut.debt_of(user), U256::zero());
-    // User should have no mCSPR left (we only borrowed borrow_amount)
-    assert_eq!(mcspr_mut.balance_of(user), U256::zero());
+    let seqs: Vec<u64> = (0..env.events_count(&magni))
+        .filter_map(|i| sequenced_event_seq(&env, &magni, i as i32))
+        .collect();
+    assert_eq!(seqs.len(), 4);
+    assert!(seqs.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(*seqs.last().unwrap(), magni.event_seq());
 }
 
 #[test]
-#[should_panic(expected = "InsufficientAllowance")]
-fn test_repay_without_allowance_reverts() {
+fn test_user_stats_count_borrows_and_repays() {
     let env = odra_test::env();
-    let (_, magni, _) = deploy_contracts(&env);
+    let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let first = U256::from(100u64) * U256::from(WAD);
+    let second = U256::from(50u64) * U256::from(WAD);
+    magni_mut.borrow(first);
+    magni_mut.borrow(second);
 
-    // Borrow
-    let borrow_amount = U256::from(100u64) * U256::from(WAD);
-    magni_mut.borrow(borrow_amount);
+    let repay_amount = U256::from(30u64) * U256::from(WAD);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), repay_amount);
+    magni_mut.repay(repay_amount);
 
-    // Try to repay without approve
-    magni_mut.repay(borrow_amount);
+    let stats = magni_mut.user_stats(user);
+    assert_eq!(stats.borrow_count, 2);
+    assert_eq!(stats.repay_count, 1);
+    assert_eq!(stats.lifetime_borrowed_wad, first + second);
+    assert_eq!(stats.lifetime_repaid_wad, repay_amount);
+    assert_eq!(magni_mut.user_stats(env.get_account(2)).borrow_count, 0);
 }
 
-// ==========================================
-// T18: Withdraw Tests (2-step)
-// ==========================================
-
 #[test]
-fn test_withdraw_request_no_debt() {
+fn test_simulate_borrow_matches_actual_borrow() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(100);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Request withdraw
-    magni_mut.request_withdraw(deposit_amount);
-
-    // Check status is Withdrawing
-    assert_eq!(magni_mut.status_of(user), 2); // Withdrawing
-    assert_eq!(magni_mut.pending_withdraw_of(user), deposit_amount);
-    assert_eq!(magni_mut.collateral_of(user), U512::zero());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+
+    let amount = U256::from(300u64) * U256::from(WAD);
+    let simulated = magni.simulate_borrow(user, amount);
+    // Simulation leaves state untouched
+    assert_eq!(magni.debt_of(user), U256::from(100u64) * U256::from(WAD));
+
+    magni_mut.borrow(amount);
+    let actual = magni.get_position(user);
+    assert_eq!(simulated.ltv_bps, 4000);
+    assert_eq!(simulated.ltv_bps, actual.ltv_bps);
+    assert_eq!(simulated.health_factor, actual.health_factor);
+    assert_eq!(simulated.debt_wad, actual.debt_wad);
 }
 
 #[test]
-fn test_finalize_withdraw_with_liquid_balance() {
+fn test_simulate_borrow_over_ltv_does_not_revert() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
-    // Deposit less than min delegation so no actual delegation happens
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(100); // 100 CSPR < 500 CSPR min
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Request withdraw
-    magni_mut.request_withdraw(deposit_amount);
-
-    // Finalize should work since liquid balance is available
-    magni_mut.finalize_withdraw();
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
 
-    // Check vault is cleared
-    assert_eq!(magni_mut.status_of(user), 0); // None
-    assert_eq!(magni_mut.collateral_of(user), U512::zero());
-    assert_eq!(magni_mut.pending_withdraw_of(user), U512::zero());
+    let simulated = magni.simulate_borrow(user, U256::from(900u64) * U256::from(WAD));
+    assert_eq!(simulated.ltv_bps, 9000);
+    assert!(simulated.health_factor < 10_000);
+    assert!(magni_mut.try_borrow(U256::from(900u64) * U256::from(WAD)).is_err());
 }
 
 #[test]
-fn test_withdraw_partial_maintains_ltv() {
+fn test_debt_in_motes_rounds_up() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Borrow 40% LTV
-    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
-    magni_mut.borrow(borrow_amount);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
 
-    // Try to withdraw 50% of collateral (this should keep LTV at 80%, which is max)
-    let withdraw_amount = deposit_amount / U512::from(2u64);
-    magni_mut.request_withdraw(withdraw_amount);
+    // Whole wad amount converts exactly
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+    assert_eq!(magni.debt_in_motes(user), cspr_to_motes(100));
 
-    // Check status
-    assert_eq!(magni_mut.status_of(user), 2); // Withdrawing
+    // One extra wad unit is a fraction of a mote: shown as a full extra mote
+    magni_mut.borrow(U256::one());
+    assert_eq!(magni.debt_in_motes(user), cspr_to_motes(100) + U512::one());
 }
 
 #[test]
-#[should_panic(expected = "LtvExceeded")]
-fn test_withdraw_exceeds_ltv_reverts() {
+fn test_borrow_max_ltv() {
     let env = odra_test::env();
-    let (_, magni, _) = deploy_contracts(&env);
+    let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
@@ -360,275 +371,4038 @@ fn test_withdraw_exceeds_ltv_reverts() {
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
     magni_mut.with_tokens(deposit_amount).deposit();
 
-    // Borrow max (80% LTV)
-    let borrow_amount = max_borrow_wad(deposit_amount);
-    magni_mut.borrow(borrow_amount);
+    // Borrow exactly max LTV (80%)
+    let max_borrow = max_borrow_wad(deposit_amount);
+    magni_mut.borrow(max_borrow);
 
-    // Try to withdraw any collateral - should fail as LTV would exceed
-    magni_mut.request_withdraw(U512::from(1u64));
+    assert_eq!(magni_mut.debt_of(user), max_borrow);
+    let mcspr_ref = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    assert_eq!(mcspr_ref.balance_of(user), max_borrow);
 }
 
 #[test]
-#[should_panic(expected = "WithdrawPending")]
-fn test_double_withdraw_request_reverts() {
+#[should_panic(expected = "Overflow")]
+fn test_borrow_near_u256_max_reverts_with_overflow() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(100);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
-
-    // First request
-    magni_mut.request_withdraw(deposit_amount / U512::from(2u64));
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
 
-    // Second request should fail
-    magni_mut.request_withdraw(deposit_amount / U512::from(2u64));
+    // Existing debt + amount exceeds U256: a handled error, not an arithmetic panic
+    magni_mut.borrow(U256::MAX - U256::one());
 }
 
-// ==========================================
-// T18: Unit Conversion Tests
-// ==========================================
-
 #[test]
-fn test_motes_to_wad_conversion() {
+#[should_panic(expected = "LtvExceeded")]
+fn test_borrow_exceeds_ltv_reverts() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
-    // 1 CSPR = 1e9 motes = 1e18 wad
-    let one_cspr_motes = cspr_to_motes(1);
-    let one_cspr_wad = motes_to_wad(one_cspr_motes);
-
     env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(one_cspr_motes).deposit();
-
-    // Get position to check conversion
-    let position = magni_mut.get_position(user);
-    assert_eq!(position.collateral_motes, one_cspr_motes);
-    assert_eq!(position.collateral_wad, one_cspr_wad);
+    magni_mut.with_tokens(deposit_amount).deposit();
 
-    // 1e18 wad for 1 CSPR
-    assert_eq!(one_cspr_wad, U256::from(WAD));
+    // Try to borrow more than 80%
+    let max_borrow = max_borrow_wad(deposit_amount);
+    let excess_borrow = max_borrow + U256::one();
+    magni_mut.borrow(excess_borrow);
 }
 
 #[test]
-fn test_ltv_calculation() {
+fn test_mutating_calls_return_resulting_position() {
     let env = odra_test::env();
-    let (_, magni, _) = deploy_contracts(&env);
+    let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let amount = U256::from(100u64) * U256::from(WAD);
 
     env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
-    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
+    let info = magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    assert_eq!(info, magni_mut.get_position(user));
+    assert_eq!(info.collateral_motes, cspr_to_motes(1000));
 
-    // Borrow 50% LTV
-    let collateral_wad = motes_to_wad(deposit_amount);
-    let borrow_amount = collateral_wad / U256::from(2u64); // 50%
-    magni_mut.borrow(borrow_amount);
+    let info = magni_mut.borrow(amount);
+    assert_eq!(info, magni_mut.get_position(user));
+    assert_eq!(info.debt_wad, amount);
 
-    // Check LTV is 5000 bps (50%)
-    let ltv = magni_mut.ltv_of(user);
-    assert_eq!(ltv, 5000);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), amount);
+    let info = magni_mut.repay(amount);
+    assert_eq!(info, magni_mut.get_position(user));
+    assert_eq!(info.debt_wad, U256::zero());
+
+    let info = magni_mut.request_withdraw(cspr_to_motes(400));
+    assert_eq!(info, magni_mut.get_position(user));
+    assert_eq!(info.pending_withdraw_motes, cspr_to_motes(400));
+}
 
-    // Health factor should be 8000 / 5000 * 10000 = 16000
-    let hf = magni_mut.health_factor_of(user);
-    assert_eq!(hf, 16000);
+/// Deploy with a one-hour borrow cooldown and borrow once from a 1000 CSPR vault
+fn setup_borrow_cooldown(env: &odra::host::HostEnv) -> MagniHostRef {
+    let (_, magni, _) = deploy_contracts(env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    assert_eq!(magni_mut.borrow_cooldown_secs(), 0);
+    magni_mut.set_borrow_cooldown_secs(3600);
+
+    env.set_caller(env.get_account(1));
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+    assert_eq!(magni_mut.last_borrow_ts(env.get_account(1)), env.block_time());
+    magni_mut
 }
 
-// ==========================================
-// T18: Interest Accrual Tests
-// ==========================================
+#[test]
+#[should_panic(expected = "BorrowCooldownActive")]
+fn test_borrow_within_cooldown_reverts() {
+    let env = odra_test::env();
+    let mut magni = setup_borrow_cooldown(&env);
+
+    env.advance_block_time(3_599_000);
+    magni.borrow(U256::from(WAD));
+}
 
 #[test]
-fn test_interest_accrues_over_time() {
+fn test_borrow_after_cooldown_succeeds() {
     let env = odra_test::env();
-    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni = setup_borrow_cooldown(&env);
     let user = env.get_account(1);
 
-    env.set_caller(user);
-    let deposit_amount = cspr_to_motes(1000);
+    env.advance_block_time(3_600_000);
+    magni.borrow(U256::from(WAD));
+    assert_eq!(magni.last_borrow_ts(user), env.block_time());
+    // The clock restarts from the latest borrow
+    assert!(magni.try_borrow(U256::from(WAD)).is_err());
+}
+
+#[test]
+fn test_get_account_data_matches_individual_reads() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(300u64) * U256::from(WAD));
+    mcspr_mut.approve(magni.address(), U256::from(120u64) * U256::from(WAD));
+
+    let data = magni_mut.get_account_data(user);
+    assert_eq!(data.mcspr_balance_wad, mcspr_mut.balance_of(user));
+    assert_eq!(data.vault_allowance_wad, mcspr_mut.allowance(user, magni.address()));
+    assert_eq!(data.position, magni_mut.get_position(user));
+    assert_eq!(data.vault_allowance_wad, U256::from(120u64) * U256::from(WAD));
+}
+
+#[test]
+fn test_deposit_and_borrow_matches_two_calls() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let one_call = env.get_account(1);
+    let two_calls = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let deposit_amount = cspr_to_motes(1000);
+    let borrow_amount = U256::from(500u64) * U256::from(WAD);
+
+    env.set_caller(two_calls);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(borrow_amount);
+
+    env.set_caller(one_call);
+    let info = magni_mut.with_tokens(deposit_amount).deposit_and_borrow(borrow_amount);
+    assert!(env.emitted(&magni, "Deposited"));
+    assert!(env.emitted_event(&magni, Borrowed {
+        user: one_call,
+        amount_wad: borrow_amount,
+        new_debt_wad: borrow_amount,
+        seq: magni.event_seq(),
+    }));
+
+    assert_eq!(info, magni_mut.get_position(one_call));
+    assert_eq!(info, magni_mut.get_position(two_calls));
+    let mcspr_ref = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    assert_eq!(mcspr_ref.balance_of(one_call), mcspr_ref.balance_of(two_calls));
+}
+
+#[test]
+#[should_panic(expected = "LtvExceeded")]
+fn test_deposit_and_borrow_over_combined_ltv_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let deposit_amount = cspr_to_motes(1000);
+
+    env.set_caller(user);
+    magni_mut
+        .with_tokens(deposit_amount)
+        .deposit_and_borrow(max_borrow_wad(deposit_amount) + U256::one());
+}
+
+#[test]
+fn test_epoch_flows_bucket_borrows_and_repays() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    let wad = |n: u64| U256::from(n) * U256::from(WAD);
+
+    env.set_caller(owner);
+    assert!(magni_mut.try_set_epoch_length_secs(0).is_err());
+    magni_mut.set_epoch_length_secs(3600);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let first = magni_mut.current_epoch();
+    magni_mut.borrow(wad(100));
+    mcspr_mut.approve(magni.address(), wad(30));
+    magni_mut.repay(wad(30));
+
+    env.advance_block_time(3600 * 1000);
+    let second = magni_mut.current_epoch();
+    assert_eq!(second, first + 1);
+    magni_mut.borrow(wad(50));
+    mcspr_mut.approve(magni.address(), wad(20));
+    magni_mut.repay(wad(20));
+
+    assert_eq!(magni_mut.epoch_flows(first), (wad(100), wad(30)));
+    assert_eq!(magni_mut.epoch_flows(second), (wad(50), wad(20)));
+    assert_eq!(magni_mut.epoch_flows(second + 1), (U256::zero(), U256::zero()));
+}
+
+#[test]
+#[should_panic(expected = "NoVault")]
+fn test_borrow_without_vault_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.borrow(U256::from(100u64));
+}
+
+#[test]
+fn test_vaults_enumerable_by_index() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let users = [env.get_account(1), env.get_account(2), env.get_account(3)];
+    assert_eq!(magni_mut.vault_count(), 0);
+
+    for user in users {
+        env.set_caller(user);
+        magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    }
+    // Top-ups do not list a vault twice
+    env.set_caller(users[0]);
+    magni_mut.with_tokens(cspr_to_motes(10)).deposit();
+
+    assert_eq!(magni_mut.vault_count(), 3);
+    let listed: Vec<_> = (0..magni_mut.vault_count())
+        .map(|i| magni_mut.vault_at(i).unwrap())
+        .collect();
+    assert_eq!(listed, users);
+    assert_eq!(magni_mut.vault_at(3), None);
+}
+
+// ==========================================
+// Leverage Loop Tests
+// ==========================================
+
+/// Deploy a 1:1 mock swap pool funded with `liquidity` motes
+fn deploy_swap_pool(
+    env: &odra::host::HostEnv,
+    mcspr: &MCSPRTokenHostRef,
+    liquidity: U512,
+) -> MockSwapPoolHostRef {
+    env.set_caller(env.get_account(0));
+    let pool = MockSwapPool::deploy(env, MockSwapPoolInitArgs {
+        rate_bps: 10_000,
+        mcspr: mcspr.address(),
+    });
+    let mut pool_mut = MockSwapPoolHostRef::new(pool.address(), env.clone());
+    pool_mut.with_tokens(liquidity).fund();
+    pool
+}
+
+#[test]
+fn test_leverage_once_grows_collateral_and_debt() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let pool = deploy_swap_pool(&env, &mcspr, cspr_to_motes(1000));
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    let borrow_amount = U256::from(400u64) * U256::from(WAD);
+    magni_mut.leverage_once(borrow_amount, pool.address(), cspr_to_motes(400));
+
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1400));
+    assert_eq!(magni.debt_of(user), borrow_amount);
+    assert_eq!(mcspr.balance_of(pool.address()), borrow_amount);
+    assert_eq!(mcspr.balance_of(user), U256::zero());
+    assert_eq!(magni.ltv_of(user), 2857);
+}
+
+#[test]
+#[should_panic(expected = "SlippageExceeded")]
+fn test_leverage_once_below_min_out_reverts() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let pool = deploy_swap_pool(&env, &mcspr, cspr_to_motes(1000));
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.leverage_once(
+        U256::from(400u64) * U256::from(WAD),
+        pool.address(),
+        cspr_to_motes(401),
+    );
+}
+
+/// Deploy a pool that deposits back into `magni` mid-swap, funded with `liquidity` motes
+fn deploy_reentrant_pool(
+    env: &odra::host::HostEnv,
+    magni: &MagniHostRef,
+    liquidity: U512,
+) -> ReentrantSwapPoolHostRef {
+    env.set_caller(env.get_account(0));
+    let pool = ReentrantSwapPool::deploy(env, ReentrantSwapPoolInitArgs {
+        vault: magni.address(),
+    });
+    let mut pool_mut = ReentrantSwapPoolHostRef::new(pool.address(), env.clone());
+    pool_mut.with_tokens(liquidity).fund();
+    pool
+}
+
+#[test]
+fn test_leverage_once_rejects_reentrant_deposit() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let pool = deploy_reentrant_pool(&env, &magni, cspr_to_motes(1000));
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    // The pool's deposit would land in the balance delta and be credited twice
+    let borrow_amount = U256::from(400u64) * U256::from(WAD);
+    let result = magni_mut.try_leverage_once(borrow_amount, pool.address(), U512::zero());
+    assert_eq!(result.unwrap_err(), VaultError::Reentrant.into());
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1000));
+    assert_eq!(magni.collateral_of(pool.address()), U512::zero());
+    assert_eq!(magni.debt_of(user), U256::zero());
+    assert_eq!(magni.total_collateral(), cspr_to_motes(1000));
+
+    // The lock is released, so ordinary calls keep working
+    magni_mut.with_tokens(cspr_to_motes(10)).deposit();
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1010));
+}
+
+#[test]
+fn test_deleverage_once_unwinds_leveraged_position() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let pool = deploy_swap_pool(&env, &mcspr, cspr_to_motes(1000));
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.leverage_once(U256::from(400u64) * U256::from(WAD), pool.address(), U512::zero());
+
+    // Swap 300 CSPR of collateral back into mCSPR and repay with it
+    let min_out = U256::from(300u64) * U256::from(WAD);
+    magni_mut.deleverage_once(cspr_to_motes(300), pool.address(), min_out);
+
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1100));
+    assert_eq!(magni.debt_of(user), U256::from(100u64) * U256::from(WAD));
+    assert_eq!(mcspr.balance_of(user), U256::zero());
+    assert_eq!(mcspr.total_supply(), U256::from(100u64) * U256::from(WAD));
+    assert!(magni.supply_invariant_ok());
+}
+
+#[test]
+fn test_deleverage_once_rejects_reentrant_deposit() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let pool = deploy_swap_pool(&env, &mcspr, cspr_to_motes(1000));
+    let reentrant = deploy_reentrant_pool(&env, &magni, U512::zero());
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let debt = U256::from(400u64) * U256::from(WAD);
+    magni_mut.leverage_once(debt, pool.address(), U512::zero());
+
+    let result = magni_mut.try_deleverage_once(
+        cspr_to_motes(300),
+        reentrant.address(),
+        U256::zero(),
+    );
+    assert_eq!(result.unwrap_err(), VaultError::Reentrant.into());
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1400));
+    assert_eq!(magni.collateral_of(reentrant.address()), U512::zero());
+    assert_eq!(magni.debt_of(user), debt);
+}
+
+#[test]
+fn test_deleverage_once_refunds_mcspr_beyond_debt() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let pool = deploy_swap_pool(&env, &mcspr, cspr_to_motes(1000));
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let debt = U256::from(200u64) * U256::from(WAD);
+    magni_mut.leverage_once(debt, pool.address(), U512::zero());
+
+    // Swapping 300 CSPR returns 300 mCSPR but only 200 is owed; pool inventory covers it
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    env.set_caller(env.get_account(2));
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+    mcspr_mut.transfer(pool.address(), U256::from(100u64) * U256::from(WAD));
+
+    env.set_caller(user);
+    magni_mut.deleverage_once(cspr_to_motes(300), pool.address(), U256::zero());
+
+    assert_eq!(magni.debt_of(user), U256::zero());
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(900));
+    assert_eq!(mcspr.balance_of(user), U256::from(100u64) * U256::from(WAD));
+}
+
+// ==========================================
+// T18: Repay Tests
+// ==========================================
+
+#[test]
+fn test_repay_partial() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
     magni_mut.with_tokens(deposit_amount).deposit();
 
     // Borrow
-    let borrow_amount = U256::from(100u64) * U256::from(WAD); // 100 mCSPR
+    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
+    magni_mut.borrow(borrow_amount);
+
+    // Approve and repay half
+    let repay_amount = borrow_amount / U256::from(2u64);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), repay_amount);
+    magni_mut.repay(repay_amount);
+
+    // Check debt decreased
+    assert_eq!(magni_mut.debt_of(user), borrow_amount - repay_amount);
+}
+
+#[test]
+fn test_repaid_event_reports_allowance_spent() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(200u64) * U256::from(WAD));
+
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    let approved = U256::from(150u64) * U256::from(WAD);
+    let repay_amount = U256::from(100u64) * U256::from(WAD);
+    mcspr_mut.approve(magni.address(), approved);
+    let balance_before = mcspr_mut.balance_of(user);
+    magni_mut.repay(repay_amount);
+
+    let event: Repaid = env.get_event(&magni_mut, -1).unwrap();
+    assert_eq!(event.amount_wad, repay_amount);
+    assert_eq!(event.allowance_before, approved);
+    assert_eq!(event.allowance_after, mcspr_mut.allowance(user, magni.address()));
+    assert_eq!(event.allowance_before - event.allowance_after, event.amount_wad);
+    assert_eq!(balance_before - mcspr_mut.balance_of(user), event.amount_wad);
+}
+
+#[test]
+fn test_repay_full() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow
+    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
+    magni_mut.borrow(borrow_amount);
+
+    // Approve and repay full
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), borrow_amount);
+    magni_mut.repay(borrow_amount);
+
+    // Check debt is zero
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+}
+
+#[test]
+fn test_repay_burns_from_user_without_vault_balance() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    let repay_amount = U256::from(40u64) * U256::from(WAD);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrow_amount);
+    mcspr_mut.approve(magni.address(), U256::from(50u64) * U256::from(WAD));
+    let supply_before = mcspr_mut.total_supply();
+    magni_mut.repay(repay_amount);
+
+    assert_eq!(magni_mut.debt_of(user), borrow_amount - repay_amount);
+    assert_eq!(mcspr_mut.balance_of(user), borrow_amount - repay_amount);
+    assert_eq!(mcspr_mut.total_supply(), supply_before - repay_amount);
+    assert_eq!(
+        mcspr_mut.allowance(user, magni.address()),
+        U256::from(10u64) * U256::from(WAD)
+    );
+    // Burned in place: nothing was ever moved to the vault
+    assert!(!env.emitted(&mcspr, "TransferFrom"));
+    assert_eq!(mcspr_mut.balance_of(magni.address()), U256::zero());
+}
+
+#[test]
+fn test_repay_more_than_debt_caps_at_debt() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    // Approve more than debt and repay
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    let large_amount = borrow_amount * U256::from(2u64);
+    mcspr_mut.approve(magni.address(), large_amount);
+    magni_mut.repay(large_amount);
+
+    // Debt should be zero, not negative
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+    // User should have no mCSPR left (we only borrowed borrow_amount)
+    assert_eq!(mcspr_mut.balance_of(user), U256::zero());
+}
+
+#[test]
+#[should_panic(expected = "InsufficientAllowance")]
+fn test_repay_without_allowance_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    // Try to repay without approve
+    magni_mut.repay(borrow_amount);
+}
+
+/// Sign an mCSPR permit for `spender` as `owner`, valid for one hour of block time
+fn sign_permit(
+    env: &odra::host::HostEnv,
+    mcspr: &MCSPRTokenHostRef,
+    owner: Address,
+    spender: Address,
+    amount: U256,
+) -> (u64, odra::casper_types::bytesrepr::Bytes) {
+    let deadline = env.block_time() + 3_600_000;
+    let message = mcspr.permit_message(owner, spender, amount, deadline);
+    (deadline, env.sign_message(&message, &owner))
+}
+
+#[test]
+fn test_repay_with_permit_in_one_call() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    // No approve transaction: the signed permit is passed to repay directly
+    let (deadline, signature) = sign_permit(&env, &mcspr, user, magni.address(), borrow_amount);
+    magni_mut.repay_with_permit(borrow_amount, deadline, signature, env.public_key(&user));
+
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+    assert_eq!(mcspr.balance_of(user), U256::zero());
+    assert_eq!(mcspr.allowance(user, magni.address()), U256::zero());
+    assert_eq!(mcspr.nonces(user), 1);
+}
+
+#[test]
+#[should_panic(expected = "InvalidSignature")]
+fn test_repay_with_permit_signed_for_other_amount_reverts() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    // Signed for half the repay: the permit is checked against amount_wad, not this
+    let permitted = borrow_amount / U256::from(2u64);
+    let (deadline, signature) = sign_permit(&env, &mcspr, user, magni.address(), permitted);
+    magni_mut.repay_with_permit(borrow_amount, deadline, signature, env.public_key(&user));
+}
+
+#[test]
+#[should_panic(expected = "PermitExpired")]
+fn test_repay_with_expired_permit_reverts() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    let (deadline, signature) = sign_permit(&env, &mcspr, user, magni.address(), borrow_amount);
+    env.advance_block_time(3_600_001);
+    magni_mut.repay_with_permit(borrow_amount, deadline, signature, env.public_key(&user));
+}
+
+#[test]
+#[should_panic(expected = "InvalidSignature")]
+fn test_permit_cannot_be_replayed() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    let half = borrow_amount / U256::from(2u64);
+    let (deadline, signature) = sign_permit(&env, &mcspr, user, magni.address(), half);
+    magni_mut.repay_with_permit(half, deadline, signature.clone(), env.public_key(&user));
+    // Same signature again: nonce has moved on
+    magni_mut.repay_with_permit(half, deadline, signature, env.public_key(&user));
+}
+
+#[test]
+fn test_repay_commits_debt_before_burning() {
+    let env = odra_test::env();
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    env.set_caller(owner);
+    let token = ObservingBurnToken::deploy(&env, NoArgs);
+    let magni = Magni::deploy(&env, MagniInitArgs {
+        mcspr: token.address(),
+        validator_public_key: public_key_to_hex(&env.get_validator(0)),
+    });
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    let half = borrow_amount / U256::from(2u64);
+    let mut token_mut = ObservingBurnTokenHostRef::new(token.address(), env.clone());
+    token_mut.approve(magni.address(), half);
+    magni_mut.repay(half);
+
+    // The token saw the reduced debt while burning: effects land before the interaction
+    assert_eq!(token.debt_seen_at_burn(), Some(borrow_amount - half));
+    assert_eq!(magni.debt_of(user), borrow_amount - half);
+    assert_eq!(token.balance_of(user), borrow_amount - half);
+}
+
+// ==========================================
+// T18: Withdraw Tests (2-step)
+// ==========================================
+
+#[test]
+fn test_withdraw_request_no_debt() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Request withdraw
+    magni_mut.request_withdraw(deposit_amount);
+
+    // Check status is Withdrawing
+    assert_eq!(magni_mut.status_of(user), 2); // Withdrawing
+    assert_eq!(magni_mut.pending_withdraw_of(user), deposit_amount);
+    assert_eq!(magni_mut.collateral_of(user), U512::zero());
+}
+
+#[test]
+fn test_finalize_withdraw_with_liquid_balance() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    // Deposit less than min delegation so no actual delegation happens
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100); // 100 CSPR < 500 CSPR min
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Request withdraw
+    magni_mut.request_withdraw(deposit_amount);
+
+    // Finalize should work after unbonding since liquid balance is available
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    magni_mut.finalize_withdraw();
+
+    // Check vault is cleared
+    assert_eq!(magni_mut.status_of(user), 0); // None
+    assert_eq!(magni_mut.collateral_of(user), U512::zero());
+    assert_eq!(magni_mut.pending_withdraw_of(user), U512::zero());
+}
+
+#[test]
+fn test_finalize_closes_vault_left_with_rounding_dust() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let deposit_amount = cspr_to_motes(100);
+    let borrow_amount = U256::from(10u64) * U256::from(WAD);
+
+    // Leave one wei of debt and one mote of collateral behind
+    env.set_caller(user);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(borrow_amount);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), borrow_amount - U256::one());
+    magni_mut.repay(borrow_amount - U256::one());
+    magni_mut.request_withdraw(deposit_amount - U512::one());
+
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    let dust_debt = magni_mut.debt_of(user);
+    assert!(dust_debt > U256::zero());
+    let reserve_before = magni_mut.reserve_balance();
+    magni_mut.finalize_withdraw();
+
+    assert_eq!(magni_mut.status_of(user), 0); // None
+    assert_eq!(magni_mut.collateral_of(user), U512::zero());
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+    assert_eq!(magni_mut.total_debt(), U256::zero());
+    assert_eq!(magni_mut.reserve_balance(), reserve_before + dust_debt);
+    assert!(magni_mut.supply_invariant_ok());
+}
+
+#[test]
+fn test_liquidity_coverage_of_pending_withdrawals() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    assert_eq!(magni_mut.liquidity_coverage_bps(), BPS_DIVISOR);
+
+    // Alice's deposit gets delegated; Bob's stays liquid
+    env.set_caller(alice);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    env.set_caller(bob);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    assert_eq!(magni_mut.liquid_balance(), cspr_to_motes(100));
+
+    magni_mut.request_withdraw(cspr_to_motes(100));
+    env.set_caller(alice);
+    magni_mut.request_withdraw(cspr_to_motes(600));
+
+    assert_eq!(magni_mut.total_pending_withdraw(), cspr_to_motes(700));
+    // 100 liquid against 700 pending
+    assert_eq!(magni_mut.liquidity_coverage_bps(), 1428);
+}
+
+#[test]
+fn test_owner_force_finalizes_ready_withdrawal() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.request_withdraw(deposit_amount);
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+
+    // The owner sends the payout; the user doesn't transact
+    let balance_before = env.balance_of(&user);
+    env.set_caller(owner);
+    magni_mut.force_finalize(user);
+
+    assert_eq!(env.balance_of(&user), balance_before + deposit_amount);
+    assert_eq!(magni_mut.status_of(user), 0);
+    assert_eq!(magni_mut.pending_withdraw_of(user), U512::zero());
+    assert!(env.emitted(&magni, "WithdrawFinalized"));
+}
+
+#[test]
+fn test_request_withdraw_to_pays_recipient() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let recipient = env.get_account(5);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    let amount = cspr_to_motes(40);
+    magni_mut.request_withdraw_to(amount, recipient);
+    assert_eq!(magni_mut.withdraw_recipient_of(user), recipient);
+
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    let user_before = env.balance_of(&user);
+    let recipient_before = env.balance_of(&recipient);
+    magni_mut.finalize_withdraw();
+
+    assert_eq!(env.balance_of(&recipient), recipient_before + amount);
+    assert!(env.balance_of(&user) <= user_before);
+    let event: WithdrawFinalized = env.get_event(&magni, -1).unwrap();
+    assert_eq!(event.user, user);
+    assert_eq!(event.recipient, recipient);
+    assert_eq!(event.amount_motes, amount);
+}
+
+#[test]
+fn test_request_withdraw_defaults_recipient_to_user() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    magni_mut.request_withdraw_to(cspr_to_motes(10), env.get_account(5));
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    magni_mut.finalize_withdraw();
+
+    // A plain request afterwards pays the user again
+    magni_mut.request_withdraw(cspr_to_motes(10));
+    assert_eq!(magni_mut.withdraw_recipient_of(user), user);
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_force_finalize_requires_owner() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.request_withdraw(deposit_amount);
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+
+    env.set_caller(env.get_account(2));
+    magni_mut.force_finalize(user);
+}
+
+#[test]
+#[should_panic(expected = "UnbondingNotComplete")]
+fn test_finalize_before_unbonding_period_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.request_withdraw(deposit_amount);
+
+    // Liquid balance covers it, but the unbonding period hasn't passed
+    env.advance_block_time((UNBONDING_PERIOD_SECS - 1) * 1000);
+    magni_mut.finalize_withdraw();
+}
+
+#[test]
+fn test_finalize_after_custom_unbonding_period() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    env.set_caller(owner);
+    magni_mut.set_unbonding_period_secs(3600);
+    assert_eq!(magni_mut.unbonding_period_secs(), 3600);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.request_withdraw(deposit_amount);
+
+    env.advance_block_time(3600 * 1000);
+    magni_mut.finalize_withdraw();
+    assert_eq!(magni_mut.status_of(user), 0);
+}
+
+#[test]
+fn test_request_withdraw_with_huge_unbonding_period_saturates() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    env.set_caller(owner);
+    magni_mut.set_unbonding_period_secs(u64::MAX);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.request_withdraw(deposit_amount);
+    assert_eq!(magni_mut.withdraw_ready_ts(user), u64::MAX);
+    assert!(magni_mut.try_finalize_withdraw().is_err());
+}
+
+#[test]
+#[should_panic(expected = "UnbondingNotComplete")]
+fn test_finalize_cannot_consume_other_users_pending() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+    let carol = env.get_account(3);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Alice and Bob's deposits are delegated, leaving no liquid CSPR
+    env.set_caller(alice);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    env.set_caller(bob);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    // Carol's deposit is liquid and covers exactly one withdrawal
+    env.set_caller(carol);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+
+    // Alice is covered by liquid funds; Bob's request must undelegate
+    env.set_caller(alice);
+    magni_mut.request_withdraw(cspr_to_motes(100));
+    env.set_caller(bob);
+    magni_mut.request_withdraw(cspr_to_motes(100));
+
+    // Bob's stake hasn't come back, so he can't take the CSPR reserved for Alice
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    magni_mut.finalize_withdraw();
+}
+
+#[test]
+fn test_health_factor_saturates_for_dust_debt() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    // 1e-18 mCSPR against 1000 CSPR: the raw ratio is far beyond u64::MAX
+    magni_mut.borrow(U256::one());
+
+    assert_eq!(magni.health_factor_of(user), u64::MAX);
+    assert_eq!(magni.get_position(user).health_factor, u64::MAX);
+    assert_eq!(magni.ltv_of(user), 0);
+}
+
+#[test]
+fn test_ltv_saturates_for_extreme_debt() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1)).deposit();
+
+    let position = magni.simulate_borrow(user, U256::MAX);
+    assert_eq!(position.ltv_bps, u64::MAX);
+    assert_eq!(position.health_factor, 0);
+
+    // Debt with no collateral left behind it
+    let owner = env.get_account(0);
+    let other = env.get_account(2);
+    env.set_caller(owner);
+    assert_eq!(magni.ltv_of(other), 0);
+    magni_mut.migrate_position(other, U512::zero(), U256::from(WAD), env.block_time());
+    assert_eq!(magni.ltv_of(other), u64::MAX);
+}
+
+#[test]
+fn test_simulate_withdraw_across_sizes() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(400u64) * U256::from(WAD));
+
+    // (withdraw CSPR, expected LTV bps)
+    for (withdraw, ltv_bps) in [(0u64, 4000u64), (200, 5000), (500, 8000), (600, 10_000)] {
+        let position = magni.simulate_withdraw(user, cspr_to_motes(withdraw));
+        assert_eq!(position.ltv_bps, ltv_bps);
+        assert_eq!(position.collateral_motes, cspr_to_motes(1000 - withdraw));
+        assert_eq!(position.pending_withdraw_motes, cspr_to_motes(withdraw));
+        assert_eq!(position.status, 1);
+    }
+
+    // Past max LTV the preview still returns, but the real request reverts
+    assert!(magni.simulate_withdraw(user, cspr_to_motes(600)).health_factor < 10_000);
+    assert!(magni_mut.try_request_withdraw(cspr_to_motes(600)).is_err());
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1000));
+}
+
+#[test]
+fn test_simulate_withdraw_clamps_to_collateral() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    let position = magni.simulate_withdraw(user, cspr_to_motes(5000));
+    assert_eq!(position.status, STATUS_CLAMPED);
+    assert_eq!(position.collateral_motes, U512::zero());
+    assert_eq!(position.pending_withdraw_motes, cspr_to_motes(1000));
+    assert_eq!(position.ltv_bps, 0);
+}
+
+#[test]
+fn test_withdraw_partial_maintains_ltv() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow 40% LTV
+    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
+    magni_mut.borrow(borrow_amount);
+
+    // Try to withdraw 50% of collateral (this should keep LTV at 80%, which is max)
+    let withdraw_amount = deposit_amount / U512::from(2u64);
+    magni_mut.request_withdraw(withdraw_amount);
+
+    // Check status
+    assert_eq!(magni_mut.status_of(user), 2); // Withdrawing
+}
+
+#[test]
+#[should_panic(expected = "LtvExceeded")]
+fn test_withdraw_exceeds_ltv_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow max (80% LTV)
+    let borrow_amount = max_borrow_wad(deposit_amount);
+    magni_mut.borrow(borrow_amount);
+
+    // Try to withdraw any collateral - should fail as LTV would exceed
+    magni_mut.request_withdraw(U512::from(1u64));
+}
+
+#[test]
+#[should_panic(expected = "WithdrawPending")]
+fn test_double_withdraw_request_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // First request
+    magni_mut.request_withdraw(deposit_amount / U512::from(2u64));
+
+    // Second request should fail
+    magni_mut.request_withdraw(deposit_amount / U512::from(2u64));
+}
+
+// ==========================================
+// T18: Unit Conversion Tests
+// ==========================================
+
+#[test]
+fn test_motes_to_wad_conversion() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    // 1 CSPR = 1e9 motes = 1e18 wad
+    let one_cspr_motes = cspr_to_motes(1);
+    let one_cspr_wad = motes_to_wad(one_cspr_motes);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(one_cspr_motes).deposit();
+
+    // Get position to check conversion
+    let position = magni_mut.get_position(user);
+    assert_eq!(position.collateral_motes, one_cspr_motes);
+    assert_eq!(position.collateral_wad, one_cspr_wad);
+
+    // 1e18 wad for 1 CSPR
+    assert_eq!(one_cspr_wad, U256::from(WAD));
+}
+
+#[test]
+fn test_ltv_calculation() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow 50% LTV
+    let collateral_wad = motes_to_wad(deposit_amount);
+    let borrow_amount = collateral_wad / U256::from(2u64); // 50%
+    magni_mut.borrow(borrow_amount);
+
+    // Check LTV is 5000 bps (50%)
+    let ltv = magni_mut.ltv_of(user);
+    assert_eq!(ltv, 5000);
+
+    // Health factor should be 8000 / 5000 * 10000 = 16000
+    let hf = magni_mut.health_factor_of(user);
+    assert_eq!(hf, 16000);
+}
+
+// ==========================================
+// T18: Interest Accrual Tests
+// ==========================================
+
+#[test]
+fn test_interest_accrues_over_time() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow
+    let borrow_amount = U256::from(100u64) * U256::from(WAD); // 100 mCSPR
+    magni_mut.borrow(borrow_amount);
+
+    let debt_before = magni_mut.debt_of(user);
+    assert_eq!(debt_before, borrow_amount);
+
+    // Advance time by 1 year (Odra uses milliseconds)
+    env.advance_block_time(31_536_000 * 1000); // 1 year in ms
+
+    // Debt should have increased by 2%
+    let debt_after = magni_mut.debt_of(user);
+    assert!(debt_after > debt_before, "Debt should increase with interest");
+    assert_eq!(debt_after, borrow_amount * U256::from(102u64) / U256::from(100u64));
+}
+
+#[test]
+fn test_interest_affects_ltv() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Borrow near max
+    let max_borrow = max_borrow_wad(deposit_amount);
+    let borrow_amount = max_borrow - (max_borrow / U256::from(100u64)); // 99% of max
+    magni_mut.borrow(borrow_amount);
+
+    let ltv_before = magni_mut.ltv_of(user);
+
+    // Advance time by 1 year
+    env.advance_block_time(31_536_000 * 1000);
+
+    // LTV should have increased due to interest
+    let ltv_after = magni_mut.ltv_of(user);
+    assert!(ltv_after > ltv_before);
+}
+
+#[test]
+fn test_interest_accumulators_track_accrual_and_repayment() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let other = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrow_amount);
+
+    // A second borrower funds the user's interest payment
+    env.set_caller(other);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrow_amount);
+    mcspr_mut.transfer(user, borrow_amount);
+
+    env.advance_block_time(86_400_000);
+    let interest = magni_mut.pending_interest_of(user);
+    assert!(interest > U256::zero());
+    assert_eq!(magni_mut.total_interest_accrued(), U256::zero());
+
+    // A partial repayment below the accrued interest is all interest
+    env.set_caller(user);
+    mcspr_mut.approve(magni.address(), U256::MAX);
+    let partial = interest / U256::from(2u64);
+    magni_mut.repay(partial);
+    assert_eq!(magni_mut.total_interest_accrued(), interest);
+    assert_eq!(magni_mut.total_interest_repaid(), partial);
+
+    // Repaying the rest settles the remaining interest, then principal
+    magni_mut.repay_all();
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+    assert_eq!(magni_mut.total_interest_accrued(), interest);
+    assert_eq!(magni_mut.total_interest_repaid(), interest);
+}
+
+#[test]
+fn test_repay_all_amount_covers_interest_until_repay() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let other = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrow_amount);
+    env.set_caller(other);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrow_amount);
+    mcspr_mut.transfer(user, borrow_amount);
+
+    env.advance_block_time(86_400_000);
+    let debt = magni_mut.debt_of(user);
+    let amount = magni_mut.repay_all_amount(user, 10);
+    // debt * 1.001, rounded up
+    let expected = (debt * U256::from(10_010u64) + U256::from(9_999u64)) / U256::from(10_000u64);
+    assert_eq!(amount, expected);
+    assert!(amount > debt);
+    assert_eq!(magni_mut.repay_all_amount(user, 0), debt);
+
+    // Approving the buffered amount survives interest accrued before the repay
+    env.set_caller(user);
+    mcspr_mut.approve(magni.address(), amount);
+    env.advance_block_time(60_000);
+    magni_mut.repay_all();
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+}
+
+#[test]
+fn test_interest_free_window_skips_interest() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_interest_free_secs(86_400);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let principal = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(principal);
+    assert_eq!(magni_mut.interest_free_until(user), env.block_time() + 86_400_000);
+
+    // Inside the window nothing accrues, even once written to state
+    env.advance_block_time(86_400_000);
+    assert_eq!(magni_mut.pending_interest_of(user), U256::zero());
+    magni_mut.borrow(U256::from(WAD));
+    assert_eq!(magni_mut.debt_of(user), principal + U256::from(WAD));
+
+    // Past the window interest runs on the whole debt again
+    env.advance_block_time(86_400_000);
+    assert_eq!(
+        magni_mut.pending_interest_of(user),
+        expected_interest(principal + U256::from(WAD), 200, 86_400_000)
+    );
+}
+
+#[test]
+fn test_interest_free_window_charges_only_time_after_it() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_interest_free_secs(86_400);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let principal = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(principal);
+
+    // One accrual period straddling the window end pays for the second half only
+    env.advance_block_time(2 * 86_400_000);
+    assert_eq!(magni_mut.pending_interest_of(user), expected_interest(principal, 200, 86_400_000));
+}
+
+#[test]
+fn test_unbounded_interest_free_window_saturates() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_interest_free_secs(u64::MAX);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+    assert_eq!(magni_mut.interest_free_until(user), u64::MAX);
+
+    env.advance_block_time(86_400_000);
+    assert_eq!(magni_mut.pending_interest_of(user), U256::zero());
+}
+
+#[test]
+fn test_interest_repaid_excludes_principal() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrow_amount);
+
+    // Repaying before any time passes is principal only
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), borrow_amount);
+    magni_mut.repay(borrow_amount / U256::from(2u64));
+    assert_eq!(magni_mut.total_interest_accrued(), U256::zero());
+    assert_eq!(magni_mut.total_interest_repaid(), U256::zero());
+}
+
+/// Number of BecameLiquidatable events emitted by `magni`
+fn became_liquidatable_count(env: &odra::host::HostEnv, magni: &MagniHostRef) -> u32 {
+    (0..env.events_count(magni))
+        .filter(|i| env.get_event::<BecameLiquidatable>(magni, *i as i32).is_ok())
+        .count() as u32
+}
+
+#[test]
+fn test_became_liquidatable_fires_once_per_crossing() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(max_borrow_wad(deposit_amount));
+
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), U256::from(1000u64) * U256::from(WAD));
+    let one = U256::from(WAD);
+
+    // Interest pushes the 80% position past the 85% threshold
+    env.advance_block_time(120_000_000_000);
+    magni_mut.repay(one);
+    assert!(magni.ltv_of(user) > 8500);
+    assert_eq!(became_liquidatable_count(&env, &magni), 1);
+    assert!(env.emitted(&magni, "BecameLiquidatable"));
+
+    // Still above the threshold: no repeat
+    env.advance_block_time(10_000_000_000);
+    magni_mut.repay(one);
+    assert_eq!(became_liquidatable_count(&env, &magni), 1);
+
+    // Back to health re-arms the flag
+    magni_mut.repay(U256::from(200u64) * U256::from(WAD));
+    assert!(magni.ltv_of(user) < 8500);
+    env.advance_block_time(500_000_000_000);
+    magni_mut.repay(one);
+    assert_eq!(became_liquidatable_count(&env, &magni), 2);
+}
+
+/// Grace period applied by default before a vault may be liquidated (ms)
+const LIQUIDATION_GRACE_MS: u64 = 3_600_000;
+
+/// Push a max-LTV vault over the liquidation threshold and start its grace period.
+/// The liquidator holds 200 mCSPR approved to the vault.
+fn setup_liquidatable(env: &odra::host::HostEnv) -> (MagniHostRef, Address, Address) {
+    let (mcspr, magni, _) = deploy_contracts(env);
+    let user = env.get_account(1);
+    let liquidator = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(max_borrow_wad(deposit_amount));
+
+    env.set_caller(liquidator);
+    let liquidator_funds = U256::from(200u64) * U256::from(WAD);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(liquidator_funds);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), liquidator_funds);
+
+    env.advance_block_time(150_000_000_000);
+    magni_mut.sync_position(user);
+    assert!(magni_mut.ltv_of(user) > 8500);
+    assert_eq!(magni_mut.liquidatable_since(user), env.block_time());
+    (magni_mut, user, liquidator)
+}
+
+#[test]
+#[should_panic(expected = "GracePeriodActive")]
+fn test_liquidate_within_grace_period_reverts() {
+    let env = odra_test::env();
+    let (mut magni, user, _) = setup_liquidatable(&env);
+
+    env.advance_block_time(LIQUIDATION_GRACE_MS - 1);
+    magni.liquidate(user, U256::from(100u64) * U256::from(WAD));
+}
+
+#[test]
+fn test_liquidate_after_grace_period_seizes_collateral() {
+    let env = odra_test::env();
+    let (mut magni, user, liquidator) = setup_liquidatable(&env);
+
+    env.advance_block_time(LIQUIDATION_GRACE_MS);
+    let debt_before = magni.debt_of(user);
+    let repaid = U256::from(100u64) * U256::from(WAD);
+    magni.liquidate(user, repaid);
+
+    // 100 mCSPR of debt buys 105 CSPR of collateral
+    let seized = cspr_to_motes(105);
+    assert_eq!(magni.debt_of(user), debt_before - repaid);
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1000) - seized);
+    assert_eq!(magni.collateral_of(liquidator), cspr_to_motes(1000) + seized);
+    assert!(env.emitted_event(&magni, Liquidated {
+        user,
+        liquidator,
+        repaid_wad: repaid,
+        seized_motes: seized,
+        reserve_motes: U512::zero(),
+    }));
+}
+
+#[test]
+fn test_liquidation_bonus_split_with_reserve() {
+    let env = odra_test::env();
+    let (mut magni, user, liquidator) = setup_liquidatable(&env);
+    let owner = env.get_account(0);
+
+    env.set_caller(owner);
+    assert!(magni.try_set_liquidation_reserve_share_bps(5001).is_err());
+    magni.set_liquidation_reserve_share_bps(4000);
+
+    env.set_caller(liquidator);
+    env.advance_block_time(LIQUIDATION_GRACE_MS);
+    let total_before = magni.total_collateral();
+    let repaid = U256::from(100u64) * U256::from(WAD);
+    magni.liquidate(user, repaid);
+
+    // 105 CSPR seized: the 5 CSPR bonus splits 3 to the liquidator, 2 to the reserve
+    let seized = cspr_to_motes(105);
+    let reserve = cspr_to_motes(2);
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(1000) - seized);
+    assert_eq!(magni.collateral_of(liquidator), cspr_to_motes(1103));
+    assert_eq!(magni.liquidation_reserve_motes(), reserve);
+    assert_eq!(magni.total_collateral(), total_before - reserve);
+    assert!(env.emitted_event(&magni, Liquidated {
+        user,
+        liquidator,
+        repaid_wad: repaid,
+        seized_motes: seized,
+        reserve_motes: reserve,
+    }));
+
+    // The kept bonus is paid out to the fee recipient (the owner by default)
+    env.set_caller(owner);
+    let owner_before = env.balance_of(&owner);
+    magni.claim_liquidation_reserve();
+    assert_eq!(env.balance_of(&owner), owner_before + reserve);
+    assert_eq!(magni.liquidation_reserve_motes(), U512::zero());
+}
+
+#[test]
+#[should_panic(expected = "NotLiquidatable")]
+fn test_liquidate_healthy_vault_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+
+    env.set_caller(env.get_account(2));
+    magni_mut.liquidate(user, U256::from(WAD));
+}
+
+#[test]
+fn test_recovery_resets_liquidation_grace_timer() {
+    let env = odra_test::env();
+    let (mut magni, user, liquidator) = setup_liquidatable(&env);
+    let first_since = magni.liquidatable_since(user);
+
+    // Topping up brings the vault back to health and clears the timer
+    env.set_caller(user);
+    magni.with_tokens(cspr_to_motes(100)).deposit();
+    assert!(magni.ltv_of(user) <= 8500);
+    assert_eq!(magni.liquidatable_since(user), 0);
+
+    // Degrading again starts a fresh grace period
+    env.advance_block_time(150_000_000_000);
+    magni.sync_position(user);
+    let second_since = magni.liquidatable_since(user);
+    assert_eq!(second_since, env.block_time());
+    assert!(second_since > first_since + LIQUIDATION_GRACE_MS);
+
+    env.set_caller(liquidator);
+    env.advance_block_time(LIQUIDATION_GRACE_MS / 2);
+    assert!(magni.try_liquidate(user, U256::from(WAD)).is_err());
+    env.advance_block_time(LIQUIDATION_GRACE_MS / 2);
+    magni.liquidate(user, U256::from(WAD));
+}
+
+/// Deploy, require a 10% health buffer and open a 1000 CSPR vault for account 1
+fn setup_health_buffer(env: &odra::host::HostEnv) -> (MagniHostRef, Address) {
+    let (_, magni, _) = deploy_contracts(env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(env.get_account(0));
+    magni_mut.set_min_borrow_health_factor(11_000);
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    (magni_mut, user)
+}
+
+#[test]
+fn test_borrow_up_to_health_buffer() {
+    let env = odra_test::env();
+    let (mut magni, user) = setup_health_buffer(&env);
+    assert_eq!(magni.min_borrow_health_factor(), 11_000);
+
+    // Max debt is 800; a health factor of 1.1 allows 800 / 1.1 = 727.27...
+    let max_debt = max_borrow_wad(cspr_to_motes(1000));
+    let limit = max_debt * U256::from(BPS_DIVISOR) / U256::from(11_000u64);
+    magni.borrow(limit);
+    assert!(magni.health_factor_of(user) >= 11_000);
+    assert_eq!(magni.debt_of(user), limit);
+}
+
+#[test]
+#[should_panic(expected = "HealthFactorTooLow")]
+fn test_borrow_past_health_buffer_reverts() {
+    let env = odra_test::env();
+    let (mut magni, _) = setup_health_buffer(&env);
+
+    // Still under max LTV, but inside the buffer
+    let max_debt = max_borrow_wad(cspr_to_motes(1000));
+    let limit = max_debt * U256::from(BPS_DIVISOR) / U256::from(11_000u64);
+    magni.borrow(limit + U256::one());
+}
+
+#[test]
+fn test_set_min_borrow_health_factor_validates() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    assert_eq!(magni_mut.min_borrow_health_factor(), 10_000);
+
+    assert!(magni_mut.try_set_min_borrow_health_factor(9_999).is_err());
+    env.set_caller(env.get_account(1));
+    assert!(magni_mut.try_set_min_borrow_health_factor(12_000).is_err());
+}
+
+#[test]
+fn test_accrue_many_brings_total_debt_up_to_date() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let borrowers = [env.get_account(1), env.get_account(2), env.get_account(3)];
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    for (i, borrower) in borrowers.iter().enumerate() {
+        env.set_caller(*borrower);
+        magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+        magni_mut.borrow(U256::from(100u64 * (i as u64 + 1)) * U256::from(WAD));
+    }
+    env.advance_block_time(86_400_000);
+    let principal_total = U256::from(600u64) * U256::from(WAD);
+    assert_eq!(magni_mut.total_debt(), principal_total);
+
+    // An account without a vault is skipped
+    env.set_caller(env.get_account(4));
+    let mut users = borrowers.to_vec();
+    users.push(env.get_account(5));
+    magni_mut.accrue_many(users);
+
+    let sum = borrowers
+        .iter()
+        .fold(U256::zero(), |sum, borrower| sum + magni_mut.debt_of(*borrower));
+    assert!(sum > principal_total);
+    assert_eq!(magni_mut.total_debt(), sum);
+}
+
+#[test]
+#[should_panic(expected = "BatchTooLarge")]
+fn test_accrue_many_over_batch_limit_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    magni_mut.accrue_many(vec![env.get_account(1); 51]);
+}
+
+#[test]
+fn test_unhealthy_among_returns_only_positions_over_threshold() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let (maxed, modest, idle) = (env.get_account(1), env.get_account(2), env.get_account(3));
+    let no_vault = env.get_account(4);
+
+    for (user, borrow) in [(maxed, 800u64), (modest, 100u64), (idle, 0u64)] {
+        env.set_caller(user);
+        magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+        if borrow > 0 {
+            magni_mut.borrow(U256::from(borrow) * U256::from(WAD));
+        }
+    }
+    // Interest pushes the maxed-out vault past 80%
+    env.advance_block_time(86_400_000_000);
+
+    let users = vec![idle, maxed, no_vault, modest];
+    assert_eq!(magni_mut.unhealthy_among(users.clone(), 8000), vec![maxed]);
+    assert_eq!(magni_mut.unhealthy_among(users, 0), vec![maxed, modest]);
+    assert!(magni_mut.try_unhealthy_among(vec![maxed; 51], 8000).is_err());
+}
+
+#[test]
+fn test_liquidate_at_close_factor_boundary() {
+    let env = odra_test::env();
+    let (mut magni, user, _) = setup_liquidatable(&env);
+    assert_eq!(magni.close_factor_bps(), 5000);
+
+    env.set_caller(env.get_account(0));
+    magni.set_close_factor_bps(1000);
+    env.set_caller(env.get_account(2));
+    env.advance_block_time(LIQUIDATION_GRACE_MS);
+
+    // 10% of the debt is the most one call may repay
+    let debt = magni.debt_of(user);
+    let max_repay = debt * U256::from(1000u64) / U256::from(BPS_DIVISOR);
+    assert!(magni.try_liquidate(user, max_repay + U256::one()).is_err());
+    magni.liquidate(user, max_repay);
+    assert_eq!(magni.debt_of(user), debt - max_repay);
+}
+
+#[test]
+#[should_panic(expected = "CloseFactorExceeded")]
+fn test_liquidate_above_close_factor_reverts() {
+    let env = odra_test::env();
+    let (mut magni, user, _) = setup_liquidatable(&env);
+
+    env.set_caller(env.get_account(0));
+    magni.set_close_factor_bps(1000);
+    env.set_caller(env.get_account(2));
+    env.advance_block_time(LIQUIDATION_GRACE_MS);
+    magni.liquidate(user, U256::from(150u64) * U256::from(WAD));
+}
+
+#[test]
+fn test_liquidate_past_close_factor_when_remainder_is_dust() {
+    let env = odra_test::env();
+    let (mut magni, user, _) = setup_liquidatable(&env);
+
+    // Whatever a 10% repay leaves behind now counts as dust
+    env.set_caller(env.get_account(0));
+    magni.set_close_factor_bps(1000);
+    magni.set_liquidation_dust_wad(U256::from(1_000u64) * U256::from(WAD));
+    env.set_caller(env.get_account(2));
+    env.advance_block_time(LIQUIDATION_GRACE_MS);
+
+    let debt = magni.debt_of(user);
+    let repaid = U256::from(150u64) * U256::from(WAD);
+    magni.liquidate(user, repaid);
+    assert_eq!(magni.debt_of(user), debt - repaid);
+}
+
+#[test]
+fn test_set_close_factor_validates_range() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    assert!(magni_mut.try_set_close_factor_bps(0).is_err());
+    assert!(magni_mut.try_set_close_factor_bps(10_001).is_err());
+    magni_mut.set_close_factor_bps(10_000);
+    assert_eq!(magni_mut.close_factor_bps(), 10_000);
+
+    env.set_caller(env.get_account(1));
+    assert!(magni_mut.try_set_close_factor_bps(5000).is_err());
+    assert!(magni_mut.try_set_liquidation_dust_wad(U256::zero()).is_err());
+}
+
+/// Deploy with a two-slope model, open a 1000 CSPR vault and borrow `borrow_cspr`
+fn setup_rate_model(env: &odra::host::HostEnv, borrow_cspr: u64) -> (MagniHostRef, Address) {
+    let (_, magni, _) = deploy_contracts(env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    // 2% base, +4% up to 50% utilization, +60% from 50% to 100%
+    magni_mut.set_interest_rate_model(200, 400, 6000, 5000);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(borrow_cspr) * U256::from(WAD));
+    (magni_mut, user)
+}
+
+/// Interest on `principal` at `rate_bps` over `elapsed` ms of block time
+fn expected_interest(principal: U256, rate_bps: u64, elapsed: u64) -> U256 {
+    principal * U256::from(rate_bps) * U256::from(elapsed)
+        / U256::from(31_536_000_000u128 * BPS_DIVISOR as u128)
+}
+
+#[test]
+fn test_effective_apr_matches_debt_growth_over_a_year() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let principal = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(principal);
+
+    assert_eq!(magni_mut.current_interest_rate_bps(), 200);
+    let apr = magni_mut.effective_apr_bps();
+    assert_eq!(apr, 200);
+
+    // One calendar year of block time
+    env.advance_block_time(31_536_000_000);
+    let growth = magni_mut.debt_of(user) - principal;
+    assert_eq!(growth, principal * U256::from(apr) / U256::from(BPS_DIVISOR));
+}
+
+#[test]
+fn test_one_year_of_block_time_accrues_exactly_two_percent() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(2000)).deposit();
+    let principal = U256::from(1000u64) * U256::from(WAD);
+    magni_mut.borrow(principal);
+    assert_eq!(magni_mut.current_interest_rate_bps(), 200);
+
+    // 365 days of block time, in Odra's milliseconds
+    env.advance_block_time(31_536_000 * 1000);
+    magni_mut.sync_position(user);
+
+    let debt = magni_mut.debt_of(user);
+    let expected = U256::from(1020u64) * U256::from(WAD);
+    let tolerance = U256::from(1_000_000_000u64);
+    let diff = if debt > expected { debt - expected } else { expected - debt };
+    assert!(diff <= tolerance, "debt after one year: {}", debt);
+}
+
+#[test]
+fn test_interest_rate_low_utilization() {
+    let env = odra_test::env();
+    let (magni, user) = setup_rate_model(&env, 100);
+
+    // 10% utilization: 200 + 400 * 1000 / 5000
+    assert_eq!(magni.utilization_bps(), 1000);
+    assert_eq!(magni.current_interest_rate_bps(), 280);
+
+    env.advance_block_time(86_400_000);
+    let principal = U256::from(100u64) * U256::from(WAD);
+    assert_eq!(magni.pending_interest_of(user), expected_interest(principal, 280, 86_400_000));
+}
+
+#[test]
+fn test_interest_rate_high_utilization() {
+    let env = odra_test::env();
+    let (magni, user) = setup_rate_model(&env, 700);
+
+    // 70% utilization: 200 + 400 + 6000 * 2000 / 5000
+    assert_eq!(magni.utilization_bps(), 7000);
+    assert_eq!(magni.current_interest_rate_bps(), 3000);
+
+    env.advance_block_time(86_400_000);
+    let principal = U256::from(700u64) * U256::from(WAD);
+    let interest = magni.pending_interest_of(user);
+    assert_eq!(interest, expected_interest(principal, 3000, 86_400_000));
+    // Per unit of debt, the steep slope charges far more than the low-utilization case
+    assert!(interest / U256::from(7u64) > expected_interest(principal / U256::from(7u64), 280, 86_400_000));
+}
+
+#[test]
+#[should_panic(expected = "InvalidConfig")]
+fn test_interest_rate_model_rejects_full_kink() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.set_interest_rate_model(200, 400, 6000, BPS_DIVISOR);
+}
+
+#[test]
+fn test_interest_rate_model_caps_each_rate() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    assert!(magni_mut.try_set_interest_rate_model(10_001, 0, 0, 5000).is_err());
+    assert!(magni_mut.try_set_interest_rate_model(0, 10_001, 0, 5000).is_err());
+    assert!(magni_mut.try_set_interest_rate_model(0, 0, 50_001, 5000).is_err());
+    assert!(magni_mut.try_set_interest_rate_model(u64::MAX, u64::MAX, u64::MAX, 5000).is_err());
+
+    // At the caps a fully utilized pool still prices without overflow
+    magni_mut.set_interest_rate_model(10_000, 10_000, 50_000, 5000);
+    let user = env.get_account(1);
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(700u64) * U256::from(WAD));
+    assert_eq!(magni_mut.utilization_bps(), 7000);
+    assert_eq!(magni_mut.current_interest_rate_bps(), 10_000 + 10_000 + 50_000 * 2000 / 5000);
+    env.advance_block_time(86_400_000);
+    magni_mut.sync_position(user);
+    assert!(magni_mut.debt_of(user) > U256::from(700u64) * U256::from(WAD));
+}
+
+/// Open a vault borrowed at the default LTV max, then lower the max to 50%
+fn setup_lowered_ltv_max(
+    env: &odra::host::HostEnv,
+) -> (MCSPRTokenHostRef, MagniHostRef, Address) {
+    let (mcspr, magni, _) = deploy_contracts(env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(max_borrow_wad(deposit_amount));
+
+    env.set_caller(owner);
+    magni_mut.set_ltv_max_bps(5000);
+    env.set_caller(user);
+    (mcspr, magni_mut, user)
+}
+
+#[test]
+fn test_lowered_ltv_max_still_allows_repay() {
+    let env = odra_test::env();
+    let (mcspr, mut magni, user) = setup_lowered_ltv_max(&env);
+    assert_eq!(magni.ltv_max_bps(), 5000);
+    assert_eq!(magni.get_config().ltv_max_bps, 5000);
+
+    // Position sits at the old 80% max, above the new limit, and can still repay
+    let repay_amount = U256::from(100u64) * U256::from(WAD);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), repay_amount);
+    magni.repay(repay_amount);
+    assert_eq!(magni.debt_of(user), max_borrow_wad(cspr_to_motes(1000)) - repay_amount);
+}
+
+#[test]
+#[should_panic(expected = "LtvExceeded")]
+fn test_lowered_ltv_max_applies_to_new_borrows() {
+    let env = odra_test::env();
+    let (_, mut magni, _) = setup_lowered_ltv_max(&env);
+
+    // A fresh vault may only borrow up to the new 50% limit
+    env.set_caller(env.get_account(2));
+    magni.with_tokens(cspr_to_motes(1000)).deposit();
+    magni.borrow(motes_to_wad(cspr_to_motes(500)));
+    magni.borrow(U256::one());
+}
+
+#[test]
+#[should_panic(expected = "InvalidConfig")]
+fn test_ltv_max_above_liquidation_threshold_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.set_ltv_max_bps(9000);
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_set_ltv_max_requires_owner() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    env.set_caller(env.get_account(1));
+    magni_mut.set_ltv_max_bps(7000);
+}
+
+#[test]
+fn test_pending_interest_of_tracks_unwritten_interest() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    assert_eq!(magni_mut.pending_interest_of(user), U256::zero());
+
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+    assert_eq!(magni_mut.pending_interest_of(user), U256::zero());
+
+    env.advance_block_time(86_400 * 1000);
+    let pending = magni_mut.pending_interest_of(user);
+    assert!(pending > U256::zero());
+    assert_eq!(pending, magni_mut.debt_of(user) - borrow_amount);
+}
+
+// ==========================================
+// T18: Admin Tests
+// ==========================================
+
+#[test]
+fn test_pause_unpause() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Pause
+    env.set_caller(owner);
+    magni_mut.pause();
+    assert!(magni_mut.is_paused());
+
+    // Unpause
+    magni_mut.unpause();
+    assert!(!magni_mut.is_paused());
+}
+
+#[test]
+fn test_operation_status_tracks_individual_flags() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    let all = OP_DEPOSIT | OP_BORROW | OP_REPAY | OP_WITHDRAW;
+    assert_eq!(magni.operation_status(), all);
+
+    env.set_caller(owner);
+    magni_mut.set_operations_paused(OP_BORROW, true);
+    assert_eq!(magni.operation_status(), OP_DEPOSIT | OP_REPAY | OP_WITHDRAW);
+    assert!(!magni.is_paused());
+
+    magni_mut.set_operations_paused(OP_DEPOSIT | OP_WITHDRAW, true);
+    assert_eq!(magni.operation_status(), OP_REPAY);
+
+    magni_mut.set_operations_paused(OP_BORROW, false);
+    assert_eq!(magni.operation_status(), OP_BORROW | OP_REPAY);
+
+    // Global pause overrides everything
+    magni_mut.pause();
+    assert_eq!(magni.operation_status(), 0);
+    magni_mut.unpause();
+    assert_eq!(magni.operation_status(), OP_BORROW | OP_REPAY);
+}
+
+#[test]
+#[should_panic(expected = "OperationPaused")]
+fn test_paused_operation_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    env.set_caller(owner);
+    magni_mut.set_operations_paused(OP_BORROW, true);
+
+    env.set_caller(user);
+    magni_mut.borrow(U256::from(WAD));
+}
+
+#[test]
+fn test_get_config_matches_init_parameters() {
+    let env = odra_test::env();
+    let (mcspr, magni, validator_hex) = deploy_contracts(&env);
+
+    let config = magni.get_config();
+    assert_eq!(config.mcspr, Some(mcspr.address()));
+    assert_eq!(config.validator_public_key, validator_hex);
+    assert_eq!(config.ltv_max_bps, LTV_MAX_BPS);
+    assert_eq!(config.interest_rate_bps, 200);
+    assert_eq!(config.base_rate_bps, 200);
+    assert_eq!(config.optimal_utilization_bps, 8000);
+    assert_eq!(config.liquidation_threshold_bps, 8500);
+    assert_eq!(config.min_delegation_motes, cspr_to_motes(500));
+    assert_eq!(config.min_deposit_motes, cspr_to_motes(1));
+    assert_eq!(config.reserve_factor_bps, 0);
+    assert!(!config.auto_delegate);
+    assert_eq!(config.unbonding_period_secs, UNBONDING_PERIOD_SECS);
+    assert!(!config.paused);
+    assert_eq!(config.price_oracle, None);
+    assert_eq!(config.price_feed_id, "");
+}
+
+#[test]
+fn test_collateral_value_usd_uses_mock_price() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    // 1000 CSPR * $0.02 = $20
+    let expected = U256::from(1000u64) * U256::from(MOCK_CSPR_USD_PRICE);
+    assert_eq!(magni.collateral_value_usd(user), expected);
+    assert_eq!(expected, U256::from(20u64) * U256::from(WAD));
+    assert_eq!(magni.collateral_value_usd(env.get_account(2)), U256::zero());
+}
+
+#[test]
+fn test_vault_operations_never_read_the_oracle() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let oracle = CountingStyksOracle::deploy(
+        &env,
+        CountingStyksOracleInitArgs {
+            price: U256::from(MOCK_CSPR_USD_PRICE),
+        },
+    );
+    magni_mut.set_price_oracle(oracle.address(), "CSPR/USD".to_string());
+
+    // Vault operations are mCSPR-denominated and never price
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+    magni_mut.request_withdraw(cspr_to_motes(100));
+    magni_mut.get_position(user);
+    assert_eq!(env.events_count(&oracle), 0);
+
+    // One USD valuation is one price fetch
+    let value = magni_mut.collateral_value_usd(user);
+    assert_eq!(value, U256::from(900u64) * U256::from(MOCK_CSPR_USD_PRICE));
+    assert_eq!(env.events_count(&oracle), 1);
+    assert!(env.emitted_event(&oracle, PriceRead {
+        feed_id: "CSPR/USD".to_string(),
+    }));
+}
+
+#[test]
+fn test_mock_oracle_prices_vault_through_external_call() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+
+    // $0.05 per CSPR
+    let price = U256::from(50_000_000_000_000_000u128);
+    oracle.set_price(feed_id.clone(), price);
+    assert_eq!(oracle.get_twap_price(feed_id.clone()), Some(price));
+    assert_eq!(oracle.get_latest_price("BTC/USD".to_string()), None);
+    magni_mut.set_price_oracle(oracle.address(), feed_id.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    assert_eq!(magni_mut.collateral_value_usd(user), U256::from(50u64) * U256::from(WAD));
+
+    // Price updates are picked up on the next read
+    oracle.set_price(feed_id, price * U256::from(2u64));
+    assert_eq!(magni_mut.collateral_value_usd(user), U256::from(100u64) * U256::from(WAD));
+}
+
+#[test]
+#[should_panic(expected = "PriceUnavailable")]
+fn test_mock_oracle_without_price_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let oracle = MockStyksOracle::deploy(&env, NoArgs);
+    magni_mut.set_price_oracle(oracle.address(), "CSPR/USD".to_string());
+
+    magni_mut.collateral_value_usd(env.get_account(1));
+}
+
+#[test]
+fn test_current_price_reports_mock_fallback() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(env.get_account(1));
+    let price = U256::from(MOCK_CSPR_USD_PRICE);
+    assert_eq!(magni_mut.current_price(), price);
+    assert!(env.emitted_event(&magni, PriceObserved {
+        price_wad: price,
+        source: PRICE_SOURCE_MOCK,
+    }));
+}
+
+#[test]
+fn test_current_price_reports_oracle_source() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+    let price = U256::from(50_000_000_000_000_000u128);
+    oracle.set_price(feed_id.clone(), price);
+    magni_mut.set_price_oracle(oracle.address(), feed_id);
+
+    env.set_caller(env.get_account(1));
+    assert_eq!(magni_mut.current_price(), price);
+    assert!(env.emitted_event(&magni, PriceObserved {
+        price_wad: price,
+        source: PRICE_SOURCE_ORACLE,
+    }));
+}
+
+#[test]
+fn test_price_spike_trips_circuit_breaker() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+    let price = U256::from(50_000_000_000_000_000u128);
+    oracle.set_price(feed_id.clone(), price);
+
+    env.set_caller(owner);
+    magni_mut.set_price_oracle(oracle.address(), feed_id.clone());
+    magni_mut.set_max_price_move_bps(2000);
+    assert!(magni_mut.refresh_price());
+    assert_eq!(magni_mut.last_good_price_wad(), Some(price));
+
+    // A 10% move is within the 20% band and becomes the new reference
+    let moved = price * U256::from(11u64) / U256::from(10u64);
+    oracle.set_price(feed_id.clone(), moved);
+    assert!(magni_mut.refresh_price());
+    assert_eq!(magni_mut.last_good_price_wad(), Some(moved));
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    // Doubling the price is rejected and pauses borrowing
+    oracle.set_price(feed_id, moved * U256::from(2u64));
+    assert!(!magni_mut.refresh_price());
+    assert_eq!(magni_mut.last_good_price_wad(), Some(moved));
+    assert!(env.emitted_event(&magni, CircuitBreakerTripped {
+        last_good_price_wad: moved,
+        price_wad: moved * U256::from(2u64),
+        move_bps: 10_000,
+    }));
+    assert_eq!(magni.operation_status() & OP_BORROW, 0);
+    assert!(magni_mut.try_borrow(U256::from(WAD)).is_err());
+
+    // Only the operator re-enables borrowing
+    env.set_caller(owner);
+    magni_mut.reset_last_good_price();
+    magni_mut.set_operations_paused(OP_BORROW, false);
+    assert!(magni_mut.refresh_price());
+
+    env.set_caller(user);
+    magni_mut.borrow(U256::from(WAD));
+    assert_eq!(magni_mut.debt_of(user), U256::from(WAD));
+}
+
+#[test]
+fn test_price_consumers_refuse_price_outside_breaker_band() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+    let price = U256::from(50_000_000_000_000_000u128);
+    oracle.set_price(feed_id.clone(), price);
+
+    env.set_caller(owner);
+    magni_mut.set_price_oracle(oracle.address(), feed_id.clone());
+    magni_mut.set_max_price_move_bps(2000);
+    assert!(magni_mut.refresh_price());
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    assert_eq!(magni.tvl_usd(), U256::from(50u64) * U256::from(WAD));
+
+    // No refresh_price call needed: every reader checks the band itself
+    oracle.set_price(feed_id, price * U256::from(2u64));
+    let refused = Err(VaultError::PriceMoveExceeded.into());
+    assert_eq!(magni.try_tvl_usd(), refused);
+    assert_eq!(magni.try_collateral_value_usd(user), refused);
+    assert_eq!(magni_mut.try_current_price(), refused);
+    assert!(!env.emitted(&magni, "PriceObserved"));
+
+    // Once the owner accepts the move, the readers use it
+    env.set_caller(owner);
+    magni_mut.reset_last_good_price();
+    assert_eq!(magni.tvl_usd(), U256::from(100u64) * U256::from(WAD));
+    assert_eq!(magni_mut.current_price(), price * U256::from(2u64));
+}
+
+#[test]
+fn test_staleness_guard_accepts_fresh_price() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+    let price = U256::from(50_000_000_000_000_000u128);
+
+    env.advance_block_time(10_000_000);
+    oracle.set_price(feed_id.clone(), price);
+    assert_eq!(
+        oracle.get_price_with_timestamp(feed_id.clone()),
+        Some((price, env.block_time()))
+    );
+    magni_mut.set_price_oracle(oracle.address(), feed_id);
+    magni_mut.set_max_price_staleness_secs(3600);
+    assert_eq!(magni_mut.max_price_staleness_secs(), 3600);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    // Exactly at the limit is still fresh
+    env.advance_block_time(3_600_000);
+    assert_eq!(magni_mut.collateral_value_usd(user), U256::from(50u64) * U256::from(WAD));
+}
+
+#[test]
+#[should_panic(expected = "StalePrice")]
+fn test_staleness_guard_rejects_stale_price() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+
+    env.advance_block_time(10_000_000);
+    // Published two hours ago
+    let published_at = env.block_time() - 7_200_000;
+    oracle.set_price_at(feed_id.clone(), U256::from(WAD), published_at);
+    magni_mut.set_price_oracle(oracle.address(), feed_id);
+    magni_mut.set_max_price_staleness_secs(3600);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.collateral_value_usd(user);
+}
+
+#[test]
+fn test_stale_price_ignored_without_staleness_limit() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut oracle = MockStyksOracle::deploy(&env, NoArgs);
+    let feed_id = "CSPR/USD".to_string();
+
+    oracle.set_price_at(feed_id.clone(), U256::from(WAD), 0);
+    magni_mut.set_price_oracle(oracle.address(), feed_id);
+    env.advance_block_time(86_400_000);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    assert_eq!(magni_mut.collateral_value_usd(user), U256::from(1000u64) * U256::from(WAD));
+}
+
+#[test]
+fn test_set_max_price_staleness_requires_owner() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(env.get_account(1));
+    assert!(magni_mut.try_set_max_price_staleness_secs(60).is_err());
+}
+
+#[test]
+fn test_position_at_half_price_doubles_ltv() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(400u64) * U256::from(WAD));
+
+    let now = magni_mut.get_position(user);
+    assert_eq!(magni_mut.get_position_at_price(user, U256::from(WAD)), now);
+
+    let halved = magni_mut.get_position_at_price(user, U256::from(WAD / 2));
+    assert_eq!(now.ltv_bps, 4000);
+    assert_eq!(halved.ltv_bps, now.ltv_bps * 2);
+    assert_eq!(halved.health_factor, now.health_factor / 2);
+    assert_eq!(halved.collateral_wad, now.collateral_wad / U256::from(2u64));
+    assert_eq!(halved.collateral_motes, now.collateral_motes);
+    assert_eq!(halved.debt_wad, now.debt_wad);
+
+    // The what-if view leaves the live position untouched
+    assert_eq!(magni_mut.get_position(user), now);
+}
+
+#[test]
+fn test_tvl_excludes_pending_withdrawals() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(alice);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    env.set_caller(bob);
+    magni_mut.with_tokens(cspr_to_motes(500)).deposit();
+    assert_eq!(magni_mut.tvl_motes(), cspr_to_motes(1500));
+
+    magni_mut.request_withdraw(cspr_to_motes(200));
+    assert_eq!(magni_mut.tvl_motes(), cspr_to_motes(1300));
+
+    // 1300 CSPR at the $0.02 mock price
+    let expected_usd = U256::from(26u64) * U256::from(WAD);
+    assert_eq!(magni_mut.tvl_usd(), expected_usd);
+}
+
+#[test]
+fn test_debt_value_usd_tracks_debt_with_interest() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+    assert_eq!(magni.debt_value_usd(user), borrow_amount);
+
+    env.advance_block_time(86_400_000);
+    assert_eq!(magni.debt_value_usd(user), magni.debt_of(user));
+    assert!(magni.debt_value_usd(user) > borrow_amount);
+}
+
+#[test]
+#[should_panic(expected = "ContractPaused")]
+fn test_deposit_when_paused_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Pause
+    env.set_caller(owner);
+    magni_mut.pause();
+
+    // Try deposit
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+}
+
+#[test]
+fn test_set_validator_emits_validator_changed() {
+    let env = odra_test::env();
+    let (_, magni, old_validator_hex) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let new_validator_hex = public_key_to_hex(&env.get_validator(1));
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    env.set_caller(owner);
+    allow_validator(&env, &mut magni_mut, &new_validator_hex);
+    magni_mut.set_validator_public_key(new_validator_hex.clone());
+
+    assert_eq!(magni_mut.validator_public_key(), new_validator_hex);
+    assert!(env.emitted_event(
+        &magni,
+        ValidatorChanged {
+            old_key: old_validator_hex,
+            new_key: new_validator_hex,
+            by: owner,
+        }
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_pause_by_non_owner_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Try pause as non-owner
+    env.set_caller(user);
+    magni_mut.pause();
+}
+
+// ==========================================
+// T18: Edge Cases
+// ==========================================
+
+#[test]
+#[should_panic(expected = "ZeroAmount")]
+fn test_deposit_zero_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(U512::zero()).deposit();
+}
+
+#[test]
+#[should_panic(expected = "ZeroAmount")]
+fn test_borrow_zero_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    magni_mut.borrow(U256::zero());
+}
+
+// ==========================================
+// T18: Delegation Batching Tests
+// ==========================================
+
+#[test]
+fn test_delegation_batching_below_minimum() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    // Deposit 100 CSPR (below 500 CSPR minimum)
+    let deposit_amount = cspr_to_motes(100);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Pending should accumulate
+    assert_eq!(magni_mut.pending_to_delegate(), deposit_amount);
+    assert_eq!(magni_mut.total_delegated(), U512::zero());
+}
+
+#[test]
+fn test_delegation_batching_above_minimum() {
+    let env = odra_test::env();
+    let (_, magni, _validator_hex) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let owner = env.get_account(0);
+    let validator = env.get_validator(0);
+
+    env.set_caller(user);
+    // Deposit 600 CSPR (above 500 CSPR minimum)
+    let deposit_amount = cspr_to_motes(600);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    // Delegation is NOT automatic anymore - should accumulate in pending
+    assert_eq!(magni_mut.pending_to_delegate(), deposit_amount);
+    assert_eq!(magni_mut.total_delegated(), U512::zero());
+
+    // Owner triggers delegation manually
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    // Now should be delegated
+    assert_eq!(magni_mut.pending_to_delegate(), U512::zero());
+    assert_eq!(magni_mut.total_delegated(), deposit_amount);
+
+    // Check actual delegation
+    let delegated = env.delegated_amount(magni.address(), validator);
+    assert_eq!(delegated, deposit_amount);
+}
+
+#[test]
+fn test_auto_delegate_disabled_keeps_deposit_pending() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    assert!(!magni_mut.auto_delegate());
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(600);
+    magni_mut.with_tokens(deposit_amount).deposit();
+
+    assert_eq!(magni_mut.pending_to_delegate(), deposit_amount);
+    assert_eq!(magni_mut.total_delegated(), U512::zero());
+}
+
+#[test]
+#[should_panic(expected = "DelegationTooSoon")]
+fn test_force_delegate_twice_within_interval_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_min_delegate_interval_secs(3600);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.advance_block_time(3_599_000);
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+}
+
+#[test]
+#[should_panic(expected = "DelegationTooSoon")]
+fn test_force_delegate_with_huge_interval_reverts_cleanly() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_min_delegate_interval_secs(u64::MAX);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    // The next deposit skips auto-delegation instead of overflowing
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    assert_eq!(magni.pending_to_delegate(), cspr_to_motes(600));
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+}
+
+#[test]
+fn test_force_delegate_after_interval_succeeds() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_min_delegate_interval_secs(3600);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.advance_block_time(3_600_000);
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    assert_eq!(magni.total_delegated(), cspr_to_motes(1200));
+    assert_eq!(magni.pending_to_delegate(), U512::zero());
+}
+
+#[test]
+fn test_delegate_exact_moves_exact_amount_from_pending() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let validator = env.get_validator(0);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(800)).deposit();
+
+    env.set_caller(owner);
+    let amount = cspr_to_motes(500);
+    let pending_before = magni_mut.pending_to_delegate();
+    magni_mut.delegate_exact(amount);
+
+    assert_eq!(pending_before - amount, magni_mut.pending_to_delegate());
+    assert_eq!(magni_mut.total_delegated(), amount);
+    assert_eq!(magni.validator_bonded(validator_hex), amount);
+    assert_eq!(env.delegated_amount(magni.address(), validator), amount);
+}
+
+#[test]
+fn test_force_undelegate_part_of_delegation() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    let amount = cspr_to_motes(400);
+    assert!(magni_mut
+        .try_force_undelegate(validator_hex.clone(), cspr_to_motes(1001))
+        .is_err());
+    magni_mut.force_undelegate(validator_hex.clone(), amount);
+
+    assert!(env.emitted_event(&magni, UndelegationRequested {
+        amount_motes: amount,
+    }));
+    assert_eq!(magni.validator_bonded(validator_hex), cspr_to_motes(600));
+    assert_eq!(magni_mut.undelegating_motes(), amount);
+    // No user withdrawal is involved
+    assert_eq!(magni_mut.total_pending_withdraw(), U512::zero());
+}
+
+#[test]
+#[should_panic(expected = "BelowMinDelegation")]
+fn test_delegate_exact_below_minimum_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(env.get_account(1));
+    magni_mut.with_tokens(cspr_to_motes(800)).deposit();
+    env.set_caller(env.get_account(0));
+    magni_mut.delegate_exact(cspr_to_motes(499));
+}
+
+#[test]
+fn test_delegate_exact_rejects_unbacked_amounts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(800)).deposit();
+    env.set_caller(owner);
+    assert!(magni_mut.try_delegate_exact(cspr_to_motes(900)).is_err());
+
+    // 200 CSPR of the liquid balance is owed to a withdrawal
+    env.set_caller(user);
+    magni_mut.request_withdraw(cspr_to_motes(200));
+    env.set_caller(owner);
+    assert!(magni_mut.try_delegate_exact(cspr_to_motes(700)).is_err());
+    magni_mut.delegate_exact(cspr_to_motes(600));
+    assert_eq!(magni_mut.pending_to_delegate(), cspr_to_motes(200));
+
+    env.set_caller(user);
+    assert!(magni_mut.try_delegate_exact(cspr_to_motes(500)).is_err());
+}
+
+#[test]
+fn test_validator_stake_cap_routes_to_next_validator() {
+    let env = odra_test::env();
+    let (_, magni, primary_hex) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let second_hex = public_key_to_hex(&env.get_validator(1));
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    allow_validator(&env, &mut magni_mut, &second_hex);
+    magni_mut.add_validator(second_hex.clone());
+    magni_mut.set_max_validator_stake_bps(6000);
+    assert_eq!(magni_mut.max_validator_stake_bps(), 6000);
+
+    // The first delegation has nothing to spread against
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    assert_eq!(magni.validator_bonded(primary_hex.clone()), cspr_to_motes(600));
+
+    // Another 600 on the primary would leave it with 100% of stake
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    assert_eq!(magni.validator_bonded(primary_hex), cspr_to_motes(600));
+    assert_eq!(magni.validator_bonded(second_hex), cspr_to_motes(600));
+    assert_eq!(
+        env.delegated_amount(magni.address(), env.get_validator(1)),
+        cspr_to_motes(600)
+    );
+}
+
+#[test]
+#[should_panic(expected = "ValidatorStakeCapExceeded")]
+fn test_delegate_exact_over_validator_cap_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_max_validator_stake_bps(6000);
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1200)).deposit();
+
+    // Only one validator: a second delegation has nowhere else to go
+    env.set_caller(owner);
+    magni_mut.delegate_exact(cspr_to_motes(600));
+    magni_mut.delegate_exact(cspr_to_motes(600));
+}
+
+#[test]
+fn test_capped_batch_delegation_stays_pending() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_max_validator_stake_bps(6000);
+    magni_mut.set_auto_delegate(true);
+    assert!(magni_mut.try_set_max_validator_stake_bps(0).is_err());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    assert_eq!(magni_mut.total_delegated(), cspr_to_motes(600));
+    assert_eq!(magni_mut.pending_to_delegate(), cspr_to_motes(600));
+}
+
+#[test]
+fn test_force_delegate_keeps_undelegated_remainder_pending() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(800)).deposit();
+    magni_mut.request_withdraw(cspr_to_motes(200));
+
+    // Only 600 CSPR is free to delegate; the other 200 stays in the batch
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    assert_eq!(magni_mut.total_delegated(), cspr_to_motes(600));
+    assert_eq!(magni_mut.pending_to_delegate(), cspr_to_motes(200));
+}
+
+#[test]
+fn test_balance_breakdown_after_delegation() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+
+    let breakdown = magni.balance_breakdown();
+    assert_eq!(breakdown.liquid_motes, cspr_to_motes(100));
+    assert_eq!(breakdown.delegated_motes, cspr_to_motes(600));
+    assert_eq!(breakdown.pending_to_delegate_motes, cspr_to_motes(100));
+    assert_eq!(
+        breakdown.liquid_motes + breakdown.delegated_motes,
+        magni.total_collateral()
+    );
+    assert!(breakdown.pending_to_delegate_motes <= breakdown.liquid_motes);
+}
+
+#[test]
+fn test_auto_delegate_enabled_delegates_within_deposit() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let validator = env.get_validator(0);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    env.set_caller(owner);
+    magni_mut.set_auto_delegate(true);
+
+    // Below minimum: still batched
+    env.set_caller(user);
+    let first_deposit = cspr_to_motes(300);
+    magni_mut.with_tokens(first_deposit).deposit();
+    assert_eq!(magni_mut.pending_to_delegate(), first_deposit);
+    assert_eq!(magni_mut.total_delegated(), U512::zero());
+
+    // Crossing the minimum delegates the whole batch in the same call
+    let second_deposit = cspr_to_motes(300);
+    magni_mut.with_tokens(second_deposit).deposit();
+    assert_eq!(magni_mut.pending_to_delegate(), U512::zero());
+    assert_eq!(magni_mut.total_delegated(), first_deposit + second_deposit);
+    assert_eq!(
+        env.delegated_amount(magni.address(), validator),
+        first_deposit + second_deposit
+    );
+}
+
+#[test]
+fn test_motes_until_delegation_tracks_pending_pool() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Empty pool needs the full 500 CSPR minimum
+    assert_eq!(magni_mut.motes_until_delegation(), cspr_to_motes(500));
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(300)).deposit();
+    assert_eq!(magni_mut.motes_until_delegation(), cspr_to_motes(200));
+
+    magni_mut.with_tokens(cspr_to_motes(200)).deposit();
+    assert_eq!(magni_mut.motes_until_delegation(), U512::zero());
+
+    // Beyond the minimum it stays at zero rather than underflowing
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    assert_eq!(magni_mut.pending_to_delegate(), cspr_to_motes(600));
+    assert_eq!(magni_mut.motes_until_delegation(), U512::zero());
+}
+
+// ==========================================
+// Validator Key Validation Tests
+// ==========================================
+
+#[test]
+#[should_panic(expected = "InvalidValidatorKey")]
+fn test_init_with_garbage_validator_key_reverts() {
+    let env = odra_test::env();
+    let owner = env.get_account(0);
+    env.set_caller(owner);
+    let mcspr = MCSPRToken::deploy(&env, MCSPRTokenInitArgs { minter: owner });
+    Magni::deploy(&env, MagniInitArgs {
+        mcspr: mcspr.address(),
+        validator_public_key: "not-a-key".to_string(),
+    });
+}
+
+#[test]
+fn test_init_with_valid_or_empty_validator_key_succeeds() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    assert_eq!(magni.validator_public_key(), validator_hex);
+
+    let owner = env.get_account(0);
+    env.set_caller(owner);
+    let mcspr = MCSPRToken::deploy(&env, MCSPRTokenInitArgs { minter: owner });
+    let unset = Magni::deploy(&env, MagniInitArgs {
+        mcspr: mcspr.address(),
+        validator_public_key: String::new(),
+    });
+    assert_eq!(unset.validator_public_key(), String::new());
+}
+
+#[test]
+#[should_panic(expected = "InvalidValidatorKey")]
+fn test_set_garbage_validator_key_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    env.set_caller(owner);
+    // Valid hex, unknown algorithm tag
+    magni_mut.set_validator_public_key("03abcdef".to_string());
+}
+
+// ==========================================
+// Protocol Reserve Tests
+// ==========================================
+
+#[test]
+fn test_interest_split_between_borrower_and_reserve() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_reserve_factor_bps(1000); // 10% of interest to reserve
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let borrow_amount = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+
+    env.advance_block_time(31_536_000 * 1000);
+
+    // Repay a single wad to write accrued interest to storage
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), U256::one());
+    magni_mut.repay(U256::one());
+
+    let borrower_interest = magni_mut.debt_of(user) + U256::one() - borrow_amount;
+    let reserve = magni_mut.reserve_balance();
+    assert!(borrower_interest > U256::zero());
+    assert!(reserve > U256::zero());
+
+    // Shares sum to total interest, with the reserve taking exactly its factor
+    let total_interest = borrower_interest + reserve;
+    assert_eq!(reserve, total_interest * U256::from(1000u64) / U256::from(BPS_DIVISOR));
+}
+
+#[test]
+fn test_reserve_factor_splits_one_year_of_interest_90_10() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    assert_eq!(magni_mut.reserve_factor_bps(), 0);
+    env.set_caller(owner);
+    magni_mut.set_reserve_factor_bps(1000);
+    assert_eq!(magni_mut.reserve_factor_bps(), 1000);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    let principal = U256::from(100u64) * U256::from(WAD);
+    magni_mut.borrow(principal);
+
+    env.advance_block_time(31_536_000 * 1000);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), U256::one());
+    magni_mut.repay(U256::one());
+
+    // Debt is principal plus the borrower's 90%; the reserve holds the other 10%
+    let borrower_share = magni_mut.debt_of(user) + U256::one() - principal;
+    let reserve = magni_mut.reserve_wad();
+    assert_eq!(reserve, magni_mut.reserve_balance());
+    let total_interest = borrower_share + reserve;
+    assert!(total_interest > U256::zero());
+    assert_eq!(reserve, total_interest / U256::from(10u64));
+    assert_eq!(borrower_share, total_interest - total_interest / U256::from(10u64));
+}
+
+#[test]
+fn test_withdraw_reserve_mints_to_fee_recipient() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let treasury = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_reserve_factor_bps(5000);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(U256::from(100u64) * U256::from(WAD));
+    env.advance_block_time(31_536_000 * 1000);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), U256::one());
+    magni_mut.repay(U256::one());
+
+    let reserve = magni_mut.reserve_balance();
+    env.set_caller(owner);
+    magni_mut.set_fee_recipient(treasury);
+    magni_mut.withdraw_reserve(reserve);
+
+    assert_eq!(magni_mut.reserve_balance(), U256::zero());
+    assert_eq!(mcspr_mut.balance_of(treasury), reserve);
+    assert_eq!(mcspr_mut.balance_of(owner), U256::zero());
+}
+
+#[test]
+fn test_fee_recipient_defaults_to_owner_and_can_change() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let treasury = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    assert_eq!(magni.fee_recipient(), Some(owner));
+
+    env.set_caller(owner);
+    magni_mut.set_fee_recipient(treasury);
+    assert_eq!(magni.fee_recipient(), Some(treasury));
+    assert!(env.emitted_event(&magni, FeeRecipientChanged {
+        old_recipient: Some(owner),
+        new_recipient: treasury,
+    }));
+}
+
+#[test]
+#[should_panic(expected = "InvalidConfig")]
+fn test_fee_recipient_cannot_be_vault() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(env.get_account(0));
+    magni_mut.set_fee_recipient(magni.address());
+}
+
+// ==========================================
+// Validator Registry Tests
+// ==========================================
+
+/// Hex public key of test account `i`, usable as a validator key
+fn account_key_hex(env: &odra::host::HostEnv, i: usize) -> String {
+    public_key_to_hex(&env.public_key(&env.get_account(i)))
+}
+
+#[test]
+fn test_remove_validator_without_stake() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let second = public_key_to_hex(&env.get_validator(1));
+    let third = account_key_hex(&env, 5);
+
+    allow_validator(&env, &mut magni_mut, &second);
+    allow_validator(&env, &mut magni_mut, &third);
+    magni_mut.add_validator(second.clone());
+    magni_mut.add_validator(third.clone());
+    assert_eq!(magni.validators(), vec![validator_hex.clone(), second.clone(), third.clone()]);
+
+    magni_mut.remove_validator(second);
+    // Remaining validators keep their order
+    assert_eq!(magni.validators(), vec![validator_hex.clone(), third]);
+    assert_eq!(magni.get_config().validator_public_key, validator_hex);
+}
+
+#[test]
+#[should_panic(expected = "ValidatorHasStake")]
+fn test_remove_validator_with_stake_reverts() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    assert_eq!(magni.validator_bonded(validator_hex.clone()), cspr_to_motes(600));
+
+    magni_mut.remove_validator(validator_hex);
+}
+
+#[test]
+#[should_panic(expected = "TooManyValidators")]
+fn test_add_validator_beyond_max_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Deployment registered one validator; nine more fill the registry
+    for i in 1..=10 {
+        allow_validator(&env, &mut magni_mut, &account_key_hex(&env, i));
+    }
+    for i in 1..=9 {
+        magni_mut.add_validator(account_key_hex(&env, i));
+    }
+    assert_eq!(magni.validators().len(), 10);
+    magni_mut.add_validator(account_key_hex(&env, 10));
+}
+
+#[test]
+#[should_panic(expected = "ValidatorAlreadyRegistered")]
+fn test_add_duplicate_validator_reverts() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.add_validator(validator_hex);
+}
+
+#[test]
+fn test_validator_allowlist_gates_registration() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let second = public_key_to_hex(&env.get_validator(1));
+
+    // The deployment validator is allowlisted from the start
+    assert!(magni_mut.is_validator_allowed(validator_hex));
+    assert!(!magni_mut.is_validator_allowed(second.clone()));
+    assert!(magni_mut.try_add_validator(second.clone()).is_err());
+    assert!(magni_mut.try_set_validator_public_key(second.clone()).is_err());
+
+    // Allowlisting only takes effect after the delay
+    let allowed_from = env.block_time() + VALIDATOR_ALLOWLIST_DELAY_SECS * 1000;
+    magni_mut.add_allowed_validator(second.clone());
+    assert_eq!(magni_mut.validator_allowed_from(second.clone()), Some(allowed_from));
+    assert!(magni_mut.try_add_validator(second.clone()).is_err());
+
+    env.advance_block_time(VALIDATOR_ALLOWLIST_DELAY_SECS * 1000);
+    assert!(magni_mut.is_validator_allowed(second.clone()));
+    magni_mut.set_validator_public_key(second.clone());
+    assert_eq!(magni_mut.validator_public_key(), second);
+}
+
+#[test]
+#[should_panic(expected = "ValidatorNotAllowlisted")]
+fn test_removed_validator_cannot_be_made_primary() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let second = public_key_to_hex(&env.get_validator(1));
+
+    allow_validator(&env, &mut magni_mut, &second);
+    magni_mut.set_validator_public_key(second);
+    magni_mut.remove_allowed_validator(validator_hex.clone());
+    assert_eq!(magni_mut.validator_allowed_from(validator_hex.clone()), None);
+    magni_mut.set_validator_public_key(validator_hex);
+}
+
+#[test]
+fn test_disallowed_primary_receives_no_new_stake() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let second = public_key_to_hex(&env.get_validator(1));
+
+    allow_validator(&env, &mut magni_mut, &second);
+    magni_mut.add_validator(second.clone());
+    // Still primary and registered, but no longer allowlisted
+    magni_mut.remove_allowed_validator(validator_hex.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1200)).deposit();
+    env.set_caller(owner);
+    magni_mut.delegate_exact(cspr_to_motes(600));
+    magni_mut.force_delegate();
+
+    assert_eq!(magni_mut.validator_public_key(), validator_hex.clone());
+    assert_eq!(magni_mut.validator_bonded(validator_hex), U512::zero());
+    assert_eq!(env.delegated_amount(magni.address(), env.get_validator(0)), U512::zero());
+    assert_eq!(magni_mut.validator_bonded(second), cspr_to_motes(1200));
+
+    // With no allowed validator left, new deposits wait in the batching pool
+    magni_mut.remove_allowed_validator(public_key_to_hex(&env.get_validator(1)));
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    assert!(magni_mut.try_delegate_exact(cspr_to_motes(600)).is_err());
+    magni_mut.force_delegate();
+    assert_eq!(magni_mut.pending_to_delegate(), cspr_to_motes(600));
+    assert_eq!(magni_mut.total_delegated(), cspr_to_motes(1200));
+}
+
+#[test]
+fn test_upgrade_allowlists_registered_validators() {
+    let env = odra_test::env();
+    let (_, magni, validator_hex) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Simulate a version 1 registry that predates the allowlist
+    magni_mut.remove_allowed_validator(validator_hex.clone());
+    magni_mut.set_storage_version(1);
+    magni_mut.on_upgrade();
+    assert!(magni_mut.is_validator_allowed(validator_hex));
+}
+
+// ==========================================
+// Token Rescue Tests
+// ==========================================
+
+#[test]
+fn test_rescue_tokens_returns_stray_tcspr() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let amount = U256::from(5u64) * U256::from(WAD);
+
+    let mut tcspr = TCSPRToken::deploy(&env, NoArgs);
+    tcspr.faucet_mint(magni.address(), amount);
+    assert_eq!(tcspr.balance_of(magni.address()), amount);
+
+    env.set_caller(owner);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.rescue_tokens(tcspr.address(), user, amount);
+
+    assert_eq!(tcspr.balance_of(magni.address()), U256::zero());
+    assert_eq!(tcspr.balance_of(user), amount);
+    assert!(env.emitted_event(&magni, TokensRescued {
+        token: tcspr.address(),
+        to: user,
+        amount,
+    }));
+}
+
+#[test]
+fn test_repay_with_tcspr_when_enabled() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let borrowed = U256::from(200u64) * U256::from(WAD);
+    let amount = U256::from(100u64) * U256::from(WAD);
+
+    env.set_caller(owner);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.set_tcspr_repay_enabled(true);
+    let mut tcspr = TCSPRToken::deploy(&env, NoArgs);
+    tcspr.faucet_mint(user, amount);
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(borrowed);
+    // Stand-in for mCSPR the borrower sold: it ends up protocol-held and is burned
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.transfer(magni.address(), amount);
+    tcspr.approve(magni.address(), amount);
+    magni_mut.repay_with_tcspr(tcspr.address(), amount);
+
+    assert_eq!(magni_mut.debt_of(user), borrowed - amount);
+    assert_eq!(tcspr.balance_of(magni.address()), amount);
+    assert_eq!(tcspr.balance_of(user), U256::zero());
+    assert_eq!(mcspr_mut.balance_of(magni.address()), U256::zero());
+    assert_eq!(mcspr_mut.total_supply(), borrowed - amount);
+    assert!(magni_mut.supply_invariant_ok());
+    assert!(env.emitted_event(&magni, RepaidWithTcspr {
+        user,
+        tcspr: tcspr.address(),
+        amount_wad: amount,
+        new_debt_wad: borrowed - amount,
+    }));
+}
+
+#[test]
+#[should_panic(expected = "TcsprRepayDisabled")]
+fn test_repay_with_tcspr_disabled_by_default() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let amount = U256::from(100u64) * U256::from(WAD);
+
+    let mut tcspr = TCSPRToken::deploy(&env, NoArgs);
+    tcspr.faucet_mint(user, amount);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    magni_mut.borrow(amount);
+    tcspr.approve(magni.address(), amount);
+    assert!(!magni_mut.tcspr_repay_enabled());
+    magni_mut.repay_with_tcspr(tcspr.address(), amount);
+}
+
+#[test]
+#[should_panic(expected = "ProtectedToken")]
+fn test_rescue_tokens_refuses_mcspr() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+
+    env.set_caller(owner);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.rescue_tokens(mcspr.address(), owner, U256::from(WAD));
+}
+
+#[test]
+fn test_rescue_cspr_moves_only_surplus() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let treasury = env.get_account(2);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    assert_eq!(magni.cspr_surplus(), U512::zero());
+
+    // Stray CSPR sent straight to the contract
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(7));
+    assert_eq!(magni.cspr_surplus(), cspr_to_motes(7));
+
+    let treasury_before = env.balance_of(&treasury);
+    env.set_caller(owner);
+    magni_mut.rescue_cspr(treasury, cspr_to_motes(7));
+
+    assert_eq!(env.balance_of(&treasury), treasury_before + cspr_to_motes(7));
+    assert_eq!(magni.cspr_surplus(), U512::zero());
+    assert_eq!(magni.liquid_balance(), cspr_to_motes(1000));
+}
+
+#[test]
+fn test_rescue_cspr_ignores_unbonded_stake_before_sweep() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let treasury = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    env.set_caller(user);
+    magni_mut.request_withdraw(cspr_to_motes(100));
+
+    // The unbonded stake is back in the contract but still tracked as undelegating.
+    // advance_block_time runs no auctions, so the return is made by hand.
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(100));
+    assert_eq!(magni_mut.undelegating_motes(), cspr_to_motes(100));
+    assert_eq!(magni.cspr_surplus(), U512::zero());
+
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(7));
+    assert_eq!(magni.cspr_surplus(), cspr_to_motes(7));
+    env.set_caller(owner);
+    assert!(magni_mut.try_rescue_cspr(treasury, cspr_to_motes(8)).is_err());
+    magni_mut.rescue_cspr(treasury, cspr_to_motes(7));
+
+    // The pending withdrawal is still fully backed
+    assert_eq!(magni.liquid_balance(), cspr_to_motes(100));
+    env.set_caller(user);
+    magni_mut.finalize_withdraw();
+    assert_eq!(magni.collateral_of(user), cspr_to_motes(500));
+}
+
+#[test]
+fn test_harvest_rewards_skims_performance_fee() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+    let treasury = env.get_account(3);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    assert!(magni_mut.try_set_performance_fee_bps(2001).is_err());
+    magni_mut.set_performance_fee_bps(1000);
+    magni_mut.set_fee_recipient(treasury);
+
+    env.set_caller(alice);
+    magni_mut.with_tokens(cspr_to_motes(300)).deposit();
+    env.set_caller(bob);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+
+    // 20 CSPR of rewards land in the contract: 2 are fee, 18 go to depositors 3:1
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(20));
+    env.set_caller(owner);
+    magni_mut.harvest_rewards();
+
+    assert_eq!(magni_mut.accrued_performance_fee(), cspr_to_motes(2));
+    assert_eq!(magni_mut.total_collateral(), cspr_to_motes(418));
+    assert_eq!(magni_mut.collateral_of(alice), cspr_to_motes(300) + cspr_to_motes(27) / 2);
+    assert_eq!(magni_mut.collateral_of(bob), cspr_to_motes(100) + cspr_to_motes(9) / 2);
+    assert_eq!(magni.cspr_surplus(), U512::zero());
+    assert!(env.emitted_event(&magni, RewardsHarvested {
+        rewards_motes: cspr_to_motes(20),
+        fee_motes: cspr_to_motes(2),
+        collateral_index: magni_mut.collateral_index(),
+    }));
+
+    // Nothing new to harvest: principal and the set-aside fee are left alone
+    magni_mut.harvest_rewards();
+    assert_eq!(magni_mut.total_collateral(), cspr_to_motes(418));
+    assert_eq!(magni_mut.accrued_performance_fee(), cspr_to_motes(2));
+
+    let treasury_before = env.balance_of(&treasury);
+    magni_mut.claim_performance_fee();
+    assert_eq!(env.balance_of(&treasury), treasury_before + cspr_to_motes(2));
+    assert_eq!(magni_mut.accrued_performance_fee(), U512::zero());
+    assert_eq!(magni.liquid_balance(), cspr_to_motes(418));
+}
+
+#[test]
+fn test_harvest_rewards_leaves_unswept_withdrawal_alone() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    env.set_caller(user);
+    magni_mut.request_withdraw(cspr_to_motes(100));
+
+    // The withdrawal has unbonded but not been swept; 20 CSPR of rewards arrive too.
+    // advance_block_time runs no auctions, so the unbonded stake is returned by hand.
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(100));
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(20));
+
+    env.set_caller(owner);
+    magni_mut.harvest_rewards();
+
+    // Only the rewards are distributed; the unbonded 100 CSPR still backs the withdrawal
+    assert_eq!(magni_mut.total_collateral(), cspr_to_motes(520));
+    assert_eq!(magni_mut.collateral_of(user), cspr_to_motes(520));
+    assert_eq!(magni_mut.pending_withdraw_of(user), cspr_to_motes(100));
+    assert_eq!(magni.cspr_surplus(), U512::zero());
+    assert!(env.emitted_event(&magni, RewardsHarvested {
+        rewards_motes: cspr_to_motes(20),
+        fee_motes: U512::zero(),
+        collateral_index: magni_mut.collateral_index(),
+    }));
+
+    env.set_caller(user);
+    magni_mut.finalize_withdraw();
+    assert_eq!(magni.liquid_balance(), cspr_to_motes(20));
+}
+
+#[test]
+#[should_panic(expected = "InsufficientSurplus")]
+fn test_rescue_cspr_beyond_surplus_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(7));
+
+    env.set_caller(owner);
+    magni_mut.rescue_cspr(owner, cspr_to_motes(8));
+}
+
+// ==========================================
+// Supply Invariant Tests
+// ==========================================
+
+#[test]
+fn test_supply_invariant_holds_across_borrow_repay_cycles() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+
+    let borrow_amount = U256::from(200u64) * U256::from(WAD);
+    magni_mut.borrow(borrow_amount);
+    assert!(magni_mut.supply_invariant_ok());
+
+    mcspr_mut.approve(magni.address(), borrow_amount / U256::from(2u64));
+    magni_mut.repay(borrow_amount / U256::from(2u64));
+    assert!(magni_mut.supply_invariant_ok());
+
     magni_mut.borrow(borrow_amount);
+    mcspr_mut.approve(magni.address(), magni_mut.debt_of(user));
+    magni_mut.repay_all();
+    assert!(magni_mut.supply_invariant_ok());
+    assert_eq!(mcspr_mut.total_supply(), magni_mut.total_debt());
+}
 
-    let debt_before = magni_mut.debt_of(user);
-    assert_eq!(debt_before, borrow_amount);
+#[test]
+fn test_supply_invariant_detects_unbacked_mint() {
+    let env = odra_test::env();
+    let owner = env.get_account(0);
+    let validator_hex = public_key_to_hex(&env.get_validator(0));
 
-    // Advance time by 1 year (Odra uses milliseconds)
-    env.advance_block_time(31_536_000 * 1000); // 1 year in ms
+    env.set_caller(owner);
+    let mut mcspr = MCSPRToken::deploy(&env, MCSPRTokenInitArgs { minter: owner });
+    // Mint outside the vault before handing over minter rights
+    mcspr.mint(owner, U256::from(WAD));
 
-    // Debt should have increased by ~2%
-    let debt_after = magni_mut.debt_of(user);
+    let magni = Magni::deploy(&env, MagniInitArgs {
+        mcspr: mcspr.address(),
+        validator_public_key: validator_hex,
+    });
+    mcspr.set_minter(magni.address());
 
-    // Just verify interest accrued (may be large if time is in ms)
-    assert!(debt_after > debt_before, "Debt should increase with interest");
+    assert!(!magni.supply_invariant_ok());
 }
 
+// ==========================================
+// Close Vault Tests
+// ==========================================
+
 #[test]
-fn test_interest_affects_ltv() {
+fn test_close_vault_repays_and_requests_full_withdraw() {
     let env = odra_test::env();
-    let (_, magni, _) = deploy_contracts(&env);
+    let (mcspr, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
 
     env.set_caller(user);
     let deposit_amount = cspr_to_motes(1000);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
     magni_mut.with_tokens(deposit_amount).deposit();
-
-    // Borrow near max
-    let max_borrow = max_borrow_wad(deposit_amount);
-    let borrow_amount = max_borrow - (max_borrow / U256::from(100u64)); // 99% of max
+    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
     magni_mut.borrow(borrow_amount);
 
-    let ltv_before = magni_mut.ltv_of(user);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), magni_mut.debt_of(user));
+    magni_mut.close_vault();
 
-    // Advance time by 1 year
-    env.advance_block_time(31_536_000 * 1000);
+    assert_eq!(magni_mut.status_of(user), 2); // Withdrawing
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+    assert_eq!(magni_mut.collateral_of(user), U512::zero());
+    assert_eq!(magni_mut.pending_withdraw_of(user), deposit_amount);
+    assert_eq!(mcspr_mut.balance_of(user), U256::zero());
+}
 
-    // LTV should have increased due to interest
-    let ltv_after = magni_mut.ltv_of(user);
-    assert!(ltv_after > ltv_before);
+#[test]
+#[should_panic(expected = "InsufficientAllowance")]
+fn test_close_vault_without_full_allowance_reverts() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(1000);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    magni_mut.with_tokens(deposit_amount).deposit();
+    let borrow_amount = max_borrow_wad(deposit_amount) / U256::from(2u64);
+    magni_mut.borrow(borrow_amount);
+
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), borrow_amount - U256::one());
+    magni_mut.close_vault();
 }
 
 // ==========================================
-// T18: Admin Tests
+// Undelegation Sweep Tests
 // ==========================================
 
 #[test]
-fn test_pause_unpause() {
+fn test_sweep_undelegated_reconciles_tracking() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let owner = env.get_account(0);
-
+    let user = env.get_account(1);
+    let validator = env.get_validator(0);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
 
-    // Pause
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(600);
+    magni_mut.with_tokens(deposit_amount).deposit();
     env.set_caller(owner);
-    magni_mut.pause();
-    assert!(magni_mut.is_paused());
+    magni_mut.force_delegate();
 
-    // Unpause
-    magni_mut.unpause();
-    assert!(!magni_mut.is_paused());
+    // No liquid CSPR, so the withdrawal undelegates
+    env.set_caller(user);
+    let withdraw_amount = cspr_to_motes(100);
+    magni_mut.request_withdraw(withdraw_amount);
+    assert_eq!(magni_mut.undelegating_motes(), withdraw_amount);
+    assert_eq!(magni_mut.total_delegated(), deposit_amount);
+
+    // Stake has left the validator; the sweep moves it from tracking to liquid
+    assert_eq!(
+        env.delegated_amount(magni.address(), validator),
+        deposit_amount - withdraw_amount
+    );
+    env.set_caller(owner);
+    magni_mut.sweep_undelegated();
+
+    assert_eq!(magni_mut.total_delegated(), deposit_amount - withdraw_amount);
+    assert_eq!(magni_mut.undelegating_motes(), U512::zero());
+    assert_eq!(magni_mut.swept_liquid_motes(), withdraw_amount);
+    assert!(env.emitted(&magni, "UndelegatedSwept"));
 }
 
 #[test]
-#[should_panic(expected = "ContractPaused")]
-fn test_deposit_when_paused_reverts() {
+fn test_delegation_drift_reports_tracked_vs_actual() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
     let owner = env.get_account(0);
     let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(600)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    env.set_caller(user);
+    magni_mut.request_withdraw(cspr_to_motes(100));
+
+    // Requested undelegations are not counted as tracked stake
+    let drift = magni_mut.delegation_drift();
+    assert_eq!(drift.tracked_motes, cspr_to_motes(500));
+    assert_eq!(drift.actual_motes, magni_mut.delegated_amount());
+
+    // Rewards or slashing may move the chain figure; the drift must still reconcile
+    if drift.actual_above_tracked {
+        assert_eq!(drift.actual_motes - drift.tracked_motes, drift.drift_motes);
+    } else {
+        assert_eq!(drift.tracked_motes - drift.actual_motes, drift.drift_motes);
+    }
+}
 
+#[test]
+fn test_solvency_check_flags_unbacked_collateral() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
+    let other = env.get_account(2);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
 
-    // Pause
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
     env.set_caller(owner);
-    magni_mut.pause();
+    magni_mut.force_delegate();
+    assert!(magni_mut.solvency_check());
 
-    // Try deposit
+    // Stake still unbonding backs the withdrawal it was undelegated for
     env.set_caller(user);
-    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    magni_mut.request_withdraw(cspr_to_motes(100));
+    assert_eq!(magni_mut.undelegating_motes(), cspr_to_motes(100));
+    assert!(magni_mut.solvency_check());
+
+    // Collateral recorded without any CSPR behind it, while the withdrawal is in flight
+    env.set_caller(owner);
+    magni_mut.migrate_position(other, cspr_to_motes(50), U256::zero(), env.block_time());
+    assert!(!magni_mut.solvency_check());
+
+    // Still short once the stake is back and swept into the balance.
+    // advance_block_time runs no auctions, so the return is made by hand.
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(100));
+    magni_mut.sweep_undelegated();
+    assert_eq!(magni_mut.undelegating_motes(), U512::zero());
+    assert!(!magni_mut.solvency_check());
 }
 
 #[test]
-#[should_panic(expected = "Unauthorized")]
-fn test_pause_by_non_owner_reverts() {
+fn test_solvency_check_holds_through_a_withdrawal() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
     let user = env.get_account(1);
-
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
 
-    // Try pause as non-owner
     env.set_caller(user);
-    magni_mut.pause();
-}
+    magni_mut.with_tokens(cspr_to_motes(1000)).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+    env.set_caller(user);
+    magni_mut.request_withdraw(cspr_to_motes(100));
+    assert!(magni_mut.solvency_check());
 
-// ==========================================
-// T18: Edge Cases
-// ==========================================
+    // advance_block_time runs no auctions, so the unbonded stake is returned by hand
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(100));
+    env.set_caller(owner);
+    magni_mut.sweep_undelegated();
+    assert!(magni_mut.solvency_check());
+
+    env.set_caller(user);
+    magni_mut.finalize_withdraw();
+    assert!(magni_mut.solvency_check());
+}
 
 #[test]
-#[should_panic(expected = "ZeroAmount")]
-fn test_deposit_zero_reverts() {
+fn test_solvency_check_counts_unclaimed_performance_fee() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
     let user = env.get_account(1);
+    let other = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(owner);
+    magni_mut.set_performance_fee_bps(1000);
 
     env.set_caller(user);
-    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(U512::zero()).deposit();
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    env.transfer_tokens(&magni.address(), &cspr_to_motes(20));
+    env.set_caller(owner);
+    magni_mut.harvest_rewards();
+    assert_eq!(magni_mut.accrued_performance_fee(), cspr_to_motes(2));
+    assert!(magni_mut.solvency_check());
+
+    // Collateral as large as the set-aside fee, with nothing behind it
+    magni_mut.migrate_position(other, cspr_to_motes(2), U256::zero(), env.block_time());
+    assert!(!magni_mut.solvency_check());
 }
 
 #[test]
-#[should_panic(expected = "ZeroAmount")]
-fn test_borrow_zero_reverts() {
+fn test_apply_slashing_without_shortfall_keeps_collateral() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
     let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
 
     env.set_caller(user);
+    let deposit_amount = cspr_to_motes(600);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    env.set_caller(owner);
+    magni_mut.force_delegate();
+
+    // Chain stake matches tracked stake, so nothing is written down
+    let index_before = magni_mut.collateral_index();
+    magni_mut.apply_slashing();
+    assert_eq!(magni_mut.collateral_index(), index_before);
+    assert_eq!(magni_mut.collateral_of(user), deposit_amount);
+    assert_eq!(magni_mut.total_collateral(), deposit_amount);
+    assert!(!env.emitted(&magni, "SlashingApplied"));
+}
+
+#[test]
+fn test_slashing_never_takes_collateral_index_to_zero() {
+    let index = U512::from(WAD);
+    let total = cspr_to_motes(600);
+
+    // A 10% loss scales the index by 0.9
+    assert_eq!(
+        slashed_collateral_index(index, total, cspr_to_motes(60)),
+        Some(U512::from(WAD) * U512::from(9u64) / U512::from(10u64))
+    );
+    // Full and over-full losses are refused instead of zeroing the index
+    assert_eq!(slashed_collateral_index(index, total, total), None);
+    assert_eq!(slashed_collateral_index(index, total, total + U512::one()), None);
+    // So is a loss that leaves collateral but rounds the index down to zero
+    assert_eq!(slashed_collateral_index(U512::one(), total, U512::one()), None);
+    assert_eq!(slashed_collateral_index(index, U512::zero(), U512::zero()), None);
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_apply_slashing_requires_owner() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
-    magni_mut.borrow(U256::zero());
+    env.set_caller(env.get_account(1));
+    magni_mut.apply_slashing();
 }
 
-// ==========================================
-// T18: Delegation Batching Tests
-// ==========================================
+#[test]
+fn test_contracts_report_version_and_kind() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let poc = StakingPoC::deploy(&env, NoArgs);
+
+    assert_eq!(magni.version(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(magni.version(), magni_casper::VERSION);
+    assert_eq!(magni.contract_kind(), "magni_vault");
+    assert_eq!(poc.version(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(poc.contract_kind(), "staking_poc");
+}
 
 #[test]
-fn test_delegation_batching_below_minimum() {
+fn test_init_marks_contracts_initialized() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let poc = StakingPoC::deploy(&env, NoArgs);
+
+    // A second init would revert with AlreadyInitialized; the host can't re-run it
+    assert!(magni.is_initialized());
+    assert!(mcspr.is_initialized());
+    assert!(poc.is_initialized());
+}
+
+#[test]
+fn test_on_upgrade_migrates_old_storage_version() {
     let env = odra_test::env();
     let (_, magni, _) = deploy_contracts(&env);
-    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    assert_eq!(magni_mut.storage_version(), STORAGE_VERSION);
+
+    magni_mut.set_storage_version(0);
+    assert_eq!(magni_mut.storage_version(), 0);
+
+    magni_mut.on_upgrade();
+    assert_eq!(magni_mut.storage_version(), STORAGE_VERSION);
+    assert!(env.emitted_event(&magni, StorageUpgraded {
+        from_version: 0,
+        to_version: STORAGE_VERSION,
+    }));
+    // Backfilling never overwrites parameters that were already written
+    assert_eq!(magni_mut.ltv_max_bps(), LTV_MAX_BPS);
+    assert_eq!(magni_mut.close_factor_bps(), 5000);
+}
 
-    env.set_caller(user);
-    // Deposit 100 CSPR (below 500 CSPR minimum)
-    let deposit_amount = cspr_to_motes(100);
+#[test]
+#[should_panic(expected = "StorageVersionCurrent")]
+fn test_on_upgrade_when_current_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
 
-    // Pending should accumulate
-    assert_eq!(magni_mut.pending_to_delegate(), deposit_amount);
-    assert_eq!(magni_mut.total_delegated(), U512::zero());
+    magni_mut.on_upgrade();
 }
 
 #[test]
-fn test_delegation_batching_above_minimum() {
+fn test_storage_version_controls_are_owner_only() {
     let env = odra_test::env();
-    let (_, magni, _validator_hex) = deploy_contracts(&env);
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    assert!(magni_mut.try_set_storage_version(STORAGE_VERSION).is_err());
+    magni_mut.set_storage_version(0);
+
+    env.set_caller(env.get_account(1));
+    assert!(magni_mut.try_on_upgrade().is_err());
+    assert!(magni_mut.try_set_storage_version(0).is_err());
+    assert_eq!(magni_mut.storage_version(), 0);
+}
+
+#[test]
+fn test_migrate_positions_updates_totals() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+    let debt = U256::from(100u64) * U256::from(WAD);
+    let now = env.block_time();
+
+    magni_mut.migrate_position(alice, cspr_to_motes(1000), debt, now);
+    magni_mut.migrate_position(bob, cspr_to_motes(500), U256::zero(), now);
+
+    assert_eq!(magni_mut.total_collateral(), cspr_to_motes(1500));
+    assert_eq!(magni_mut.total_debt(), debt);
+    assert_eq!(magni_mut.collateral_of(alice), cspr_to_motes(1000));
+    assert_eq!(magni_mut.debt_of(alice), debt);
+    assert_eq!(magni_mut.status_of(bob), 1); // Active
+
+    // Re-importing a user replaces their position instead of double counting
+    magni_mut.migrate_position(bob, cspr_to_motes(200), U256::zero(), now);
+    assert_eq!(magni_mut.total_collateral(), cspr_to_motes(1200));
+}
+
+#[test]
+#[should_panic(expected = "MigrationFinalized")]
+fn test_migrate_position_after_finalize_reverts() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    magni_mut.finalize_migration();
+    assert!(magni_mut.migration_finalized());
+    magni_mut.migrate_position(env.get_account(1), cspr_to_motes(1000), U256::zero(), 0);
+}
+
+#[test]
+fn test_extreme_collateral_max_debt_does_not_overflow() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
     let user = env.get_account(1);
-    let owner = env.get_account(0);
-    let validator = env.get_validator(0);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    // Collateral whose wad value is close to U256::MAX, so collateral_wad * 8000 overflows
+    let motes_per_wad = 1_000_000_000u64;
+    let collateral_motes =
+        U512::from(u128::MAX) * U512::from(u128::MAX) / U512::from(motes_per_wad);
+    magni_mut.migrate_position(user, collateral_motes, U256::zero(), env.block_time());
+
+    let collateral_wad = magni_mut.get_position(user).collateral_wad;
+    assert!(collateral_wad.checked_mul(U256::from(LTV_MAX_BPS)).is_none());
+    // 80% computed as 4/5 without forming the product
+    let expected_max = collateral_wad / U256::from(5u64) * U256::from(4u64)
+        + collateral_wad % U256::from(5u64) * U256::from(4u64) / U256::from(5u64);
 
     env.set_caller(user);
-    // Deposit 600 CSPR (above 500 CSPR minimum)
-    let deposit_amount = cspr_to_motes(600);
+    magni_mut.borrow(expected_max);
+    assert_eq!(magni_mut.debt_of(user), expected_max);
+    assert!(magni_mut.try_borrow(U256::one()).is_err());
+
+    let info = magni_mut.get_position(user);
+    assert_eq!(info.debt_wad, expected_max);
+    assert_eq!(info.health_factor, BPS_DIVISOR);
+    assert_eq!(magni_mut.health_factor_of(user), BPS_DIVISOR);
+    assert!(magni_mut.max_withdraw_of(user) < collateral_motes);
+}
+
+#[test]
+fn test_retired_vault_winds_down_without_borrowing() {
+    let env = odra_test::env();
+    let (mcspr, magni, _) = deploy_contracts(&env);
+    let owner = env.get_account(0);
+    let user = env.get_account(1);
     let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
-    magni_mut.with_tokens(deposit_amount).deposit();
+    let debt = U256::from(50u64) * U256::from(WAD);
 
-    // Delegation is NOT automatic anymore - should accumulate in pending
-    assert_eq!(magni_mut.pending_to_delegate(), deposit_amount);
-    assert_eq!(magni_mut.total_delegated(), U512::zero());
+    env.set_caller(user);
+    let deposit_amount = cspr_to_motes(100);
+    magni_mut.with_tokens(deposit_amount).deposit();
+    magni_mut.borrow(debt);
 
-    // Owner triggers delegation manually
     env.set_caller(owner);
-    magni_mut.force_delegate();
+    magni_mut.retire();
+    assert!(magni_mut.is_retired());
+    assert_eq!(magni.operation_status(), OP_DEPOSIT | OP_REPAY | OP_WITHDRAW);
 
-    // Now should be delegated
-    assert_eq!(magni_mut.pending_to_delegate(), U512::zero());
-    assert_eq!(magni_mut.total_delegated(), deposit_amount);
+    env.set_caller(user);
+    assert!(magni_mut.try_borrow(U256::from(WAD)).is_err());
 
-    // Check actual delegation
-    let delegated = env.delegated_amount(magni.address(), validator);
-    assert_eq!(delegated, deposit_amount);
+    let mut mcspr_mut = MCSPRTokenHostRef::new(mcspr.address(), env.clone());
+    mcspr_mut.approve(magni.address(), debt);
+    magni_mut.repay(debt);
+    assert_eq!(magni_mut.debt_of(user), U256::zero());
+
+    magni_mut.request_withdraw(deposit_amount);
+    env.advance_block_time(UNBONDING_PERIOD_SECS * 1000);
+    magni_mut.finalize_withdraw();
+    assert_eq!(magni_mut.status_of(user), 0);
+}
+
+#[test]
+#[should_panic(expected = "Retired")]
+fn test_retire_cannot_be_undone() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let user = env.get_account(1);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    magni_mut.retire();
+    // Neither a pause cycle nor clearing the borrow bit brings borrowing back
+    magni_mut.pause();
+    magni_mut.unpause();
+    magni_mut.set_operations_paused(OP_BORROW, false);
+    assert!(magni_mut.try_retire().is_err());
+
+    env.set_caller(user);
+    magni_mut.with_tokens(cspr_to_motes(100)).deposit();
+    magni_mut.borrow(U256::from(WAD));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_migrate_position_requires_owner() {
+    let env = odra_test::env();
+    let (_, magni, _) = deploy_contracts(&env);
+    let mut magni_mut = MagniHostRef::new(magni.address(), env.clone());
+
+    env.set_caller(env.get_account(1));
+    magni_mut.migrate_position(env.get_account(1), cspr_to_motes(1000), U256::zero(), 0);
 }
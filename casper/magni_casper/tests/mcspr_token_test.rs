@@ -0,0 +1,628 @@
+//! mCSPR Token Governance Tests
+//!
+//! Admin handshake, privileged function access and metadata setters
+
+use odra::casper_types::account::AccountHash;
+use odra::casper_types::U256;
+use odra::host::{Deployer, HostRef, NoArgs};
+use odra::prelude::Address;
+
+use magni_casper::tokens::events::{
+    AdminTransferStarted, AdminTransferred, FaucetMinted, MetadataChanged, MinterProposed,
+    MinterSet, MinterTimelockChanged, MinterTimelockDecreaseQueued,
+};
+use magni_casper::tokens::{
+    MCSPRToken, MCSPRTokenHostRef, MCSPRTokenInitArgs, TCSPRToken, TokenError,
+};
+
+fn deploy_token(env: &odra::host::HostEnv) -> MCSPRTokenHostRef {
+    let owner = env.get_account(0);
+    env.set_caller(owner);
+    MCSPRToken::deploy(env, MCSPRTokenInitArgs { minter: owner })
+}
+
+#[test]
+fn test_deployer_is_admin() {
+    let env = odra_test::env();
+    let token = deploy_token(&env);
+
+    assert_eq!(token.admin(), Some(env.get_account(0)));
+    assert_eq!(token.pending_admin(), None);
+}
+
+#[test]
+fn test_admin_handshake() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let old_admin = env.get_account(0);
+    let new_admin = env.get_account(1);
+
+    token.transfer_admin(new_admin);
+    // Nomination alone does not change the admin
+    assert_eq!(token.admin(), Some(old_admin));
+    assert_eq!(token.pending_admin(), Some(new_admin));
+
+    env.set_caller(new_admin);
+    token.accept_admin();
+    assert_eq!(token.admin(), Some(new_admin));
+    assert_eq!(token.pending_admin(), None);
+    assert!(env.emitted_event(&token, AdminTransferred {
+        old_admin: Some(old_admin),
+        new_admin,
+    }));
+
+    // New admin controls the minter
+    token.force_set_minter(env.get_account(2));
+    assert_eq!(token.minter(), Some(env.get_account(2)));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_accept_admin_by_non_nominee_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    token.transfer_admin(env.get_account(1));
+    env.set_caller(env.get_account(2));
+    token.accept_admin();
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_transfer_admin_by_non_admin_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    env.set_caller(env.get_account(1));
+    token.transfer_admin(env.get_account(1));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_set_minter_by_non_minter_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    env.set_caller(env.get_account(1));
+    token.set_minter(env.get_account(1));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_set_minter_by_minter_without_admin_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    token.set_minter(env.get_account(1));
+    env.set_caller(env.get_account(1));
+    token.set_minter(env.get_account(2));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_force_set_minter_by_non_admin_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    // Even the current minter can't use the admin path
+    token.set_minter(env.get_account(1));
+    env.set_caller(env.get_account(1));
+    token.force_set_minter(env.get_account(1));
+}
+
+#[test]
+fn test_deploy_with_contract_minter_preset() {
+    let env = odra_test::env();
+    let admin = env.get_account(0);
+    env.set_caller(admin);
+    // Any deployed contract stands in for the vault
+    let vault = TCSPRToken::deploy(&env, NoArgs).address();
+
+    let mut token = MCSPRToken::deploy(&env, MCSPRTokenInitArgs { minter: vault });
+    assert_eq!(token.minter(), Some(vault));
+    assert!(env.emitted_event(&token, MinterSet {
+        old_minter: None,
+        new_minter: vault,
+    }));
+    // No temporary minter: the deployer is admin only
+    assert_eq!(token.admin(), Some(admin));
+    assert_eq!(
+        token.try_mint(admin, U256::one()),
+        Err(TokenError::Unauthorized.into())
+    );
+}
+
+#[test]
+fn test_admin_recovers_minter_set_to_dead_address() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let admin = env.get_account(0);
+    let dead = Address::Account(AccountHash::new([0xde; 32]));
+    let vault = env.get_account(3);
+
+    // Nobody holds the dead key, so minting is stuck
+    token.set_minter(dead);
+    assert!(token.try_mint(env.get_account(1), U256::one()).is_err());
+
+    // The admin key rotates the minter without the current minter's cooperation
+    env.set_caller(admin);
+    assert_eq!(token.try_set_minter(vault), Err(TokenError::Unauthorized.into()));
+    token.force_set_minter(vault);
+    assert_eq!(token.minter(), Some(vault));
+    assert!(env.emitted_event(&token, MinterSet {
+        old_minter: Some(dead),
+        new_minter: vault,
+    }));
+
+    env.set_caller(vault);
+    token.mint(env.get_account(1), U256::one());
+    assert_eq!(token.balance_of(env.get_account(1)), U256::one());
+}
+
+#[test]
+fn test_minter_timelock_enforced() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let admin = env.get_account(0);
+    let next_minter = env.get_account(2);
+    let holder = env.get_account(1);
+
+    token.set_minter_timelock_secs(3600);
+    // The instant paths are closed while a timelock is configured
+    assert_eq!(token.try_set_minter(next_minter), Err(TokenError::MinterTimelocked.into()));
+    assert_eq!(
+        token.try_force_set_minter(next_minter),
+        Err(TokenError::MinterTimelocked.into())
+    );
+
+    let ready_at = env.block_time() + 3_600_000;
+    token.propose_minter(next_minter);
+    assert_eq!(token.proposed_minter(), Some((next_minter, ready_at)));
+    assert!(env.emitted_event(&token, MinterProposed {
+        proposed_minter: next_minter,
+        ready_at,
+    }));
+
+    // During the window the current minter keeps working
+    env.advance_block_time(3_599_000);
+    assert_eq!(token.try_apply_minter(), Err(TokenError::MinterTimelocked.into()));
+    token.mint(holder, U256::one());
+    assert_eq!(token.minter(), Some(admin));
+
+    env.advance_block_time(1_000);
+    token.apply_minter();
+    assert_eq!(token.minter(), Some(next_minter));
+    assert_eq!(token.proposed_minter(), None);
+    assert!(env.emitted_event(&token, MinterSet {
+        old_minter: Some(admin),
+        new_minter: next_minter,
+    }));
+    assert!(token.try_mint(holder, U256::one()).is_err());
+}
+
+#[test]
+fn test_minter_timelock_decrease_waits_out_the_old_delay() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let admin = env.get_account(0);
+    let next_minter = env.get_account(2);
+
+    token.set_minter_timelock_secs(3600);
+    assert_eq!(token.try_apply_minter_timelock_secs(), Err(TokenError::NoTimelockQueued.into()));
+
+    // Dropping to zero is only queued; the instant path stays closed meanwhile
+    let ready_at = env.block_time() + 3_600_000;
+    token.set_minter_timelock_secs(0);
+    assert_eq!(token.minter_timelock_secs(), 3600);
+    assert_eq!(token.queued_minter_timelock(), Some((0, ready_at)));
+    assert!(env.emitted_event(&token, MinterTimelockDecreaseQueued {
+        new_timelock_secs: 0,
+        ready_at,
+    }));
+    assert_eq!(token.try_set_minter(next_minter), Err(TokenError::MinterTimelocked.into()));
+
+    env.advance_block_time(3_599_000);
+    assert_eq!(
+        token.try_apply_minter_timelock_secs(),
+        Err(TokenError::MinterTimelocked.into())
+    );
+    assert_eq!(token.try_set_minter(next_minter), Err(TokenError::MinterTimelocked.into()));
+    assert_eq!(token.minter(), Some(admin));
+
+    env.advance_block_time(1_000);
+    token.apply_minter_timelock_secs();
+    assert_eq!(token.minter_timelock_secs(), 0);
+    assert_eq!(token.queued_minter_timelock(), None);
+    assert!(env.emitted_event(&token, MinterTimelockChanged {
+        old_timelock_secs: 3600,
+        new_timelock_secs: 0,
+    }));
+    token.set_minter(next_minter);
+    assert_eq!(token.minter(), Some(next_minter));
+}
+
+#[test]
+fn test_minter_timelock_increase_applies_at_once_and_cancels_queued_decrease() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    token.set_minter_timelock_secs(60);
+    token.set_minter_timelock_secs(10);
+    assert!(token.queued_minter_timelock().is_some());
+
+    token.set_minter_timelock_secs(120);
+    assert_eq!(token.minter_timelock_secs(), 120);
+    assert_eq!(token.queued_minter_timelock(), None);
+    assert!(env.emitted_event(&token, MinterTimelockChanged {
+        old_timelock_secs: 60,
+        new_timelock_secs: 120,
+    }));
+}
+
+#[test]
+fn test_zero_minter_timelock_applies_instantly() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let next_minter = env.get_account(2);
+
+    assert_eq!(token.minter_timelock_secs(), 0);
+    assert_eq!(token.try_apply_minter(), Err(TokenError::NoMinterProposed.into()));
+
+    token.propose_minter(next_minter);
+    token.apply_minter();
+    assert_eq!(token.minter(), Some(next_minter));
+
+    // The admin still rotates directly
+    token.force_set_minter(env.get_account(3));
+    assert_eq!(token.minter(), Some(env.get_account(3)));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_old_admin_loses_access_after_transfer() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let old_admin = env.get_account(0);
+
+    token.transfer_admin(env.get_account(1));
+    env.set_caller(env.get_account(1));
+    token.accept_admin();
+
+    env.set_caller(old_admin);
+    token.force_set_minter(old_admin);
+}
+
+#[test]
+fn test_every_admin_function_emits_event() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let admin = env.get_account(0);
+    let new_minter = env.get_account(1);
+    let next_minter = env.get_account(2);
+    let new_admin = env.get_account(3);
+
+    token.force_set_minter(new_minter);
+    assert!(env.emitted_event(&token, MinterSet {
+        old_minter: Some(admin),
+        new_minter,
+    }));
+
+    token.set_minter_timelock_secs(60);
+    assert!(env.emitted_event(&token, MinterTimelockChanged {
+        old_timelock_secs: 0,
+        new_timelock_secs: 60,
+    }));
+
+    token.propose_minter(next_minter);
+    let ready_at = env.block_time() + 60_000;
+    assert!(env.emitted_event(&token, MinterProposed {
+        proposed_minter: next_minter,
+        ready_at,
+    }));
+    env.advance_block_time(60_000);
+    token.apply_minter();
+    assert!(env.emitted_event(&token, MinterSet {
+        old_minter: Some(new_minter),
+        new_minter: next_minter,
+    }));
+
+    token.set_minter_timelock_secs(30);
+    let ready_at = env.block_time() + 60_000;
+    assert!(env.emitted_event(&token, MinterTimelockDecreaseQueued {
+        new_timelock_secs: 30,
+        ready_at,
+    }));
+    env.advance_block_time(60_000);
+    token.apply_minter_timelock_secs();
+    assert!(env.emitted_event(&token, MinterTimelockChanged {
+        old_timelock_secs: 60,
+        new_timelock_secs: 30,
+    }));
+
+    token.set_name("Magni Staked CSPR".to_string());
+    token.set_symbol("mCS".to_string());
+    token.set_decimals(9);
+    assert!(env.emitted_event(&token, MetadataChanged {
+        name: "Magni Staked CSPR".to_string(),
+        symbol: "mCS".to_string(),
+        decimals: 9,
+    }));
+
+    token.transfer_admin(new_admin);
+    assert!(env.emitted_event(&token, AdminTransferStarted {
+        admin,
+        pending_admin: new_admin,
+    }));
+    env.set_caller(new_admin);
+    token.accept_admin();
+    assert!(env.emitted_event(&token, AdminTransferred {
+        old_admin: Some(admin),
+        new_admin,
+    }));
+}
+
+#[test]
+fn test_burn_from_spends_minter_allowance() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let minter = env.get_account(0);
+    let holder = env.get_account(1);
+    let amount = U256::from(100u64);
+
+    token.mint(holder, amount);
+    env.set_caller(holder);
+    token.approve(minter, U256::from(30u64));
+    // Only the minter may burn, and only within its allowance
+    assert!(token.try_burn_from(holder, U256::from(10u64)).is_err());
+    env.set_caller(minter);
+    assert!(token.try_burn_from(holder, U256::from(31u64)).is_err());
+
+    token.burn_from(holder, U256::from(30u64));
+    assert_eq!(token.balance_of(holder), U256::from(70u64));
+    assert_eq!(token.total_supply(), U256::from(70u64));
+    assert_eq!(token.allowance(holder, minter), U256::zero());
+}
+
+#[test]
+fn test_admin_renames_token() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    token.set_name("Magni Staked CSPR".to_string());
+    token.set_symbol("mCSPR2".to_string());
+    assert_eq!(token.name(), "Magni Staked CSPR");
+    assert_eq!(token.symbol(), "mCSPR2");
+    assert!(env.emitted_event(&token, MetadataChanged {
+        name: "Magni Staked CSPR".to_string(),
+        symbol: "mCSPR2".to_string(),
+        decimals: 18,
+    }));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_set_name_by_non_admin_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    env.set_caller(env.get_account(1));
+    token.set_name("Fake".to_string());
+}
+
+#[test]
+fn test_set_decimals_with_zero_supply() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    token.set_decimals(9);
+    assert_eq!(token.decimals(), 9);
+}
+
+#[test]
+#[should_panic(expected = "SupplyExists")]
+fn test_set_decimals_with_supply_reverts() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+
+    token.mint(env.get_account(1), U256::one());
+    token.set_decimals(9);
+}
+
+#[test]
+fn test_tcspr_metadata_setters() {
+    let env = odra_test::env();
+    env.set_caller(env.get_account(0));
+    let mut token = TCSPRToken::deploy(&env, NoArgs);
+
+    token.set_symbol("tCSPR2".to_string());
+    token.set_decimals(9);
+    assert_eq!(token.symbol(), "tCSPR2");
+    assert_eq!(token.decimals(), 9);
+
+    token.faucet_mint(env.get_account(1), U256::one());
+    assert!(token.try_set_decimals(18).is_err());
+}
+
+#[test]
+fn test_faucet_mint_emits_faucet_minted() {
+    let env = odra_test::env();
+    let mut token = TCSPRToken::deploy(&env, NoArgs);
+    let to = env.get_account(1);
+    let amount = U256::from(500u64);
+
+    env.set_caller(to);
+    token.faucet_mint(to, amount);
+
+    assert!(env.emitted_event(&token, FaucetMinted { to, amount }));
+    assert_eq!(token.total_faucet_minted(), amount);
+    assert_eq!(token.faucet_total_cap(), None);
+}
+
+#[test]
+#[should_panic(expected = "FaucetCapReached")]
+fn test_faucet_mint_past_total_cap_reverts() {
+    let env = odra_test::env();
+    env.set_caller(env.get_account(0));
+    let mut token = TCSPRToken::deploy(&env, NoArgs);
+    token.set_faucet_total_cap(U256::from(1000u64));
+
+    // Up to the cap is fine, across any number of recipients
+    env.set_caller(env.get_account(1));
+    token.faucet_mint(env.get_account(1), U256::from(600u64));
+    token.faucet_mint(env.get_account(2), U256::from(400u64));
+    assert_eq!(token.total_faucet_minted(), U256::from(1000u64));
+
+    token.faucet_mint(env.get_account(1), U256::one());
+}
+
+#[test]
+fn test_faucet_mint_overflowing_total_reverts_with_cap_error() {
+    let env = odra_test::env();
+    let mut token = TCSPRToken::deploy(&env, NoArgs);
+    let to = env.get_account(1);
+
+    env.set_caller(to);
+    token.faucet_mint(to, U256::one());
+    assert_eq!(
+        token.try_faucet_mint(to, U256::MAX),
+        Err(TokenError::FaucetCapReached.into())
+    );
+    assert_eq!(token.total_faucet_minted(), U256::one());
+}
+
+#[test]
+fn test_set_faucet_total_cap_requires_admin() {
+    let env = odra_test::env();
+    env.set_caller(env.get_account(0));
+    let mut token = TCSPRToken::deploy(&env, NoArgs);
+
+    env.set_caller(env.get_account(1));
+    assert!(token.try_set_faucet_total_cap(U256::zero()).is_err());
+    assert_eq!(token.faucet_total_cap(), None);
+}
+
+#[test]
+fn test_holder_count_tracks_nonzero_balances() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+    let amount = U256::from(100u64);
+
+    token.mint(alice, amount);
+    token.mint(bob, amount);
+    assert_eq!(token.holder_count(), 2);
+
+    // Minting to an existing holder doesn't add one
+    token.mint(alice, amount);
+    assert_eq!(token.holder_count(), 2);
+
+    // Emptying a balance removes the holder; the recipient already held tokens
+    env.set_caller(alice);
+    token.transfer(bob, amount * 2);
+    assert_eq!(token.holder_count(), 1);
+    assert_eq!(token.balances_of(vec![alice, bob]), vec![U256::zero(), amount * 3]);
+
+    // Burning the last balance leaves no holders
+    env.set_caller(env.get_account(0));
+    token.burn(bob, amount * 3);
+    assert_eq!(token.holder_count(), 0);
+}
+
+#[test]
+fn test_holder_count_partial_transfer_adds_recipient() {
+    let env = odra_test::env();
+    let mut token = deploy_token(&env);
+    let alice = env.get_account(1);
+    let bob = env.get_account(2);
+
+    token.mint(alice, U256::from(100u64));
+    env.set_caller(alice);
+    token.transfer(bob, U256::from(40u64));
+    assert_eq!(token.holder_count(), 2);
+}
+
+#[test]
+fn test_self_transfer_rejected_by_both_tokens() {
+    let env = odra_test::env();
+    let mut mcspr = deploy_token(&env);
+    let mut tcspr = TCSPRToken::deploy(&env, NoArgs);
+    let alice = env.get_account(1);
+    let amount = U256::from(100u64);
+
+    mcspr.mint(alice, amount);
+    tcspr.faucet_mint(alice, amount);
+
+    env.set_caller(alice);
+    let expected = Err(TokenError::CannotTargetSelfUser.into());
+    assert_eq!(mcspr.try_transfer(alice, U256::one()), expected);
+    assert_eq!(tcspr.try_transfer(alice, U256::one()), expected);
+    assert_eq!(mcspr.balance_of(alice), tcspr.balance_of(alice));
+    assert_eq!(mcspr.holder_count(), 1);
+}
+
+#[test]
+fn test_self_transfer_from_rejected_by_both_tokens() {
+    let env = odra_test::env();
+    let mut mcspr = deploy_token(&env);
+    let mut tcspr = TCSPRToken::deploy(&env, NoArgs);
+    let alice = env.get_account(1);
+    let spender = env.get_account(2);
+    let amount = U256::from(100u64);
+
+    mcspr.mint(alice, amount);
+    tcspr.faucet_mint(alice, amount);
+    env.set_caller(alice);
+    mcspr.approve(spender, amount);
+    tcspr.approve(spender, amount);
+
+    env.set_caller(spender);
+    let expected = Err(TokenError::CannotTargetSelfUser.into());
+    assert_eq!(mcspr.try_transfer_from(alice, alice, U256::one()), expected);
+    assert_eq!(tcspr.try_transfer_from(alice, alice, U256::one()), expected);
+    assert_eq!(mcspr.allowance(alice, spender), amount);
+    assert_eq!(tcspr.allowance(alice, spender), amount);
+}
+
+#[test]
+fn test_permit_for_one_token_rejected_by_another() {
+    let env = odra_test::env();
+    let mut first = deploy_token(&env);
+    let mut second = deploy_token(&env);
+    let owner = env.get_account(1);
+    let spender = env.get_account(2);
+    let amount = U256::from(100u64);
+    assert_ne!(first.domain_separator(), second.domain_separator());
+
+    // Same owner, spender, amount, nonce and deadline on both instances
+    let deadline = env.block_time() + 3_600_000;
+    let message = first.permit_message(owner, spender, amount, deadline);
+    assert_ne!(message, second.permit_message(owner, spender, amount, deadline));
+    let signature = env.sign_message(&message, &owner);
+
+    let result = second.try_permit(
+        env.public_key(&owner),
+        spender,
+        amount,
+        deadline,
+        signature.clone(),
+    );
+    assert_eq!(result, Err(TokenError::InvalidSignature.into()));
+    assert_eq!(second.allowance(owner, spender), U256::zero());
+
+    first.permit(env.public_key(&owner), spender, amount, deadline, signature);
+    assert_eq!(first.allowance(owner, spender), amount);
+}
+
+#[test]
+fn test_tokens_report_version_and_kind() {
+    let env = odra_test::env();
+    let mcspr = deploy_token(&env);
+    let tcspr = TCSPRToken::deploy(&env, NoArgs);
+
+    assert_eq!(mcspr.version(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(tcspr.version(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(mcspr.contract_kind(), "mcspr_token");
+    assert_eq!(tcspr.contract_kind(), "tcspr_token");
+    assert!(tcspr.is_initialized());
+}
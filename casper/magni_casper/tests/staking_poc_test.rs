@@ -0,0 +1,217 @@
+//! StakingPoC Tests
+//!
+//! Pause control, access checks, reward and unstaked principal withdrawal for the
+//! delegation research contract
+
+use odra::casper_types::bytesrepr::ToBytes;
+use odra::casper_types::{PublicKey, U512};
+use odra::host::{Deployer, HostRef, NoArgs};
+
+use magni_casper::staking_poc::events::{Paused, RewardsWithdrawn, Unpaused, UnstakedWithdrawn};
+use magni_casper::staking_poc::{StakingPoC, StakingPoCHostRef};
+use magni_casper::units::cspr_to_motes;
+
+/// Convert public key to hex string
+fn public_key_to_hex(public_key: &PublicKey) -> String {
+    let bytes = public_key.to_bytes().expect("public key to_bytes");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn deploy_poc(env: &odra::host::HostEnv) -> (StakingPoCHostRef, String) {
+    env.set_caller(env.get_account(0));
+    let poc = StakingPoC::deploy(env, NoArgs);
+    (poc, public_key_to_hex(&env.get_validator(0)))
+}
+
+#[test]
+fn test_pause_blocks_stake() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+    let owner = env.get_account(0);
+
+    poc.pause();
+    assert!(poc.is_paused());
+    assert!(env.emitted_event(&poc, Paused { by: owner }));
+    assert!(poc
+        .with_tokens(cspr_to_motes(1000))
+        .try_stake(validator_hex.clone())
+        .is_err());
+    assert!(poc
+        .try_request_unstake(validator_hex.clone(), cspr_to_motes(1))
+        .is_err());
+
+    poc.unpause();
+    assert!(env.emitted_event(&poc, Unpaused { by: owner }));
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex);
+    assert_eq!(poc.total_delegated(), cspr_to_motes(1000));
+}
+
+#[test]
+#[should_panic(expected = "Paused")]
+fn test_stake_while_paused_reverts() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    poc.pause();
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex);
+}
+
+#[test]
+fn test_queries_available_while_paused() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    poc.pause();
+
+    assert_eq!(poc.total_delegated(), cspr_to_motes(1000));
+    assert_eq!(poc.delegated_amount(validator_hex), cspr_to_motes(1000));
+    assert_eq!(poc.owner(), Some(env.get_account(0)));
+}
+
+#[test]
+fn test_pause_requires_owner() {
+    let env = odra_test::env();
+    let (mut poc, _) = deploy_poc(&env);
+
+    env.set_caller(env.get_account(1));
+    assert!(poc.try_pause().is_err());
+    assert!(!poc.is_paused());
+    assert!(poc.try_unpause().is_err());
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_request_unstake_by_non_owner_reverts() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    // Staking stays open to anyone
+    env.set_caller(env.get_account(1));
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    assert_eq!(poc.total_delegated(), cspr_to_motes(1000));
+
+    poc.request_unstake(validator_hex, cspr_to_motes(500));
+}
+
+#[test]
+fn test_owner_can_request_unstake() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    poc.request_unstake(validator_hex, cspr_to_motes(500));
+    assert_eq!(poc.total_delegated(), cspr_to_motes(500));
+}
+
+#[test]
+#[should_panic(expected = "InsufficientDelegation")]
+fn test_request_unstake_above_delegation_reverts() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    poc.request_unstake(validator_hex, cspr_to_motes(1001));
+}
+
+#[test]
+fn test_withdraw_rewards_sends_liquid_balance() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+    let recipient = env.get_account(2);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex);
+    assert_eq!(poc.withdrawable_rewards(), U512::zero());
+
+    // Rewards landing in the contract purse
+    env.transfer_tokens(&poc.address(), &cspr_to_motes(7));
+    assert_eq!(poc.withdrawable_rewards(), cspr_to_motes(7));
+
+    let recipient_before = env.balance_of(&recipient);
+    poc.withdraw_rewards(recipient);
+
+    assert_eq!(env.balance_of(&recipient), recipient_before + cspr_to_motes(7));
+    assert_eq!(poc.withdrawable_rewards(), U512::zero());
+    assert_eq!(poc.total_delegated(), cspr_to_motes(1000));
+    assert!(env.emitted_event(
+        &poc,
+        RewardsWithdrawn {
+            to: recipient,
+            amount: cspr_to_motes(7),
+        }
+    ));
+}
+
+#[test]
+fn test_withdraw_rewards_keeps_pending_undelegations() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    poc.request_unstake(validator_hex, cspr_to_motes(500));
+    assert_eq!(poc.pending_undelegations(), cspr_to_motes(500));
+
+    env.transfer_tokens(&poc.address(), &cspr_to_motes(7));
+    // The 7 CSPR could be returning principal, so none of it is withdrawable yet
+    assert_eq!(poc.withdrawable_rewards(), U512::zero());
+    assert!(poc.try_withdraw_rewards(env.get_account(2)).is_err());
+}
+
+#[test]
+fn test_withdraw_rewards_requires_owner() {
+    let env = odra_test::env();
+    let (mut poc, _) = deploy_poc(&env);
+    env.transfer_tokens(&poc.address(), &cspr_to_motes(7));
+
+    env.set_caller(env.get_account(1));
+    assert!(poc.try_withdraw_rewards(env.get_account(1)).is_err());
+    assert_eq!(poc.withdrawable_rewards(), cspr_to_motes(7));
+}
+
+#[test]
+fn test_withdraw_unstaked_releases_returned_principal() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+    let recipient = env.get_account(2);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    poc.request_unstake(validator_hex, cspr_to_motes(500));
+    // Nothing has come back yet
+    assert!(poc.try_withdraw_unstaked(recipient, cspr_to_motes(500)).is_err());
+
+    // advance_block_time runs no auctions, so the unbonded principal is returned by hand,
+    // together with 7 CSPR of rewards
+    env.transfer_tokens(&poc.address(), &cspr_to_motes(507));
+    assert!(poc.try_withdraw_unstaked(recipient, cspr_to_motes(501)).is_err());
+
+    let recipient_before = env.balance_of(&recipient);
+    poc.withdraw_unstaked(recipient, cspr_to_motes(500));
+    assert_eq!(poc.pending_undelegations(), U512::zero());
+    assert_eq!(env.balance_of(&recipient), recipient_before + cspr_to_motes(500));
+    assert!(env.emitted_event(
+        &poc,
+        UnstakedWithdrawn {
+            to: recipient,
+            amount: cspr_to_motes(500),
+        }
+    ));
+
+    // The rewards are withdrawable again once the principal is out
+    assert_eq!(poc.withdrawable_rewards(), cspr_to_motes(7));
+    poc.withdraw_rewards(recipient);
+    assert_eq!(env.balance_of(&recipient), recipient_before + cspr_to_motes(507));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_withdraw_unstaked_requires_owner() {
+    let env = odra_test::env();
+    let (mut poc, validator_hex) = deploy_poc(&env);
+
+    poc.with_tokens(cspr_to_motes(1000)).stake(validator_hex.clone());
+    poc.request_unstake(validator_hex, cspr_to_motes(500));
+    env.transfer_tokens(&poc.address(), &cspr_to_motes(500));
+
+    env.set_caller(env.get_account(1));
+    poc.withdraw_unstaked(env.get_account(1), cspr_to_motes(500));
+}
@@ -0,0 +1,103 @@
+//! Unit Conversion Tests
+//!
+//! Boundary and round-trip checks for motes <-> wad conversion
+
+use odra::casper_types::{U256, U512};
+
+use magni_casper::units::{
+    cspr_to_motes, motes_to_wad, wad_to_motes, wad_to_motes_round_up, MOTES_TO_WAD_FACTOR, WAD,
+};
+
+#[test]
+fn test_one_cspr_is_one_wad() {
+    assert_eq!(motes_to_wad(cspr_to_motes(1)), Some(U256::from(WAD)));
+    assert_eq!(wad_to_motes(U256::from(WAD)), cspr_to_motes(1));
+}
+
+#[test]
+fn test_zero_and_single_mote() {
+    assert_eq!(motes_to_wad(U512::zero()), Some(U256::zero()));
+    assert_eq!(motes_to_wad(U512::one()), Some(U256::from(MOTES_TO_WAD_FACTOR)));
+    assert_eq!(wad_to_motes(U256::zero()), U512::zero());
+}
+
+#[test]
+fn test_wad_to_motes_rounds_down() {
+    let factor = U256::from(MOTES_TO_WAD_FACTOR);
+    assert_eq!(wad_to_motes(factor - U256::one()), U512::zero());
+    assert_eq!(wad_to_motes(factor), U512::one());
+    assert_eq!(wad_to_motes(factor * U256::from(2u64) - U256::one()), U512::one());
+}
+
+#[test]
+fn test_round_trip_is_stable() {
+    let samples = [
+        U512::one(),
+        U512::from(999u64),
+        cspr_to_motes(1),
+        cspr_to_motes(500),
+        cspr_to_motes(u64::MAX / 1_000_000_000),
+        U512::from(u128::MAX),
+    ];
+    for motes in samples {
+        let wad = motes_to_wad(motes).expect("sample fits in wad");
+        assert_eq!(wad_to_motes(wad), motes);
+    }
+}
+
+#[test]
+fn test_motes_to_wad_upper_boundary() {
+    let factor = U256::from(MOTES_TO_WAD_FACTOR);
+    let max_motes_u256 = U256::MAX / factor;
+    let mut bytes = [0u8; 32];
+    max_motes_u256.to_little_endian(&mut bytes);
+    let max_motes = U512::from_little_endian(&bytes);
+
+    assert_eq!(motes_to_wad(max_motes), Some(max_motes_u256 * factor));
+    assert_eq!(motes_to_wad(max_motes + U512::one()), None);
+    assert_eq!(motes_to_wad(U512::MAX), None);
+}
+
+#[test]
+fn test_wad_to_motes_handles_max_wad() {
+    let mut bytes = [0u8; 32];
+    (U256::MAX / U256::from(MOTES_TO_WAD_FACTOR)).to_little_endian(&mut bytes);
+    assert_eq!(wad_to_motes(U256::MAX), U512::from_little_endian(&bytes));
+}
+
+#[test]
+fn test_wad_to_motes_round_up() {
+    let factor = U256::from(MOTES_TO_WAD_FACTOR);
+    assert_eq!(wad_to_motes_round_up(U256::zero()), U512::zero());
+    assert_eq!(wad_to_motes_round_up(U256::one()), U512::one());
+    assert_eq!(wad_to_motes_round_up(factor), U512::one());
+    assert_eq!(wad_to_motes_round_up(factor + U256::one()), U512::from(2u64));
+    assert_eq!(wad_to_motes_round_up(U256::MAX), wad_to_motes(U256::MAX) + U512::one());
+}
+
+/// Widen a U256 to U512 for overflow-free checks
+fn widen(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+#[test]
+fn test_wad_to_motes_truncation_across_magnitudes() {
+    let factor = U512::from(MOTES_TO_WAD_FACTOR);
+    let mut samples = vec![U256::MAX, U256::MAX - U256::one()];
+    for shift in 0..256 {
+        let power = U256::one() << shift;
+        samples.extend([power - U256::one(), power, power.saturating_add(U256::one())]);
+    }
+
+    for wad in samples {
+        let motes = wad_to_motes(wad);
+        let up = wad_to_motes_round_up(wad);
+        // motes * 1e9 <= wad < (motes + 1) * 1e9: at most one mote lost
+        assert!(motes * factor <= widen(wad));
+        assert!(widen(wad) < (motes + U512::one()) * factor);
+        let exact = (widen(wad) % factor).is_zero();
+        assert_eq!(up - motes, if exact { U512::zero() } else { U512::one() });
+    }
+}